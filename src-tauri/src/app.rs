@@ -1,10 +1,14 @@
 use crate::commands::*;
-use crate::logging::initialize_tracing;
+use crate::logging::{emit_system_error, initialize_tracing};
+use crate::remote::{RemoteApiServer, RemoteEventHub, DEFAULT_REMOTE_API_PORT};
 use crate::state::AppState;
 use crate::utils::spawn_serial_hotplug_watcher;
 use std::sync::Mutex;
 use std::time::Duration;
-use tauri::Manager;
+use tauri::{AppHandle, Manager};
+
+const REMOTE_API_TOKEN_ENV: &str = "SFTOOL_REMOTE_API_TOKEN";
+const REMOTE_API_PORT_ENV: &str = "SFTOOL_REMOTE_API_PORT";
 
 fn set_dynamic_updater_endpoint<R: tauri::Runtime>(context: &mut tauri::Context<R>) {
     const CN_ENDPOINT: &str = "https://downloads.sifli.com/sftool-gui/cn/latest.json";
@@ -35,6 +39,27 @@ fn set_dynamic_updater_endpoint<R: tauri::Runtime>(context: &mut tauri::Context<
     }
 }
 
+// 设置了令牌环境变量时自动启动本地量产控制接口，方便产线工控机开机即接入 MES
+fn start_remote_api_from_env(app_handle: &AppHandle) {
+    let Ok(token) = std::env::var(REMOTE_API_TOKEN_ENV) else {
+        return;
+    };
+
+    let port = std::env::var(REMOTE_API_PORT_ENV)
+        .ok()
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(DEFAULT_REMOTE_API_PORT);
+
+    match RemoteApiServer::start(app_handle.clone(), port, token) {
+        Ok(server) => {
+            if let Ok(mut app_state) = app_handle.state::<Mutex<AppState>>().lock() {
+                app_state.remote_api = Some(server);
+            }
+        }
+        Err(error) => emit_system_error(app_handle, format!("启动远程控制接口失败: {error}")),
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let mut context = tauri::generate_context!();
@@ -50,7 +75,9 @@ pub fn run() {
         .setup(|app| {
             initialize_tracing(app.handle().clone());
             app.manage(Mutex::new(AppState::default()));
+            app.manage(RemoteEventHub::default());
             spawn_serial_hotplug_watcher(app.handle().clone());
+            start_remote_api_from_env(app.handle());
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -58,12 +85,29 @@ pub fn run() {
             connect_device,
             disconnect_device,
             parse_sftool_param_file,
+            export_sftool_param_file,
             validate_firmware_file,
+            inspect_firmware_file,
+            hash_firmware_files,
+            merge_images,
+            compare_flash_dump,
+            load_partition_table,
+            resolve_partition,
+            check_partition_fit,
             extract_archive,
+            build_firmware_package,
+            inspect_firmware_package,
+            import_firmware_package,
+            create_package_signing_key,
+            get_package_trusted_keys,
+            set_package_trusted_keys,
             write_flash,
             read_flash,
+            verify_flash,
+            peek_flash,
             erase_flash,
             erase_region,
+            cancel_current_operation,
             set_speed,
             soft_reset,
             mass_production_start,
@@ -73,7 +117,15 @@ pub fn run() {
             mass_production_get_snapshot,
             mass_production_get_log_paths,
             mass_production_open_port_log,
-            mass_production_open_log_directory
+            mass_production_open_log_directory,
+            serial_pool_import,
+            serial_pool_get_status,
+            serial_pool_list,
+            trace_query_records,
+            trace_export_records,
+            remote_api_start,
+            remote_api_stop,
+            remote_api_get_status
         ])
         .build(context)
         .expect("error while building tauri application");
@@ -1,112 +1,108 @@
+use crate::commands::{
+    load_sftool_param_file, resolve_package_dir, resolve_sftool_param_config, trusted_package_keys,
+};
 use crate::state::AppState;
-use crate::types::ExtractedFile;
-use std::fs;
-use std::path::{Path, PathBuf};
+use crate::types::{
+    ArchiveExtractError, ArchiveExtractResult, ArchiveLimits, ArchiveRecipeSource, ExtractedFile,
+    SftoolParamParseResult,
+};
+use crate::utils::{
+    collect_extracted_files, detect_archive_recipe, extract_archive_to_temp_dir, sdk_build_config,
+    DetectedRecipe,
+};
+use std::path::Path;
 use std::sync::Mutex;
-use tauri::State;
-use tempfile::tempdir;
-use unarc_rs::unified::ArchiveFormat;
-use walkdir::WalkDir;
+use tauri::{AppHandle, State};
 
 #[tauri::command]
 pub async fn extract_archive(
+    app_handle: AppHandle,
     state: State<'_, Mutex<AppState>>,
     archive_path: String,
-) -> Result<Vec<ExtractedFile>, String> {
-    let path = Path::new(&archive_path);
-    if !path.exists() {
-        return Err(format!("文件不存在: {}", archive_path));
-    }
-
-    // 创建临时目录，并转出 TempDir 的自动删除控制权，让目录在应用关闭前保留
-    let dir = tempdir().map_err(|e| format!("无法创建临时目录: {}", e))?;
-    let dest_path: PathBuf = dir.into_path();
-
-    // 使用 unarc-rs 解压，支持多种格式 (zip, tar, tar.gz, tar.bz2, rar, 7z 等)
-    let mut archive =
-        ArchiveFormat::open_path(&archive_path).map_err(|e| format!("打开压缩文件失败: {}", e))?;
-
-    // 遍历压缩文件中的所有条目
-    let extract_result = (|| -> Result<(), String> {
-        while let Some(entry) = archive
-            .next_entry()
-            .map_err(|e| format!("读取压缩文件条目失败: {}", e))?
-        {
-            let entry_name = entry.name();
-            let outpath = dest_path.join(entry_name);
-
-            // 判断是否是目录（目录名以 / 结尾）
-            if entry_name.ends_with('/') {
-                // 创建目录
-                fs::create_dir_all(&outpath).map_err(|e| format!("创建目录失败: {}", e))?;
-            } else {
-                // 创建父目录
-                if let Some(parent) = outpath.parent() {
-                    if !parent.exists() {
-                        fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
-                    }
-                }
-
-                // 如果输出路径已经存在且是目录，跳过
-                if outpath.exists() && outpath.is_dir() {
-                    println!("跳过目录路径: {:?}", outpath);
-                    continue;
-                }
-
-                // 提取文件
-                println!("Extracting file to: {:?} ... ", outpath);
-                let mut outfile = fs::File::create(&outpath)
-                    .map_err(|e| format!("{}: 创建文件失败: {}", entry_name, e))?;
-                archive
-                    .read_to(&entry, &mut outfile)
-                    .map_err(|e| format!("解压文件失败: {}", e))?;
-            }
-        }
-        Ok(())
-    })();
-
-    // 处理提取结果
-    extract_result?;
+    limits: Option<ArchiveLimits>,
+    current_chip: Option<String>,
+    current_memory: Option<String>,
+) -> Result<ArchiveExtractResult, ArchiveExtractError> {
+    let dest_path = extract_archive_to_temp_dir(&archive_path, limits.unwrap_or_default())?;
 
     // 将临时目录注册到 AppState，以便在应用退出时统一清理
     {
         let mut app_state = state
             .lock()
-            .map_err(|e| format!("获取应用状态失败: {}", e))?;
+            .map_err(|e| ArchiveExtractError::io(None, format!("获取应用状态失败: {}", e)))?;
         app_state.register_temp_dir(dest_path.clone());
     }
 
-    // 遍历临时目录，收集文件信息
-    let mut extracted = Vec::new();
-    for entry in WalkDir::new(&dest_path).into_iter() {
-        match entry {
-            Ok(entry) => {
-                let p = entry.path();
-                if p.is_file() {
-                    if let Ok(meta) = fs::metadata(p) {
-                        let name = p
-                            .file_name()
-                            .and_then(|n| n.to_str())
-                            .unwrap_or("")
-                            .to_string();
-                        extracted.push(ExtractedFile {
-                            path: p.to_string_lossy().to_string(),
-                            address: "".to_string(),
-                            name,
-                            size: meta.len(),
-                        });
-                    }
-                }
-            }
-            Err(err) => {
-                eprintln!(
-                    "遍历临时目录 '{}' 时出错: {}",
-                    dest_path.to_string_lossy(),
-                    err
-                );
+    let mut result = ArchiveExtractResult {
+        files: collect_extracted_files(&dest_path),
+        recipe_source: None,
+        recipe_path: None,
+        recipe: None,
+        recipe_error: None,
+    };
+
+    // 识别压缩包中的烧录配置；解析失败不影响解压结果，由前端提示
+    if let Some(detected) = detect_archive_recipe(&dest_path) {
+        result.recipe_source = Some(detected.source);
+        result.recipe_path = Some(detected.path.to_string_lossy().to_string());
+        match resolve_recipe(&app_handle, &detected, current_chip, current_memory) {
+            Ok(recipe) => {
+                apply_recipe_addresses(&mut result.files, &recipe);
+                result.recipe = Some(recipe);
             }
+            Err(e) => result.recipe_error = Some(e),
+        }
+    }
+
+    Ok(result)
+}
+
+fn resolve_recipe(
+    app_handle: &AppHandle,
+    detected: &DetectedRecipe,
+    current_chip: Option<String>,
+    current_memory: Option<String>,
+) -> Result<SftoolParamParseResult, String> {
+    match detected.source {
+        ArchiveRecipeSource::SftoolParam => load_sftool_param_file(
+            &detected.path.to_string_lossy(),
+            current_chip,
+            current_memory,
+        ),
+        ArchiveRecipeSource::PackageManifest => {
+            let package_dir = detected.path.parent().ok_or("无法获取固件包清单所在目录")?;
+            let trusted_keys = trusted_package_keys(app_handle)?;
+            resolve_package_dir(package_dir, &trusted_keys, current_chip, current_memory)
         }
+        ArchiveRecipeSource::SdkBuild => {
+            let config = sdk_build_config(&detected.path, current_chip.as_deref())?;
+            resolve_sftool_param_config(config, &detected.path, current_chip, current_memory)
+        }
+    }
+}
+
+fn same_file(a: &str, b: &str) -> bool {
+    match (Path::new(a).canonicalize(), Path::new(b).canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
     }
+}
 
-    Ok(extracted)
+/// 将配置中显式指定的地址填回解压文件列表，ELF/HEX 等未指定地址的保持为空
+fn apply_recipe_addresses(files: &mut [ExtractedFile], recipe: &SftoolParamParseResult) {
+    let Some(write_flash) = &recipe.config.write_flash else {
+        return;
+    };
+
+    for (param_file, resolved) in write_flash.files.iter().zip(&recipe.extracted_files) {
+        if param_file.address.is_none() {
+            continue;
+        }
+        if let Some(file) = files
+            .iter_mut()
+            .find(|file| same_file(&file.path, &resolved.path))
+        {
+            file.address = resolved.address.clone();
+        }
+    }
 }
@@ -1,49 +1,86 @@
-use crate::types::{ExtractedFile, SftoolParamConfig, SftoolParamParseResult};
-use crate::utils::validate_config_with_device;
+use crate::types::{
+    ExtractedFile, ExtractedReadFile, FirmwareFormat, SftoolParamConfig, SftoolParamExportSource,
+    SftoolParamParseResult,
+};
+use crate::utils::{
+    build_sftool_param_config, detect_firmware_file_format, validate_config_with_device,
+};
 use std::path::Path;
 
+/// 配置文件中的 BIN 文件未写地址时沿用的默认写入地址
+const DEFAULT_BIN_ADDRESS: &str = "0x10000000";
+
 #[tauri::command]
 pub async fn parse_sftool_param_file(
     config_file_path: String,
     current_chip: Option<String>,
     current_memory: Option<String>,
+) -> Result<SftoolParamParseResult, String> {
+    load_sftool_param_file(&config_file_path, current_chip, current_memory)
+}
+
+/// 读取并解析 sftool_param.json，相对路径按配置文件所在目录解析
+pub fn load_sftool_param_file(
+    config_file_path: &str,
+    current_chip: Option<String>,
+    current_memory: Option<String>,
 ) -> Result<SftoolParamParseResult, String> {
     // 读取配置文件
-    let config_content = std::fs::read_to_string(&config_file_path)
+    let config_content = std::fs::read_to_string(config_file_path)
         .map_err(|e| format!("无法读取配置文件 {}: {}", config_file_path, e))?;
 
     // 解析 JSON
     let config: SftoolParamConfig = serde_json::from_str(&config_content)
         .map_err(|e| format!("解析配置文件 JSON 失败: {}", e))?;
 
-    // 验证基本结构
-    let write_flash = config
-        .write_flash
-        .as_ref()
-        .ok_or("配置文件缺少 write_flash 字段")?;
-
-    if write_flash.files.is_empty() {
-        return Err("配置文件中 write_flash.files 字段为空".to_string());
-    }
-
     // 获取配置文件所在目录
-    let config_dir = Path::new(&config_file_path)
+    let config_dir = Path::new(config_file_path)
         .parent()
         .ok_or("无法获取配置文件所在目录")?;
 
+    resolve_sftool_param_config(config, config_dir, current_chip, current_memory)
+}
+
+fn resolve_config_path(config_dir: &Path, path: &str) -> String {
+    if Path::new(path).is_absolute() {
+        path.to_string()
+    } else {
+        config_dir.join(path).to_string_lossy().to_string()
+    }
+}
+
+/// 校验配置并将其中的相对路径按 config_dir 解析为实际文件
+pub fn resolve_sftool_param_config(
+    config: SftoolParamConfig,
+    config_dir: &Path,
+    current_chip: Option<String>,
+    current_memory: Option<String>,
+) -> Result<SftoolParamParseResult, String> {
+    // 验证基本结构
+    if !config.has_any_command() {
+        return Err(
+            "配置文件缺少 write_flash / read_flash / erase_flash / erase_region 字段".to_string(),
+        );
+    }
+
+    if let Some(write_flash) = &config.write_flash {
+        if write_flash.files.is_empty() {
+            return Err("配置文件中 write_flash.files 字段为空".to_string());
+        }
+    }
+
     // 提取和验证文件
     let mut extracted_files = Vec::new();
+    let mut warnings = Vec::new();
 
-    for file_config in &write_flash.files {
+    let write_files = config
+        .write_flash
+        .as_ref()
+        .map(|write_flash| write_flash.files.as_slice())
+        .unwrap_or_default();
+    for file_config in write_files {
         // 解析文件路径（可能是相对路径）
-        let file_path = if Path::new(&file_config.path).is_absolute() {
-            file_config.path.clone()
-        } else {
-            config_dir
-                .join(&file_config.path)
-                .to_string_lossy()
-                .to_string()
-        };
+        let file_path = resolve_config_path(config_dir, &file_config.path);
 
         // 检查文件是否存在
         let file_metadata = std::fs::metadata(&file_path)
@@ -56,11 +93,20 @@ pub async fn parse_sftool_param_file(
             .unwrap_or(&file_config.path)
             .to_string();
 
-        // 处理地址
-        let address = file_config
-            .address
-            .clone()
-            .unwrap_or_else(|| "0x10000000".to_string());
+        // 未写地址时 ELF/HEX 由文件自身决定；BIN 文件没有地址信息，沿用默认地址并提示确认
+        let address = match &file_config.address {
+            Some(address) => address.clone(),
+            None if detect_firmware_file_format(&file_path)? != FirmwareFormat::Bin => {
+                String::new()
+            }
+            None => {
+                warnings.push(format!(
+                    "BIN 文件 {} 未指定写入地址，已使用默认地址 {}，请确认",
+                    file_config.path, DEFAULT_BIN_ADDRESS
+                ));
+                DEFAULT_BIN_ADDRESS.to_string()
+            }
+        };
 
         extracted_files.push(ExtractedFile {
             path: file_path,
@@ -70,13 +116,33 @@ pub async fn parse_sftool_param_file(
         });
     }
 
+    // 读取任务的输出文件此时还不存在，只解析路径
+    let read_files = config
+        .read_flash
+        .as_ref()
+        .map(|read_flash| {
+            read_flash
+                .files
+                .iter()
+                .map(|file| ExtractedReadFile {
+                    path: resolve_config_path(config_dir, &file.path),
+                    address: file.address.clone(),
+                    size: file.size.clone(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
     // 验证配置与当前设备设置的兼容性
-    let validation = validate_config_with_device(&config, current_chip, current_memory);
+    let mut validation = validate_config_with_device(&config, current_chip, current_memory);
+    validation.warnings.extend(warnings);
 
     Ok(SftoolParamParseResult {
         config,
         validation,
         extracted_files,
+        read_files,
+        stub_config_path: None,
     })
 }
 
@@ -105,3 +171,26 @@ pub async fn validate_firmware_file(file_path: String) -> Result<bool, String> {
     // 这里可以添加更多的文件格式验证逻辑
     Ok(true)
 }
+
+/// 将当前写入配置导出为 sftool_param.json，供命令行 sftool 使用
+#[tauri::command]
+pub async fn export_sftool_param_file(
+    output_path: String,
+    source: SftoolParamExportSource,
+) -> Result<SftoolParamConfig, String> {
+    let output_dir = Path::new(&output_path)
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    if !output_dir.is_dir() {
+        return Err(format!("输出目录不存在: {}", output_dir.to_string_lossy()));
+    }
+
+    let config = build_sftool_param_config(&source, output_dir)?;
+    let content =
+        serde_json::to_string_pretty(&config).map_err(|e| format!("序列化配置文件失败: {}", e))?;
+    std::fs::write(&output_path, content)
+        .map_err(|e| format!("写入配置文件 {} 失败: {}", output_path, e))?;
+
+    Ok(config)
+}
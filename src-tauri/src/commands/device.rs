@@ -1,12 +1,13 @@
+use crate::commands::{run_device_operation, OperationGuard};
 use crate::logging::emit_system_error;
 use crate::progress::TauriProgressCallback;
 use crate::state::AppState;
 use crate::types::{DeviceConfig, PortInfo};
-use crate::utils::{create_tool_instance_with_progress, list_serial_ports};
+use crate::utils::{create_tool_instance_with_progress, list_serial_ports, refine_flash_error};
 use sftool_lib::progress::ProgressSinkArc;
 use sftool_lib::CancelToken;
 use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, State};
+use tauri::{AppHandle, Manager, State};
 
 #[tauri::command]
 pub fn get_serial_ports() -> Result<Vec<PortInfo>, String> {
@@ -40,69 +41,82 @@ pub async fn connect_device(
     };
 
     // 创建 Tauri 进度回调
-    let progress_callback: ProgressSinkArc =
-        Arc::new(TauriProgressCallback::new(app_handle.clone()));
+    let progress = Arc::new(TauriProgressCallback::new(app_handle.clone()));
+    let progress_callback: ProgressSinkArc = progress.clone();
 
-    // 创建带进度回调的工具实例
-    let tool = match create_tool_instance_with_progress(
-        &device_config,
-        progress_callback,
-        CancelToken::new(),
-    ) {
-        Ok(tool) => tool,
-        Err(error) => {
-            emit_system_error(&app_handle, format!("连接失败: {error}"));
-            return Err(error);
-        }
-    };
+    // 连接与其它单设备操作互斥；旧连接即将被替换，先断开，
+    // 新的取消令牌保存到状态中，cancel_current_operation 可以中止连接过程
+    let cancel_token = CancelToken::new();
+    {
+        let mut app_state = state.lock().unwrap();
+        app_state.mark_running("连接")?;
+        app_state.clear_device_connection();
+        app_state.cancel_token = Some(cancel_token.clone());
+    }
 
-    // 保存设备配置和工具实例到状态
-    let mut app_state = state.lock().unwrap();
-    app_state.device_config = Some(device_config);
-    app_state.sftool = Some(Arc::new(Mutex::new(tool)));
+    // 创建工具实例会同步串口并下载 stub，放到阻塞线程中执行
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let _busy = OperationGuard(handle.clone());
+        let result = create_tool_instance_with_progress(
+            &device_config,
+            progress_callback,
+            cancel_token.clone(),
+        )
+        .map_err(|error| {
+            refine_flash_error(
+                error.or_stage(progress.failure_stage()),
+                cancel_token.is_cancelled(),
+                &device_config.port_name,
+            )
+        });
 
-    Ok(true)
+        // 在忙碌状态清除之前保存连接，避免其它操作看到半完成的状态
+        let state = handle.state::<Mutex<AppState>>();
+        let mut app_state = state.lock().unwrap();
+        match result {
+            Ok(tool) => {
+                app_state.device_config = Some(device_config);
+                app_state.sftool = Some(Arc::new(Mutex::new(tool)));
+                app_state.progress_callback = Some(progress);
+                Ok(true)
+            }
+            Err(error) => {
+                app_state.clear_device_connection();
+                drop(app_state);
+                emit_system_error(&handle, format!("连接失败: {error}"));
+                Err(error.into())
+            }
+        }
+    })
+    .await
+    .map_err(|e| format!("连接线程异常退出: {}", e))?
 }
 
 #[tauri::command]
 pub fn disconnect_device(state: State<'_, Mutex<AppState>>) -> Result<(), String> {
     let mut app_state = state.lock().unwrap();
+    app_state.ensure_idle()?;
     app_state.clear_device_connection();
     Ok(())
 }
 
 #[tauri::command]
-pub async fn set_speed(state: State<'_, Mutex<AppState>>, baud_rate: u32) -> Result<(), String> {
-    let sftool = {
-        let app_state = state.lock().unwrap();
-        app_state
-            .sftool
-            .as_ref()
-            .ok_or("设备未连接，请先连接设备")?
-            .clone()
-    };
-
-    let mut tool = sftool.lock().unwrap();
-    tool.set_speed(baud_rate)
-        .map_err(|e| format!("设置速度失败: {}", e))?;
-
-    Ok(())
+pub async fn set_speed(app_handle: AppHandle, baud_rate: u32) -> Result<(), String> {
+    run_device_operation(&app_handle, "设置速度", move |tool, _| {
+        tool.set_speed(baud_rate)
+            .map_err(|e| format!("设置速度失败: {}", e).into())
+    })
+    .await
+    .map_err(String::from)
 }
 
 #[tauri::command]
-pub async fn soft_reset(state: State<'_, Mutex<AppState>>) -> Result<(), String> {
-    let sftool = {
-        let app_state = state.lock().unwrap();
-        app_state
-            .sftool
-            .as_ref()
-            .ok_or("设备未连接，请先连接设备")?
-            .clone()
-    };
-
-    let mut tool = sftool.lock().unwrap();
-    tool.soft_reset()
-        .map_err(|e| format!("软重置失败: {}", e))?;
-
-    Ok(())
+pub async fn soft_reset(app_handle: AppHandle) -> Result<(), String> {
+    run_device_operation(&app_handle, "软重置", |tool, _| {
+        tool.soft_reset()
+            .map_err(|e| format!("软重置失败: {}", e).into())
+    })
+    .await
+    .map_err(String::from)
 }
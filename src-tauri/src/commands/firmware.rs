@@ -0,0 +1,41 @@
+use crate::types::{
+    CompareFlashDumpRequest, FileHash, FirmwareImageInfo, FlashDumpComparison, MergeImagesRequest,
+    MergeImagesResult, WriteFlashFileInfo,
+};
+use crate::utils::{compare_flash_dump_files, hash_and_check, inspect_firmware, merge_image_files};
+
+/// 解析固件文件实际落到 Flash 上的地址段；BIN 文件使用传入的地址作为起始地址
+#[tauri::command]
+pub async fn inspect_firmware_file(
+    file_path: String,
+    address: Option<u32>,
+) -> Result<FirmwareImageInfo, String> {
+    if !std::path::Path::new(&file_path).exists() {
+        return Err(format!("文件不存在: {}", file_path));
+    }
+
+    inspect_firmware(&file_path, address.unwrap_or(0))
+}
+
+/// 计算写入列表中每个文件的 CRC32、SHA-256 与大小；给出期望摘要时一并比较
+#[tauri::command]
+pub async fn hash_firmware_files(files: Vec<WriteFlashFileInfo>) -> Result<Vec<FileHash>, String> {
+    files
+        .iter()
+        .map(|file| hash_and_check(&file.file_path, file.expected_hash.as_deref()))
+        .collect()
+}
+
+/// 将写入列表合并为从起始地址开始的单个 BIN，空隙填充 0xFF，可选同时输出 Intel HEX
+#[tauri::command]
+pub async fn merge_images(request: MergeImagesRequest) -> Result<MergeImagesResult, String> {
+    merge_image_files(&request)
+}
+
+/// 对比读出文件与源镜像，报告一致、不一致及未覆盖的地址范围
+#[tauri::command]
+pub async fn compare_flash_dump(
+    request: CompareFlashDumpRequest,
+) -> Result<FlashDumpComparison, String> {
+    compare_flash_dump_files(&request)
+}
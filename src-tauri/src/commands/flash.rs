@@ -1,140 +1,275 @@
 use crate::state::AppState;
-use crate::types::{ReadFlashRequest, WriteFlashRequest};
-use sftool_lib::{
-    utils::Utils, EraseFlashParams, EraseRegionFile, EraseRegionParams, ReadFlashFile,
-    ReadFlashParams, WriteFlashParams,
+use crate::types::{
+    DeviceConfig, EraseRegionInfo, FlashError, FlashErrorCategory, OperationError, PeekFlashResult,
+    ReadFlashRequest, VerifyFlashFileResult, WriteFlashFileInfo, WriteFlashRequest,
+    PEEK_FLASH_MAX_SIZE,
 };
-use std::sync::Mutex;
-use tauri::State;
+use crate::utils::{
+    classify_write_error, configured_flash_size, erase_granularity, read_flash_window,
+    refine_flash_error, validate_erase_regions, validate_write_plan, verify_device_flash,
+    verify_expected_hashes, FlashBounds,
+};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use sftool_lib::{CancelToken, EraseFlashParams, EraseRegionFile, EraseRegionParams, SifliTool};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager, State};
 
-#[tauri::command]
-pub async fn write_flash(
-    state: State<'_, Mutex<AppState>>,
-    request: WriteFlashRequest,
-) -> Result<(), String> {
-    let sftool = {
+type SharedTool = Arc<Mutex<Box<dyn SifliTool>>>;
+
+/// 区分用户取消与普通失败；取消后工具实例内的令牌已触发，需断开并重新连接
+fn operation_error(
+    app_handle: &AppHandle,
+    sftool: &SharedTool,
+    error: FlashError,
+) -> OperationError {
+    let state = app_handle.state::<Mutex<AppState>>();
+    let (cancelled, port_name) = {
         let app_state = state.lock().unwrap();
-        app_state
-            .sftool
-            .as_ref()
-            .ok_or("设备未连接，请先连接设备")?
-            .clone()
+        (
+            app_state
+                .cancel_token
+                .as_ref()
+                .is_some_and(CancelToken::is_cancelled),
+            app_state
+                .device_config
+                .as_ref()
+                .map(|config| config.port_name.clone())
+                .unwrap_or_default(),
+        )
     };
+    let error = refine_flash_error(error, cancelled, &port_name);
+    if error.category != FlashErrorCategory::Cancelled {
+        return error.into();
+    }
 
-    // 准备写入文件参数
-    let mut files = Vec::new();
-    for file_info in request.files {
-        // 检查文件是否存在
-        if !std::path::Path::new(&file_info.file_path).exists() {
-            return Err(format!("文件不存在: {}", file_info.file_path));
-        }
-
-        // 根据文件类型和地址构造文件字符串
-        let file_string = if file_info.address == 0 {
-            // 对于地址为 0 的情况，可能是 ELF/HEX 文件，让 parse_file_info 自动检测
-            file_info.file_path.clone()
-        } else {
-            // 对于指定地址的情况，使用 file@address 格式
-            format!("{}@0x{:08X}", file_info.file_path, file_info.address)
-        };
+    let mut app_state = state.lock().unwrap();
+    if app_state
+        .sftool
+        .as_ref()
+        .is_some_and(|current| Arc::ptr_eq(current, sftool))
+    {
+        app_state.clear_device_connection();
+    }
+    OperationError::cancelled()
+}
 
-        // 使用 Utils::parse_file_info 解析文件
-        let parsed_files = Utils::parse_file_info(&file_string)
-            .map_err(|e| format!("解析文件 {} 失败: {}", file_info.file_path, e))?;
+/// 操作线程结束（包括 panic）时清除忙碌状态
+pub(crate) struct OperationGuard(pub(crate) AppHandle);
 
-        files.extend(parsed_files);
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        let state = self.0.state::<Mutex<AppState>>();
+        if let Ok(mut app_state) = state.lock() {
+            app_state.finish_operation();
+        }
     }
+}
 
-    let params = WriteFlashParams {
-        files,
-        verify: request.verify,
-        no_compress: request.no_compress,
-        erase_all: request.erase_all,
+/// 在独立的阻塞线程中执行单设备操作，避免占用异步运行时；
+/// 同一时间只允许一个操作，其余请求立即返回 busy 错误
+pub(crate) async fn run_device_operation<T, F>(
+    app_handle: &AppHandle,
+    name: &'static str,
+    operation: F,
+) -> Result<T, OperationError>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut dyn SifliTool, Option<DeviceConfig>) -> Result<T, FlashError> + Send + 'static,
+{
+    let (sftool, device_config) = {
+        let state = app_handle.state::<Mutex<AppState>>();
+        let mut app_state = state.lock().unwrap();
+        let sftool = app_state.begin_operation(name)?;
+        (sftool, app_state.device_config.clone())
     };
 
-    let mut tool = sftool.lock().unwrap();
-    tool.write_flash(&params)
-        .map_err(|e| format!("写入 Flash 失败: {}", e))?;
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let _busy = OperationGuard(handle.clone());
+        let mut tool = sftool.lock().unwrap();
+        operation(tool.as_mut(), device_config).map_err(|e| operation_error(&handle, &sftool, e))
+    })
+    .await
+    .map_err(|e| OperationError::failed(format!("{}线程异常退出: {}", name, e)))?
+}
+
+/// 触发当前连接的取消令牌，中止正在进行的写入/读取/擦除
+#[tauri::command]
+pub fn cancel_current_operation(state: State<'_, Mutex<AppState>>) -> Result<(), String> {
+    let app_state = state.lock().unwrap();
+
+    // 没有进行中的操作时触发令牌会让之后的操作全部失败
+    if app_state.running_operation.is_none() {
+        return Err("当前没有正在进行的操作".to_string());
+    }
+    if let Some(token) = &app_state.cancel_token {
+        token.cancel();
+    }
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn read_flash(
-    state: State<'_, Mutex<AppState>>,
-    request: ReadFlashRequest,
-) -> Result<(), String> {
-    let sftool = {
-        let app_state = state.lock().unwrap();
-        app_state
-            .sftool
-            .as_ref()
-            .ok_or("设备未连接，请先连接设备")?
-            .clone()
-    };
+pub async fn write_flash(
+    app_handle: AppHandle,
+    request: WriteFlashRequest,
+) -> Result<(), OperationError> {
+    let progress = app_handle
+        .state::<Mutex<AppState>>()
+        .lock()
+        .unwrap()
+        .progress_callback
+        .clone();
 
-    // 准备读取文件参数
-    let files = request
-        .files
-        .into_iter()
-        .map(|file_info| ReadFlashFile {
-            file_path: file_info.file_path,
-            address: file_info.address,
-            size: file_info.size,
+    run_device_operation(&app_handle, "写入 Flash", move |tool, device_config| {
+        // 准备写入文件参数
+        let params = request.to_write_flash_params()?;
+
+        // 校验文件摘要，确保写入的是指定版本的固件
+        verify_expected_hashes(
+            request
+                .files
+                .iter()
+                .map(|file| (file.file_path.as_str(), file.expected_hash.as_deref())),
+        )?;
+
+        // 检查文件间地址重叠及 Flash 地址范围
+        if let Some(config) = &device_config {
+            validate_write_plan(
+                request
+                    .files
+                    .iter()
+                    .map(|file| (file.file_path.as_str(), file.address)),
+                &config.chip_type,
+                &config.memory_type,
+                FlashBounds {
+                    flash_size: configured_flash_size(
+                        &config.stub_config_path,
+                        &config.memory_type,
+                    )?,
+                    skip_check: false,
+                },
+            )?;
+        }
+
+        if let Some(progress) = &progress {
+            progress.reset_failure_stage();
+        }
+        tool.write_flash(&params).map_err(|e| {
+            classify_write_error(
+                progress
+                    .as_ref()
+                    .and_then(|progress| progress.failure_stage()),
+                format!("写入 Flash 失败: {}", e),
+            )
         })
-        .collect();
+    })
+    .await
+}
 
-    let params = ReadFlashParams { files };
+/// 只回读文件覆盖的地址范围并与文件内容比较，不写入设备
+#[tauri::command]
+pub async fn verify_flash(
+    app_handle: AppHandle,
+    files: Vec<WriteFlashFileInfo>,
+) -> Result<Vec<VerifyFlashFileResult>, OperationError> {
+    let emitter = app_handle.clone();
+    run_device_operation(&app_handle, "校验 Flash", move |tool, _| {
+        Ok(verify_device_flash(tool, &files, |event| {
+            if let Err(e) = emitter.emit("flash-progress", &event) {
+                eprintln!("Failed to emit progress event: {}", e);
+            }
+        })?)
+    })
+    .await
+}
 
-    let mut tool = sftool.lock().unwrap();
-    tool.read_flash(&params)
-        .map_err(|e| format!("读取 Flash 失败: {}", e))?;
+#[tauri::command]
+pub async fn read_flash(
+    app_handle: AppHandle,
+    request: ReadFlashRequest,
+) -> Result<(), OperationError> {
+    run_device_operation(&app_handle, "读取 Flash", move |tool, _| {
+        // 准备读取文件参数
+        let params = request.to_read_flash_params();
 
-    Ok(())
+        tool.read_flash(&params)
+            .map_err(|e| format!("读取 Flash 失败: {}", e).into())
+    })
+    .await
 }
 
+/// 读取不超过 64 KB 的一段 Flash 到内存，供前端以十六进制显示
 #[tauri::command]
-pub async fn erase_flash(state: State<'_, Mutex<AppState>>, address: u32) -> Result<(), String> {
-    let sftool = {
-        let app_state = state.lock().unwrap();
-        app_state
-            .sftool
-            .as_ref()
-            .ok_or("设备未连接，请先连接设备")?
-            .clone()
-    };
+pub async fn peek_flash(
+    app_handle: AppHandle,
+    address: u32,
+    size: u32,
+) -> Result<PeekFlashResult, OperationError> {
+    if size == 0 || size > PEEK_FLASH_MAX_SIZE {
+        return Err(format!(
+            "读取长度应在 1 到 {} 字节之间，当前为 {}",
+            PEEK_FLASH_MAX_SIZE, size
+        )
+        .into());
+    }
+    if address.checked_add(size - 1).is_none() {
+        return Err(format!("读取范围 0x{:08X} + 0x{:X} 超出地址空间", address, size).into());
+    }
 
-    let params = EraseFlashParams { address };
+    run_device_operation(&app_handle, "读取 Flash", move |tool, _| {
+        let data = read_flash_window(tool, address, size)?;
+        Ok(PeekFlashResult {
+            address,
+            size: data.len() as u32,
+            data: BASE64.encode(data),
+        })
+    })
+    .await
+}
 
-    let mut tool = sftool.lock().unwrap();
-    tool.erase_flash(&params)
-        .map_err(|e| format!("擦除 Flash 失败: {}", e))?;
+#[tauri::command]
+pub async fn erase_flash(app_handle: AppHandle, address: u32) -> Result<(), OperationError> {
+    run_device_operation(&app_handle, "擦除 Flash", move |tool, _| {
+        let params = EraseFlashParams { address };
 
-    Ok(())
+        tool.erase_flash(&params).map_err(|e| {
+            FlashError::new(FlashErrorCategory::Erase, format!("擦除 Flash 失败: {}", e))
+        })
+    })
+    .await
 }
 
 #[tauri::command]
 pub async fn erase_region(
-    state: State<'_, Mutex<AppState>>,
-    address: u32,
-    size: u32,
-) -> Result<(), String> {
-    let sftool = {
-        let app_state = state.lock().unwrap();
-        app_state
-            .sftool
+    app_handle: AppHandle,
+    regions: Vec<EraseRegionInfo>,
+) -> Result<(), OperationError> {
+    run_device_operation(&app_handle, "擦除区域", move |tool, device_config| {
+        let memory_type = device_config
             .as_ref()
-            .ok_or("设备未连接，请先连接设备")?
-            .clone()
-    };
+            .map(|config| config.memory_type.as_str())
+            .unwrap_or_default();
+        validate_erase_regions(
+            regions.iter().map(|region| (region.address, region.size)),
+            erase_granularity(memory_type),
+        )
+        .map_err(|e| FlashError::new(FlashErrorCategory::Config, e))?;
 
-    let params = EraseRegionParams {
-        regions: vec![EraseRegionFile { address, size }],
-    };
-
-    let mut tool = sftool.lock().unwrap();
-    tool.erase_region(&params)
-        .map_err(|e| format!("擦除区域失败: {}", e))?;
+        // 所有区域合并为一次 erase_region 调用，只产生一个进度会话
+        let params = EraseRegionParams {
+            regions: regions
+                .iter()
+                .map(|region| EraseRegionFile {
+                    address: region.address,
+                    size: region.size,
+                })
+                .collect(),
+        };
 
-    Ok(())
+        tool.erase_region(&params)
+            .map_err(|e| FlashError::new(FlashErrorCategory::Erase, format!("擦除区域失败: {}", e)))
+    })
+    .await
 }
@@ -1,15 +1,28 @@
 use crate::logging::{emit_app_log, AppLogEntry};
-use crate::state::{AppState, MassProductionState, PortIdentity};
+use crate::progress::total_from_progress_type;
+use crate::remote::RemoteEventHub;
+use crate::state::{AppState, MassProductionState, PortIdentity, RetryHold};
 use crate::types::{
-    DeviceConfig, MassProductionLogPaths, MassProductionPortInfo, MassProductionPortStatus,
-    MassProductionProgressEvent, MassProductionSnapshot, MassProductionStartRequest,
+    parse_write_file, DeviceConfig, FlashError, FlashErrorCategory, MassProductionLogPaths,
+    MassProductionMode, MassProductionPortInfo, MassProductionPortStatus,
+    MassProductionProgressEvent, MassProductionRetryPolicy, MassProductionSnapshot,
+    MassProductionStartRequest, SerialFailurePolicy, SerialPoolImportRequest, SerialPoolStatus,
     TauriProgressContext, TauriProgressEvent, TauriProgressOperation, TauriProgressStatus,
-    TauriProgressType,
+    TauriProgressType, TraceExportFormat, TraceFirmwareFile, TraceQuery, TraceQueryResult,
+    TraceRecord, TraceResult, WriteFlashFileInfo,
+};
+use crate::utils::{
+    append_trace_record, classify_write_error, configured_flash_size,
+    create_tool_instance_with_progress, describe_verify_failures, export_trace_records,
+    freeze_firmware_files, import_serial_pool, list_serial_pools, list_serial_ports,
+    load_serial_pool_status, query_trace_records, refine_flash_error, serial_pool_dir,
+    trace_records_path, unit_data_write_file, validate_write_plan, verify_device_flash,
+    verify_expected_hashes, FlashBounds, FrozenFirmware, SerialPool, SerialPoolWrite,
+    UnitDataRenderer, UnitIndexAllocator,
 };
-use crate::utils::{create_tool_instance_with_progress, list_serial_ports};
 use chrono::{Local, TimeZone};
 use sftool_lib::progress::{ProgressEvent, ProgressSink, ProgressSinkArc};
-use sftool_lib::{utils::Utils, CancelToken, WriteFlashParams};
+use sftool_lib::{CancelToken, WriteFlashParams};
 use std::any::Any;
 use std::backtrace::Backtrace;
 use std::collections::{HashMap, HashSet};
@@ -24,44 +37,113 @@ use tauri_plugin_opener::OpenerExt;
 
 const SCAN_INTERVAL_MS: u64 = 800;
 
+/// 量产流程的宿主：负责对外发送快照/进度/日志事件并提供日志目录。
+///
+/// GUI 模式下由 AppHandle 实现，headless 模式下由终端输出实现。
+pub trait MassProductionHost: Clone + Send + Sync + 'static {
+    fn emit_snapshot(&self, snapshot: &MassProductionSnapshot);
+    fn emit_progress(&self, payload: &MassProductionProgressEvent);
+    fn emit_log(&self, entry: AppLogEntry);
+    fn config_dir(&self) -> Result<PathBuf, String>;
+    /// 序列号池和追溯记录所在目录
+    fn data_dir(&self) -> Result<PathBuf, String>;
+
+    /// 量产运行日志目录，默认位于数据目录下
+    fn runtime_log_dir(&self) -> Result<PathBuf, String> {
+        self.data_dir()
+            .map(|dir| dir.join(MASS_PRODUCTION_RUNTIME_LOG_DIRNAME))
+    }
+}
+
+impl<R: tauri::Runtime> MassProductionHost for AppHandle<R> {
+    fn emit_snapshot(&self, snapshot: &MassProductionSnapshot) {
+        if let Err(e) = self.emit("mass-production-snapshot", snapshot.clone()) {
+            eprintln!("Failed to emit mass production snapshot: {e}");
+        }
+        if let Some(hub) = self.try_state::<RemoteEventHub>() {
+            hub.publish_snapshot(snapshot);
+        }
+    }
+
+    fn emit_progress(&self, payload: &MassProductionProgressEvent) {
+        if let Err(e) = self.emit("mass-production-progress", payload.clone()) {
+            eprintln!("Failed to emit mass production progress event: {e}");
+        }
+        if let Some(hub) = self.try_state::<RemoteEventHub>() {
+            hub.publish_progress(payload);
+        }
+    }
+
+    fn emit_log(&self, entry: AppLogEntry) {
+        emit_app_log(self, entry);
+    }
+
+    fn config_dir(&self) -> Result<PathBuf, String> {
+        self.path()
+            .app_config_dir()
+            .map_err(|e| format!("获取配置目录失败: {e}"))
+    }
+
+    fn data_dir(&self) -> Result<PathBuf, String> {
+        self.path()
+            .app_data_dir()
+            .map_err(|e| format!("获取数据目录失败: {e}"))
+    }
+}
+
 #[derive(Default)]
 struct ProgressCounter {
     current: u64,
     total: Option<u64>,
 }
 
-struct PortProgressCallback<R: tauri::Runtime> {
-    app_handle: AppHandle<R>,
+struct PortProgressCallback<H: MassProductionHost> {
+    host: H,
+    session_id: u64,
     port_name: String,
     state: Arc<Mutex<MassProductionState>>,
     contexts: Mutex<HashMap<u64, TauriProgressContext>>,
     counters: Mutex<HashMap<u64, ProgressCounter>>,
+    /// 最近开始的操作所处阶段，写入失败时用于归类错误
+    stage: Mutex<Option<FlashErrorCategory>>,
 }
 
-impl<R: tauri::Runtime> PortProgressCallback<R> {
+impl<H: MassProductionHost> PortProgressCallback<H> {
     fn new(
-        app_handle: AppHandle<R>,
+        host: H,
+        session_id: u64,
         port_name: String,
         state: Arc<Mutex<MassProductionState>>,
     ) -> Self {
         Self {
-            app_handle,
+            host,
+            session_id,
             port_name,
             state,
             contexts: Mutex::new(HashMap::new()),
             counters: Mutex::new(HashMap::new()),
+            stage: Mutex::new(None),
+        }
+    }
+
+    fn failure_stage(&self) -> Option<FlashErrorCategory> {
+        *self.stage.lock().unwrap()
+    }
+
+    fn record_stage(&self, operation: &TauriProgressOperation) {
+        if let Some(stage) = operation.failure_stage() {
+            *self.stage.lock().unwrap() = Some(stage);
         }
     }
 
     fn emit_event(&self, event: TauriProgressEvent) {
         let payload = MassProductionProgressEvent {
+            session_id: self.session_id,
             port_name: self.port_name.clone(),
             event,
         };
 
-        if let Err(e) = self.app_handle.emit("mass-production-progress", payload) {
-            eprintln!("Failed to emit mass production progress event: {e}");
-        }
+        self.host.emit_progress(&payload);
     }
 
     fn update_port_progress(&self, progress: u8, message: Option<String>) {
@@ -97,7 +179,7 @@ impl<R: tauri::Runtime> PortProgressCallback<R> {
     }
 }
 
-impl<R: tauri::Runtime> ProgressSink for PortProgressCallback<R> {
+impl<H: MassProductionHost> ProgressSink for PortProgressCallback<H> {
     fn on_event(&self, event: ProgressEvent) {
         match event {
             ProgressEvent::Start { id, ctx } => {
@@ -105,6 +187,7 @@ impl<R: tauri::Runtime> ProgressSink for PortProgressCallback<R> {
                 let context = TauriProgressContext::from(ctx);
                 let total = total_from_progress_type(&context.progress_type);
 
+                self.record_stage(&context.operation);
                 self.contexts.lock().unwrap().insert(id.0, context.clone());
                 self.counters.lock().unwrap().insert(
                     id.0,
@@ -144,6 +227,7 @@ impl<R: tauri::Runtime> ProgressSink for PortProgressCallback<R> {
             }
             ProgressEvent::Update { id, ctx } => {
                 let context = TauriProgressContext::from(ctx);
+                self.record_stage(&context.operation);
                 self.contexts.lock().unwrap().insert(id.0, context.clone());
 
                 self.update_port_message(Self::operation_label(&context.operation).to_string());
@@ -226,13 +310,6 @@ impl<R: tauri::Runtime> ProgressSink for PortProgressCallback<R> {
     }
 }
 
-fn total_from_progress_type(progress_type: &TauriProgressType) -> Option<u64> {
-    match progress_type {
-        TauriProgressType::Spinner => None,
-        TauriProgressType::Bar { total } => Some(*total),
-    }
-}
-
 fn now_millis() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -242,24 +319,15 @@ fn now_millis() -> u64 {
 
 const SETTINGS_STORE_FILENAME: &str = "massProduction.json";
 const SESSION_LOG_STORE_FILENAME: &str = "massProduction-log.json";
-const MASS_PRODUCTION_RUNTIME_LOG_DIRNAME: &str = "logs";
+pub const MASS_PRODUCTION_RUNTIME_LOG_DIRNAME: &str = "logs";
 const MASS_PRODUCTION_RUNTIME_LOG_FILENAME: &str = "mass-production-runtime.log";
 const MASS_PRODUCTION_PORT_LOG_PREFIX: &str = "mass-production-port";
 
-fn resolve_mass_production_log_paths<R: tauri::Runtime>(
-    app_handle: &AppHandle<R>,
+fn resolve_mass_production_log_paths<H: MassProductionHost>(
+    host: &H,
 ) -> Result<MassProductionLogPaths, String> {
-    let config_dir = app_handle
-        .path()
-        .app_config_dir()
-        .map_err(|e| format!("获取配置目录失败: {e}"))?;
-
-    let data_dir = app_handle
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("获取数据目录失败: {e}"))?;
-
-    let runtime_log_dir = data_dir.join(MASS_PRODUCTION_RUNTIME_LOG_DIRNAME);
+    let config_dir = host.config_dir()?;
+    let runtime_log_dir = host.runtime_log_dir()?;
     let runtime_log_path = runtime_log_dir.join(MASS_PRODUCTION_RUNTIME_LOG_FILENAME);
 
     Ok(MassProductionLogPaths {
@@ -276,10 +344,8 @@ fn resolve_mass_production_log_paths<R: tauri::Runtime>(
     })
 }
 
-fn ensure_runtime_log_path<R: tauri::Runtime>(
-    app_handle: &AppHandle<R>,
-) -> Result<PathBuf, String> {
-    let paths = resolve_mass_production_log_paths(app_handle)?;
+fn ensure_runtime_log_path<H: MassProductionHost>(host: &H) -> Result<PathBuf, String> {
+    let paths = resolve_mass_production_log_paths(host)?;
     let runtime_log_dir = PathBuf::from(paths.runtime_log_dir);
     fs::create_dir_all(&runtime_log_dir).map_err(|e| format!("创建量产日志目录失败: {e}"))?;
     Ok(PathBuf::from(paths.runtime_log_path))
@@ -303,12 +369,12 @@ fn sanitize_log_filename_fragment(input: &str) -> String {
     trimmed.chars().take(80).collect()
 }
 
-fn ensure_port_runtime_log_path<R: tauri::Runtime>(
-    app_handle: &AppHandle<R>,
+fn ensure_port_runtime_log_path<H: MassProductionHost>(
+    host: &H,
     session_id: u64,
     port_name: &str,
 ) -> Result<PathBuf, String> {
-    let paths = resolve_mass_production_log_paths(app_handle)?;
+    let paths = resolve_mass_production_log_paths(host)?;
     let runtime_log_dir = PathBuf::from(paths.runtime_log_dir);
     fs::create_dir_all(&runtime_log_dir).map_err(|e| format!("创建量产日志目录失败: {e}"))?;
 
@@ -359,21 +425,13 @@ fn ensure_log_file_exists(log_path: &PathBuf) -> Result<(), String> {
 
     Ok(())
 }
-fn append_mass_runtime_log<R: tauri::Runtime>(
-    app_handle: &AppHandle<R>,
-    level: &str,
-    message: &str,
-) {
-    emit_app_log(app_handle, AppLogEntry::mass_production(level, message));
-    append_mass_runtime_log_file(app_handle, level, message);
+fn append_mass_runtime_log<H: MassProductionHost>(host: &H, level: &str, message: &str) {
+    host.emit_log(AppLogEntry::mass_production(level, message));
+    append_mass_runtime_log_file(host, level, message);
 }
 
-fn append_mass_runtime_log_file<R: tauri::Runtime>(
-    app_handle: &AppHandle<R>,
-    level: &str,
-    message: &str,
-) {
-    let runtime_log_path = match ensure_runtime_log_path(app_handle) {
+fn append_mass_runtime_log_file<H: MassProductionHost>(host: &H, level: &str, message: &str) {
+    let runtime_log_path = match ensure_runtime_log_path(host) {
         Ok(path) => path,
         Err(error) => {
             eprintln!("[mass-production][log][{level}] {message}");
@@ -387,14 +445,14 @@ fn append_mass_runtime_log_file<R: tauri::Runtime>(
     }
 }
 
-fn append_mass_port_runtime_log<R: tauri::Runtime>(
-    app_handle: &AppHandle<R>,
+fn append_mass_port_runtime_log<H: MassProductionHost>(
+    host: &H,
     session_id: u64,
     port_name: &str,
     level: &str,
     message: &str,
 ) {
-    let port_log_path = match ensure_port_runtime_log_path(app_handle, session_id, port_name) {
+    let port_log_path = match ensure_port_runtime_log_path(host, session_id, port_name) {
         Ok(path) => path,
         Err(error) => {
             eprintln!("[mass-production][{port_name}][{level}] {message}");
@@ -408,26 +466,25 @@ fn append_mass_port_runtime_log<R: tauri::Runtime>(
     }
 }
 
-fn append_mass_worker_runtime_log<R: tauri::Runtime>(
-    app_handle: &AppHandle<R>,
+fn append_mass_worker_runtime_log<H: MassProductionHost>(
+    host: &H,
     session_id: u64,
     port_name: &str,
     level: &str,
     message: &str,
 ) {
-    emit_app_log(
-        app_handle,
+    host.emit_log(
         AppLogEntry::mass_production(level, message)
             .with_session_id(session_id)
             .with_port(port_name),
     );
 
     append_mass_runtime_log_file(
-        app_handle,
+        host,
         level,
         &format!("session_id={session_id} port={port_name} {message}"),
     );
-    append_mass_port_runtime_log(app_handle, session_id, port_name, level, message);
+    append_mass_port_runtime_log(host, session_id, port_name, level, message);
 }
 fn panic_payload_summary(payload: &(dyn Any + Send)) -> String {
     if let Some(message) = payload.downcast_ref::<String>() {
@@ -462,22 +519,17 @@ fn sanitize_request(
 
     request.max_concurrency = request.max_concurrency.clamp(1, 32);
 
-    let mut fixed_addresses = HashMap::new();
+    if let Some(retry) = &mut request.retry {
+        retry.max_attempts = retry.max_attempts.clamp(1, 10);
+        if retry.retry_baud_rate == Some(0) {
+            return Err("重试波特率必须大于 0".to_string());
+        }
+    }
+
     for file in &request.files {
         if !std::path::Path::new(&file.file_path).exists() {
             return Err(format!("文件不存在: {}", file.file_path));
         }
-
-        if file.address != 0 {
-            if let Some(existing_file_path) =
-                fixed_addresses.insert(file.address, file.file_path.clone())
-            {
-                return Err(format!(
-                    "烧录地址重复: 0x{:08X} 已用于 {} 和 {}",
-                    file.address, existing_file_path, file.file_path
-                ));
-            }
-        }
     }
 
     if !request.stub_config_path.trim().is_empty()
@@ -486,6 +538,37 @@ fn sanitize_request(
         return Err(format!("Stub 配置文件不存在: {}", request.stub_config_path));
     }
 
+    let ranges = validate_write_plan(
+        request
+            .files
+            .iter()
+            .map(|file| (file.file_path.as_str(), file.address)),
+        &request.chip_model,
+        &request.memory_type,
+        FlashBounds {
+            flash_size: configured_flash_size(&request.stub_config_path, &request.memory_type)?,
+            skip_check: request.skip_address_check,
+        },
+    )?;
+
+    if let Some(template) = &request.unit_data {
+        let renderer = UnitDataRenderer::new(template.clone())?;
+        renderer.check_no_overlap(&ranges)?;
+        if renderer.capacity() == Some(0) {
+            return Err("出厂数据 CSV 文件没有数据行".to_string());
+        }
+        if renderer.uses_serial() && request.serial.is_none() {
+            return Err("出厂数据模板引用了序列号，但未选择序列号池".to_string());
+        }
+    }
+
+    verify_expected_hashes(
+        request
+            .files
+            .iter()
+            .map(|file| (file.file_path.as_str(), file.expected_hash.as_deref())),
+    )?;
+
     if !request.external_stub_path.trim().is_empty()
         && !std::path::Path::new(&request.external_stub_path).exists()
     {
@@ -498,7 +581,7 @@ fn sanitize_request(
     Ok(request)
 }
 
-fn should_soft_reset_after_operation(after_operation: &str) -> Result<bool, String> {
+pub(crate) fn should_soft_reset_after_operation(after_operation: &str) -> Result<bool, String> {
     match after_operation.trim() {
         "soft_reset" => Ok(true),
         "no_reset" | "" => Ok(false),
@@ -586,17 +669,13 @@ fn enumerate_ports() -> Result<Vec<MassProductionPortInfo>, String> {
                 last_seen_at: now,
                 task_started_at: None,
                 task_finished_at: None,
+                error_category: None,
+                attempt: 0,
             }
         })
         .collect())
 }
 
-fn emit_snapshot<R: tauri::Runtime>(app_handle: &AppHandle<R>, snapshot: &MassProductionSnapshot) {
-    if let Err(e) = app_handle.emit("mass-production-snapshot", snapshot.clone()) {
-        eprintln!("Failed to emit mass production snapshot: {e}");
-    }
-}
-
 fn queue_port(state: &mut MassProductionState, port_name: &str, now: u64) {
     if state.active_ports.contains(port_name) || state.queue.contains(&port_name.to_string()) {
         return;
@@ -619,6 +698,8 @@ fn reset_terminal_port_for_requeue(port: &mut MassProductionPortInfo, now: u64)
     port.message = Some("Ready".to_string());
     port.task_started_at = Some(now);
     port.task_finished_at = None;
+    port.error_category = None;
+    port.attempt = 0;
 }
 
 fn can_reset_terminal_status(port: &MassProductionPortInfo) -> bool {
@@ -724,7 +805,7 @@ fn scan_ports(state: &mut MassProductionState, trigger_flash: bool) -> Result<()
                     existing.status = MassProductionPortStatus::Filtered;
                     existing.progress = 0;
                     existing.message = Some("Filtered".to_string());
-                    state.queue.retain(|p| p != &name);
+                    drop_queued_port(state, &name);
                 }
                 continue;
             }
@@ -782,7 +863,7 @@ fn scan_ports(state: &mut MassProductionState, trigger_flash: bool) -> Result<()
             continue;
         }
 
-        state.queue.retain(|queued| queued != &port_name);
+        drop_queued_port(state, &port_name);
 
         if let Some(port) = state.ports.get_mut(&port_name) {
             if port.status != MassProductionPortStatus::Disconnected {
@@ -805,16 +886,7 @@ fn build_write_flash_params(
     let mut files = Vec::new();
 
     for file_info in &request.files {
-        let file_string = if file_info.address == 0 {
-            file_info.file_path.clone()
-        } else {
-            format!("{}@0x{:08X}", file_info.file_path, file_info.address)
-        };
-
-        let parsed_files = Utils::parse_file_info(&file_string)
-            .map_err(|e| format!("解析文件 {} 失败: {e}", file_info.file_path))?;
-
-        files.extend(parsed_files);
+        files.extend(parse_write_file(&file_info.file_path, file_info.address)?);
     }
 
     Ok(WriteFlashParams {
@@ -825,8 +897,57 @@ fn build_write_flash_params(
     })
 }
 
-fn run_worker<R: tauri::Runtime>(
-    app_handle: AppHandle<R>,
+/// 汇总一次烧录尝试的追溯信息；固件摘要取自会话锁定的副本
+fn build_trace_record(
+    state: &MassProductionState,
+    request: &MassProductionStartRequest,
+    port_name: &str,
+    session_id: u64,
+    serial: Option<String>,
+    (started_at, finished_at): (u64, u64),
+    result: &Result<(), FlashError>,
+) -> TraceRecord {
+    let port = state.ports.get(port_name);
+
+    TraceRecord {
+        session_id,
+        session_key: state.session_key.clone(),
+        port_name: port_name.to_string(),
+        attempt: port.map(|port| port.attempt).unwrap_or(1),
+        vid: port.and_then(|port| port.vid.clone()),
+        pid: port.and_then(|port| port.pid.clone()),
+        usb_serial: port.and_then(|port| port.serial_number.clone()),
+        location_path: port.and_then(|port| port.location_path.clone()),
+        chip_model: request.chip_model.clone(),
+        memory_type: request.memory_type.clone(),
+        mode: request.mode,
+        firmware: state
+            .frozen_firmware
+            .as_ref()
+            .map(FrozenFirmware::infos)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|file| TraceFirmwareFile {
+                file_path: file.file_path,
+                address: file.address,
+                sha256: file.sha256,
+            })
+            .collect(),
+        serial,
+        started_at,
+        finished_at,
+        result: match result {
+            Ok(()) => TraceResult::Success,
+            Err(error) if error.category == FlashErrorCategory::Cancelled => TraceResult::Cancelled,
+            Err(_) => TraceResult::Failed,
+        },
+        error_category: result.as_ref().err().map(|error| error.category),
+        error: result.as_ref().err().map(|error| error.message.clone()),
+    }
+}
+
+fn run_worker<H: MassProductionHost>(
+    host: H,
     state: Arc<Mutex<MassProductionState>>,
     request: MassProductionStartRequest,
     port_name: String,
@@ -834,8 +955,9 @@ fn run_worker<R: tauri::Runtime>(
 ) {
     let now = now_millis();
     let cancel_token = CancelToken::new();
+    let mut attempt = 1;
 
-    {
+    let (reservation, unit_data) = {
         let mut locked = state.lock().unwrap();
         if locked.session_id != session_id || !locked.running {
             locked.active_ports.remove(&port_name);
@@ -848,101 +970,227 @@ fn run_worker<R: tauri::Runtime>(
         if let Some(port) = locked.ports.get_mut(&port_name) {
             port.status = MassProductionPortStatus::Flashing;
             port.progress = 0;
-            port.message = Some("Flashing".to_string());
+            port.message = Some(
+                match request.mode {
+                    MassProductionMode::Write => "Flashing",
+                    MassProductionMode::Verify => "Verifying",
+                }
+                .to_string(),
+            );
             port.task_started_at = Some(now);
             port.task_finished_at = None;
+            port.error_category = None;
+            port.attempt = port.attempt.saturating_add(1);
+            attempt = port.attempt;
+        }
+
+        // 在锁内分配设备序号和序列号，保证并发 worker 拿到的值互不相同
+        let reservation = reserve_for_attempt(&mut locked, &port_name, now);
+        let unit_data = match (
+            locked.unit_data.clone(),
+            locked.ports.get(&port_name).cloned(),
+        ) {
+            (Some(renderer), Some(port)) => reservation
+                .unit_index
+                .clone()
+                .map(|index| index.map(|index| (renderer, index, port))),
+            _ => Ok(None),
+        };
+        (reservation, unit_data)
+    };
+    let AttemptReservation {
+        mut serial,
+        serial_write,
+        // 写入一旦开始，序列号和出厂数据可能已经落到设备上；重试沿用之前尝试的状态
+        mut write_started,
+        ..
+    } = reservation;
+    // 池中已记为预留的值，即使写盘失败也要在结束时结算
+    let reserved_serial = serial.clone().ok().flatten();
+    let serial_kept = serial_write.is_none();
+
+    // 预留在写盘后才生效，即使随后崩溃该序列号也不会再被分配
+    if let Some(write) = serial_write {
+        if let Err(e) = write.persist() {
+            serial = Err(format!("保存序列号预留失败: {e}"));
         }
     }
 
-    let progress_callback: ProgressSinkArc = Arc::new(PortProgressCallback::new(
-        app_handle.clone(),
+    let port_progress = Arc::new(PortProgressCallback::new(
+        host.clone(),
+        session_id,
         port_name.clone(),
         state.clone(),
     ));
+    let progress_callback: ProgressSinkArc = port_progress.clone();
 
-    append_mass_worker_runtime_log(
-        &app_handle,
-        session_id,
-        &port_name,
-        "INFO",
-        "worker started",
-    );
+    append_mass_worker_runtime_log(&host, session_id, &port_name, "INFO", "worker started");
+    if let Ok(Some(value)) = &serial {
+        let action = if serial_kept {
+            "kept for retry"
+        } else {
+            "reserved"
+        };
+        append_mass_worker_runtime_log(
+            &host,
+            session_id,
+            &port_name,
+            "INFO",
+            &format!("serial {value} {action}"),
+        );
+    }
+    if let Ok(Some((renderer, index, _))) = &unit_data {
+        let message = match renderer.capacity() {
+            Some(rows) => format!("unit #{index} assigned CSV row {} of {rows}", index + 1),
+            None => format!("unit #{index} assigned"),
+        };
+        append_mass_worker_runtime_log(&host, session_id, &port_name, "INFO", &message);
+    }
 
     let port_name_for_panic = port_name.clone();
-    let result: Result<(), String> = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let device_config = DeviceConfig {
-            chip_type: request.chip_model.clone(),
-            memory_type: request.memory_type.clone(),
-            port_name: port_name.clone(),
-            baud_rate: request.baud_rate.unwrap_or(1_000_000),
-            stub_config_path: request.stub_config_path.clone(),
-            external_stub_path: request.external_stub_path.clone(),
-            before_operation: request.before_operation.clone(),
-            after_operation: request.after_operation.clone(),
-        };
+    let result: Result<(), FlashError> =
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let serial = serial.clone()?;
+            let unit_data = unit_data.clone()?;
+            let device_config = DeviceConfig {
+                chip_type: request.chip_model.clone(),
+                memory_type: request.memory_type.clone(),
+                port_name: port_name.clone(),
+                baud_rate: request
+                    .retry
+                    .as_ref()
+                    .filter(|_| attempt > 1)
+                    .and_then(|retry| retry.retry_baud_rate)
+                    .or(request.baud_rate)
+                    .unwrap_or(1_000_000),
+                stub_config_path: request.stub_config_path.clone(),
+                external_stub_path: request.external_stub_path.clone(),
+                before_operation: request.before_operation.clone(),
+                after_operation: request.after_operation.clone(),
+            };
 
-        let mut tool = create_tool_instance_with_progress(
-            &device_config,
-            progress_callback,
-            cancel_token.clone(),
-        )?;
-        let params = build_write_flash_params(&request)?;
-        tool.write_flash(&params)
-            .map_err(|e| format!("写入 Flash 失败: {e}"))?;
+            let mut tool = create_tool_instance_with_progress(
+                &device_config,
+                progress_callback,
+                cancel_token.clone(),
+            )
+            .map_err(|error| error.or_stage(port_progress.failure_stage()))?;
+            let unit_image = match &unit_data {
+                Some((renderer, index, port)) => {
+                    let image = renderer.render(*index, port, serial.as_deref())?;
+                    append_mass_worker_runtime_log(
+                        &host,
+                        session_id,
+                        &port_name,
+                        "INFO",
+                        &format!(
+                            "per-unit data #{} rendered at 0x{:08X}",
+                            index,
+                            renderer.address()
+                        ),
+                    );
+                    Some((renderer.address(), image))
+                }
+                None => None,
+            };
+            // request.files 指向会话锁定的副本，写入前确认副本未被改动
+            verify_expected_hashes(
+                request
+                    .files
+                    .iter()
+                    .map(|file| (file.file_path.as_str(), file.expected_hash.as_deref())),
+            )?;
+            match request.mode {
+                MassProductionMode::Write => {
+                    let mut params = build_write_flash_params(&request)?;
+                    if let Some((address, image)) = &unit_image {
+                        params.files.push(unit_data_write_file(*address, image)?);
+                    }
+                    write_started = true;
+                    tool.write_flash(&params).map_err(|e| {
+                        classify_write_error(
+                            port_progress.failure_stage(),
+                            format!("写入 Flash 失败: {e}"),
+                        )
+                    })?;
+                }
+                MassProductionMode::Verify => {
+                    let files: Vec<WriteFlashFileInfo> = request
+                        .files
+                        .iter()
+                        .map(|file| WriteFlashFileInfo {
+                            address: file.address,
+                            file_path: file.file_path.clone(),
+                            expected_hash: None,
+                        })
+                        .collect();
+                    let results = verify_device_flash(tool.as_mut(), &files, |event| {
+                        port_progress.emit_event(event)
+                    })?;
+                    if let Some(failure) = describe_verify_failures(&results) {
+                        return Err(FlashError::new(FlashErrorCategory::VerifyMismatch, failure));
+                    }
+                }
+            }
 
-        if should_soft_reset_after_operation(&request.after_operation)? {
-            append_mass_worker_runtime_log(
-                &app_handle,
-                session_id,
-                &port_name,
-                "INFO",
-                "post-operation soft reset started",
-            );
-            tool.soft_reset()
-                .map_err(|e| format!("下载后软复位失败: {e}"))?;
+            if should_soft_reset_after_operation(&request.after_operation)? {
+                append_mass_worker_runtime_log(
+                    &host,
+                    session_id,
+                    &port_name,
+                    "INFO",
+                    "post-operation soft reset started",
+                );
+                tool.soft_reset()
+                    .map_err(|e| format!("下载后软复位失败: {e}"))?;
+                append_mass_worker_runtime_log(
+                    &host,
+                    session_id,
+                    &port_name,
+                    "INFO",
+                    "post-operation soft reset finished",
+                );
+            }
+
+            Ok(())
+        }))
+        .map_err(|panic_payload| {
+            let summary = panic_payload_summary(panic_payload.as_ref());
+            let details = panic_payload_details(panic_payload.as_ref());
             append_mass_worker_runtime_log(
-                &app_handle,
+                &host,
                 session_id,
-                &port_name,
-                "INFO",
-                "post-operation soft reset finished",
+                &port_name_for_panic,
+                "ERROR",
+                &format!("uncaught panic: {details}"),
             );
-        }
-
-        Ok(())
-    }))
-    .map_err(|panic_payload| {
-        let summary = panic_payload_summary(panic_payload.as_ref());
-        let details = panic_payload_details(panic_payload.as_ref());
-        append_mass_worker_runtime_log(
-            &app_handle,
-            session_id,
-            &port_name_for_panic,
-            "ERROR",
-            &format!("uncaught panic: {details}"),
-        );
-        format!("量产任务发生未捕获异常: {summary}")
-    })
-    .and_then(|result| result);
+            FlashError::new(
+                FlashErrorCategory::Panic,
+                format!("量产任务发生未捕获异常: {summary}"),
+            )
+        })
+        .and_then(|result| result)
+        .map_err(|error| refine_flash_error(error, cancel_token.is_cancelled(), &port_name));
 
     match &result {
         Ok(()) => append_mass_worker_runtime_log(
-            &app_handle,
+            &host,
             session_id,
             &port_name,
             "INFO",
             "worker finished successfully",
         ),
         Err(error) => append_mass_worker_runtime_log(
-            &app_handle,
+            &host,
             session_id,
             &port_name,
             "ERROR",
-            &format!("worker failed: {error}"),
+            &format!("worker failed ({}): {error}", error.category.as_str()),
         ),
     }
 
-    {
+    let trace_path = host.data_dir().map(|dir| trace_records_path(&dir));
+    let (settlement, trace_record, retry_scheduled) = {
         let mut locked = state.lock().unwrap();
         if locked.session_id != session_id {
             locked.active_ports.remove(&port_name);
@@ -954,19 +1202,52 @@ fn run_worker<R: tauri::Runtime>(
         locked.active_cancel_tokens.remove(&port_name);
 
         let finished_at = now_millis();
-        let is_cancelled = result
-            .as_ref()
-            .err()
-            .map(|error| error.to_lowercase())
-            .is_some_and(|error| {
-                error.contains("operation cancelled")
-                    || error.contains("cancelled")
-                    || error.contains("canceled")
-            });
+        let failure_category = result.as_ref().err().map(|error| error.category);
+        let is_cancelled = failure_category == Some(FlashErrorCategory::Cancelled);
         let is_success = result.is_ok();
+        // 会话仍在运行且端口未被过滤时，按策略安排原端口重试
+        let retry_in = match (&request.retry, failure_category) {
+            (Some(policy), Some(category))
+                if locked.running
+                    && locked
+                        .ports
+                        .get(&port_name)
+                        .is_some_and(|port| port.is_allowed) =>
+            {
+                retry_delay(policy, attempt, category)
+            }
+            _ => None,
+        };
+
+        let settlement = settle_attempt(
+            &mut locked,
+            &port_name,
+            AttemptOutcome {
+                unit_index: unit_data
+                    .as_ref()
+                    .ok()
+                    .and_then(|data| data.as_ref().map(|(_, index, _)| *index)),
+                serial: reserved_serial.clone(),
+                serial_saved: serial.is_ok(),
+                is_success,
+                retrying: retry_in.is_some(),
+                write_started,
+            },
+        );
+
+        // 每次失败的尝试都计入类别统计，包括随后安排了重试的；failed_count 只统计最终失败的设备
+        if let Some(category) = failure_category.filter(|_| !is_cancelled) {
+            let count = locked.failure_counts.entry(category).or_insert(0);
+            *count = count.saturating_add(1);
+        }
 
         if is_success {
             locked.success_count = locked.success_count.saturating_add(1);
+        } else if let Some(delay) = retry_in {
+            locked.retry_count = locked.retry_count.saturating_add(1);
+            locked
+                .retry_queue
+                .push((port_name.clone(), finished_at.saturating_add(delay)));
         } else if is_cancelled {
             locked.cancelled_count = locked.cancelled_count.saturating_add(1);
         } else {
@@ -975,41 +1256,368 @@ fn run_worker<R: tauri::Runtime>(
 
         if let Some(port) = locked.ports.get_mut(&port_name) {
             port.task_finished_at = Some(finished_at);
+            port.error_category = failure_category.filter(|_| !is_cancelled);
 
             if is_success {
                 port.status = MassProductionPortStatus::Success;
                 port.progress = 100;
                 port.message = Some("Completed".to_string());
+            } else if let (Some(delay), Err(e)) = (retry_in, &result) {
+                port.status = MassProductionPortStatus::Queued;
+                port.progress = 0;
+                port.message = Some(format!(
+                    "Retry {}/{} in {} ms: {}",
+                    attempt + 1,
+                    request
+                        .retry
+                        .as_ref()
+                        .map(|retry| retry.max_attempts)
+                        .unwrap_or(attempt + 1),
+                    delay,
+                    e.message
+                ));
             } else if is_cancelled {
                 port.status = MassProductionPortStatus::Cancelled;
                 port.progress = 0;
                 port.message = Some("Cancelled by user".to_string());
             } else if let Err(e) = &result {
                 port.status = MassProductionPortStatus::Error;
-                port.message = Some(e.clone());
+                port.message = Some(e.message.clone());
             }
         }
 
+        // 在锁内生成记录，释放锁后再追加到文件
+        let trace_record = build_trace_record(
+            &locked,
+            &request,
+            &port_name,
+            session_id,
+            serial.as_ref().ok().and_then(|value| value.clone()),
+            (now, finished_at),
+            &result,
+        );
+
         if !locked.running && locked.active_ports.is_empty() {
             locked.ended_at = Some(finished_at);
         }
+        (settlement, trace_record, retry_in)
+    };
+
+    if let Some((value, settled)) = settlement.serial {
+        // 写盘失败时文件中的序列号仍为预留，下次打开池时作废
+        let (level, message) =
+            match settled.and_then(|(action, write)| write.persist().map(|()| action)) {
+                Ok(action) => ("INFO", format!("serial {value} {action}")),
+                Err(e) => ("ERROR", format!("serial {value} stays reserved: {e}")),
+            };
+        append_mass_worker_runtime_log(&host, session_id, &port_name, level, &message);
+    }
+    for message in settlement.notes {
+        append_mass_worker_runtime_log(&host, session_id, &port_name, "INFO", &message);
+    }
+    if let Some(delay) = retry_scheduled {
+        append_mass_worker_runtime_log(
+            &host,
+            session_id,
+            &port_name,
+            "INFO",
+            &format!("attempt {attempt} failed, retry scheduled in {delay} ms"),
+        );
+    }
+    let trace_error = trace_path
+        .as_ref()
+        .map_err(Clone::clone)
+        .and_then(|path| append_trace_record(path, &trace_record))
+        .err();
+    if let Some(error) = trace_error {
+        append_mass_worker_runtime_log(
+            &host,
+            session_id,
+            &port_name,
+            "ERROR",
+            &format!("trace record not saved: {error}"),
+        );
     }
 
     let snapshot = { state.lock().unwrap().to_snapshot() };
-    emit_snapshot(&app_handle, &snapshot);
+    host.emit_snapshot(&snapshot);
 }
 
-fn dispatch_workers<R: tauri::Runtime>(
-    app_handle: &AppHandle<R>,
+/// 一次尝试使用的设备序号和序列号
+struct AttemptReservation {
+    unit_index: Result<Option<u64>, String>,
+    serial: Result<Option<String>, String>,
+    /// 新预留序列号后的池内容，需在锁外写盘；沿用保留的序列号时为 None
+    serial_write: Option<SerialPoolWrite>,
+    write_started: bool,
+}
+
+/// 等待重试的端口沿用为同一块板子保留的设备序号和序列号，其余端口重新分配
+fn reserve_for_attempt(
+    state: &mut MassProductionState,
+    port_name: &str,
+    now: u64,
+) -> AttemptReservation {
+    let hold = state.retry_holds.remove(port_name).unwrap_or_default();
+    let unit_index = match (&state.unit_data, hold.unit_index) {
+        (None, _) => Ok(None),
+        (Some(_), Some(index)) => Ok(Some(index)),
+        (Some(_), None) => state.unit_indices.allocate().map(Some),
+    };
+    let mut serial_write = None;
+    let serial = match (state.serial_pool.as_mut(), hold.serial) {
+        (None, _) => Ok(None),
+        (Some(_), Some(value)) => Ok(Some(value)),
+        (Some(pool), None) => pool.reserve(port_name, now).map(|(value, write)| {
+            serial_write = Some(write);
+            Some(value)
+        }),
+    };
+    AttemptReservation {
+        unit_index,
+        serial,
+        serial_write,
+        write_started: hold.write_started,
+    }
+}
+
+/// 一次尝试结束时占用的设备序号和序列号及其结果
+struct AttemptOutcome {
+    unit_index: Option<u64>,
+    serial: Option<String>,
+    /// 序列号的预留已经写盘
+    serial_saved: bool,
+    is_success: bool,
+    retrying: bool,
+    write_started: bool,
+}
+
+/// 序列号的结算动作和结算后的池内容
+type SerialSettlement = Result<(&'static str, SerialPoolWrite), String>;
+
+/// 结算结果：序列号的池内容需在锁外写盘，notes 为要记录的日志
+#[derive(Default)]
+struct Settlement {
+    serial: Option<(String, SerialSettlement)>,
+    notes: Vec<String>,
+}
+
+/// 安排了重试时为端口保留设备序号和已写盘的序列号，同一块板子重试时写入相同的数据；
+/// 否则按这块板子的最终结果结算
+fn settle_attempt(
+    state: &mut MassProductionState,
+    port_name: &str,
+    outcome: AttemptOutcome,
+) -> Settlement {
+    let serial_policy = state
+        .request
+        .as_ref()
+        .and_then(|request| request.serial.as_ref())
+        .map(|config| config.on_failure)
+        .unwrap_or_default();
+    let unit_policy = state
+        .request
+        .as_ref()
+        .and_then(|request| request.unit_data.as_ref())
+        .map(|template| template.on_failure)
+        .unwrap_or_default();
+    let mut settlement = Settlement::default();
+    let mut hold = RetryHold {
+        write_started: outcome.write_started,
+        ..RetryHold::default()
+    };
+
+    if let Some(value) = outcome.serial {
+        if outcome.retrying && outcome.serial_saved {
+            settlement
+                .notes
+                .push(format!("serial {value} held for retry"));
+            hold.serial = Some(value);
+        } else if let Some(pool) = state.serial_pool.as_mut() {
+            let settled = settle_serial(
+                pool,
+                &value,
+                outcome.is_success,
+                outcome.write_started,
+                serial_policy,
+            );
+            settlement.serial = Some((value, settled));
+        }
+    }
+
+    if let Some(index) = outcome.unit_index {
+        let action = if outcome.retrying {
+            hold.unit_index = Some(index);
+            "held for retry"
+        } else {
+            settle_unit_index(
+                &mut state.unit_indices,
+                index,
+                outcome.is_success,
+                outcome.write_started,
+                unit_policy,
+            )
+        };
+        settlement.notes.push(format!("unit #{index} {action}"));
+    }
+
+    if outcome.retrying {
+        state.retry_holds.insert(port_name.to_string(), hold);
+    }
+    settlement
+}
+
+/// 端口被过滤、断开或会话结束而不再重试时，按最终失败结算其保留的设备序号和序列号；
+/// 池内容和日志留给监督线程或停止流程在锁外写出
+fn settle_retry_hold(state: &mut MassProductionState, port_name: &str) {
+    let Some(hold) = state.retry_holds.remove(port_name) else {
+        return;
+    };
+    let settlement = settle_attempt(
+        state,
+        port_name,
+        AttemptOutcome {
+            unit_index: hold.unit_index,
+            serial: hold.serial,
+            serial_saved: true,
+            is_success: false,
+            retrying: false,
+            write_started: hold.write_started,
+        },
+    );
+    if let Some((value, settled)) = settlement.serial {
+        let note = match settled {
+            Ok((action, write)) => {
+                state.pending_pool_write = Some(write);
+                format!("serial {value} {action}")
+            }
+            Err(e) => format!("serial {value} stays reserved: {e}"),
+        };
+        state.settlement_notes.push(format!("{port_name}: {note}"));
+    }
+    for note in settlement.notes {
+        state.settlement_notes.push(format!("{port_name}: {note}"));
+    }
+}
+
+/// 端口离开派发队列和重试队列，并结算其保留的设备序号和序列号
+fn drop_queued_port(state: &mut MassProductionState, port_name: &str) {
+    state.queue.retain(|queued| queued != port_name);
+    state.retry_queue.retain(|(queued, _)| queued != port_name);
+    settle_retry_hold(state, port_name);
+}
+
+/// 在锁外写出放弃重试时的结算结果；池内容带有版本号，晚到的旧内容不会覆盖新内容
+fn flush_retry_settlements<H: MassProductionHost>(
+    host: &H,
     state: &Arc<Mutex<MassProductionState>>,
 ) {
+    let (notes, write) = {
+        let mut locked = state.lock().unwrap();
+        (
+            std::mem::take(&mut locked.settlement_notes),
+            locked.pending_pool_write.take(),
+        )
+    };
+    for note in notes {
+        append_mass_runtime_log(host, "INFO", &note);
+    }
+    if let Some(Err(e)) = write.map(SerialPoolWrite::persist) {
+        append_mass_runtime_log(host, "ERROR", &format!("serial pool not saved: {e}"));
+    }
+}
+
+/// 写入开始后序列号可能已经烧进设备，此后的失败无论策略如何都作废，只有连接、下载 stub
+/// 等写入前的失败才按策略归还
+fn settle_serial(
+    pool: &mut SerialPool,
+    value: &str,
+    is_success: bool,
+    write_started: bool,
+    policy: SerialFailurePolicy,
+) -> SerialSettlement {
+    if is_success {
+        return pool.commit(value).map(|write| ("committed", write));
+    }
+    match policy {
+        SerialFailurePolicy::Release if !write_started => {
+            pool.release(value).map(|write| ("released", write))
+        }
+        _ => pool.burn(value).map(|write| ("burned", write)),
+    }
+}
+
+/// 与序列号一样，只有写入前的失败才按策略归还设备序号
+fn settle_unit_index(
+    allocator: &mut UnitIndexAllocator,
+    index: u64,
+    is_success: bool,
+    write_started: bool,
+    policy: SerialFailurePolicy,
+) -> &'static str {
+    if is_success {
+        return "consumed";
+    }
+    match policy {
+        SerialFailurePolicy::Release if !write_started => {
+            allocator.release(index);
+            "released"
+        }
+        _ => "skipped",
+    }
+}
+
+/// 第 attempt 次尝试失败后距离下一次重试的等待时间；不可重试或次数用尽时返回 None
+fn retry_delay(
+    policy: &MassProductionRetryPolicy,
+    attempt: u32,
+    category: FlashErrorCategory,
+) -> Option<u64> {
+    if attempt >= policy.max_attempts
+        || category == FlashErrorCategory::Cancelled
+        || !policy.retry_on.contains(&category)
+    {
+        return None;
+    }
+    let factor = 1u64 << attempt.saturating_sub(1).min(16);
+    Some(
+        policy
+            .backoff_ms
+            .saturating_mul(factor)
+            .min(policy.max_backoff_ms),
+    )
+}
+
+/// 把到期的重试移入派发队列；期间被过滤或断开的端口已从 retry_queue 移除
+fn promote_due_retries(state: &mut MassProductionState, now: u64) {
+    let (due, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut state.retry_queue)
+        .into_iter()
+        .partition(|(_, retry_at)| *retry_at <= now);
+    state.retry_queue = waiting;
+
+    for (port_name, _) in due {
+        let is_waiting = state
+            .ports
+            .get(&port_name)
+            .is_some_and(|port| port.is_allowed && port.status == MassProductionPortStatus::Queued);
+        if is_waiting && !state.queue.contains(&port_name) {
+            state.queue.push_back(port_name);
+        }
+    }
+}
+
+fn dispatch_workers<H: MassProductionHost>(host: &H, state: &Arc<Mutex<MassProductionState>>) {
     let mut tasks: Vec<(String, MassProductionStartRequest, u64)> = Vec::new();
 
     {
         let mut locked = state.lock().unwrap();
-        let Some(request) = locked.request.clone() else {
+        let Some(mut request) = locked.request.clone() else {
+            return;
+        };
+        let Some(frozen_firmware) = locked.frozen_firmware.as_ref() else {
             return;
         };
+        request.files = frozen_firmware.write_files();
 
         if !locked.running {
             return;
@@ -1046,47 +1654,51 @@ fn dispatch_workers<R: tauri::Runtime>(
     }
 
     for (port_name, request, session_id) in tasks {
-        let app_handle_clone = app_handle.clone();
+        let host_clone = host.clone();
         let state_clone = state.clone();
-        thread::spawn(move || {
-            run_worker(
-                app_handle_clone,
-                state_clone,
-                request,
-                port_name,
-                session_id,
-            )
-        });
+        thread::spawn(move || run_worker(host_clone, state_clone, request, port_name, session_id));
     }
 }
 
-fn supervisor_loop(app_handle: AppHandle, state: Arc<Mutex<MassProductionState>>) {
+fn supervisor_loop<H: MassProductionHost>(host: H, state: Arc<Mutex<MassProductionState>>) {
     loop {
         let should_continue = { state.lock().unwrap().running };
         if !should_continue {
             break;
         }
 
+        let frozen_check = {
+            let mut locked = state.lock().unwrap();
+            locked
+                .frozen_firmware
+                .as_mut()
+                .map_or(Ok(()), |frozen| frozen.check_originals())
+        };
+        if let Err(reason) = frozen_check {
+            abort_mass_production(&host, &state, reason);
+            break;
+        }
+
         if let Err(e) = {
             let mut locked = state.lock().unwrap();
-            scan_ports(&mut locked, false)
+            let scanned = scan_ports(&mut locked, false);
+            promote_due_retries(&mut locked, now_millis());
+            scanned
         } {
-            append_mass_runtime_log(
-                &app_handle,
-                "ERROR",
-                &format!("supervisor scan failed: {e}"),
-            );
+            append_mass_runtime_log(&host, "ERROR", &format!("supervisor scan failed: {e}"));
             eprintln!("Mass production scan failed: {e}");
         }
 
-        dispatch_workers(&app_handle, &state);
+        flush_retry_settlements(&host, &state);
+        dispatch_workers(&host, &state);
 
         let snapshot = { state.lock().unwrap().to_snapshot() };
-        emit_snapshot(&app_handle, &snapshot);
+        host.emit_snapshot(&snapshot);
 
         thread::sleep(Duration::from_millis(SCAN_INTERVAL_MS));
     }
 
+    flush_retry_settlements(&host, &state);
     {
         let mut locked = state.lock().unwrap();
         if locked.active_ports.is_empty() {
@@ -1095,7 +1707,48 @@ fn supervisor_loop(app_handle: AppHandle, state: Arc<Mutex<MassProductionState>>
     }
 
     let snapshot = { state.lock().unwrap().to_snapshot() };
-    emit_snapshot(&app_handle, &snapshot);
+    host.emit_snapshot(&snapshot);
+}
+
+/// 中止会话：不再派发新任务，排队中的端口标记为失败；进行中的任务写入的是锁定副本，允许其完成
+fn abort_mass_production<H: MassProductionHost>(
+    host: &H,
+    state: &Arc<Mutex<MassProductionState>>,
+    reason: String,
+) {
+    append_mass_runtime_log(host, "ERROR", &format!("session aborted: {reason}"));
+
+    let mut locked = state.lock().unwrap();
+    locked.running = false;
+    locked.pending_trigger_flash = false;
+    let mut queued_ports: Vec<String> = locked.queue.drain(..).collect();
+    queued_ports.extend(locked.retry_queue.drain(..).map(|(port_name, _)| port_name));
+    let now = now_millis();
+    for port_name in queued_ports {
+        let mut did_fail = false;
+        if let Some(port) = locked.ports.get_mut(&port_name) {
+            if port.status == MassProductionPortStatus::Queued {
+                port.status = MassProductionPortStatus::Error;
+                port.progress = 0;
+                port.message = Some(reason.clone());
+                port.task_finished_at = Some(now);
+                did_fail = true;
+            }
+        }
+        if did_fail {
+            locked.failed_count = locked.failed_count.saturating_add(1);
+        }
+    }
+    settle_all_retry_holds(&mut locked);
+    locked.abort_reason = Some(reason);
+}
+
+/// 会话结束时不会再有重试，结算所有端口保留的设备序号和序列号
+fn settle_all_retry_holds(state: &mut MassProductionState) {
+    let held: Vec<String> = state.retry_holds.keys().cloned().collect();
+    for port_name in held {
+        settle_retry_hold(state, &port_name);
+    }
 }
 
 fn with_mass_state(
@@ -1106,6 +1759,10 @@ fn with_mass_state(
 }
 
 fn release_connected_tool_for_mass_production(app_state: &mut AppState) -> Result<bool, String> {
+    if let Some(running) = app_state.running_operation {
+        return Err(format!("普通模式设备正在{running}，请等待完成后再启动量产"));
+    }
+
     let Some(tool) = app_state.sftool.as_ref() else {
         return Ok(false);
     };
@@ -1129,27 +1786,12 @@ fn release_connected_tool_for_mass_production(app_state: &mut AppState) -> Resul
     Ok(true)
 }
 
-#[tauri::command]
-pub async fn mass_production_start(
-    app_handle: AppHandle,
-    state: State<'_, Mutex<AppState>>,
+/// 校验量产请求并确认当前没有正在运行的会话，同时回收已结束会话的监督线程
+pub fn prepare_mass_production_start(
+    mass_state: &Arc<Mutex<MassProductionState>>,
     request: MassProductionStartRequest,
-) -> Result<MassProductionSnapshot, String> {
+) -> Result<MassProductionStartRequest, String> {
     let request = sanitize_request(request)?;
-    append_mass_runtime_log(
-        &app_handle,
-        "INFO",
-        &format!(
-            "start requested: chip_model={} memory_type={} files={} auto_download={} max_concurrency={}",
-            request.chip_model,
-            request.memory_type,
-            request.files.len(),
-            request.auto_download,
-            request.max_concurrency
-        ),
-    );
-
-    let mass_state = with_mass_state(&state)?;
 
     let stale_supervisor_handle = {
         let mut locked = mass_state.lock().unwrap();
@@ -1168,20 +1810,26 @@ pub async fn mass_production_start(
         let _ = handle.join();
     }
 
-    let initial_ports = enumerate_ports()?;
+    Ok(request)
+}
 
-    let released_regular_connection = {
-        let mut app_state = state.lock().map_err(|e| format!("获取应用状态失败: {e}"))?;
-        release_connected_tool_for_mass_production(&mut app_state)?
+/// 启动新的量产会话：扫描端口、派发首批任务并启动监督线程
+///
+/// 调用前需先通过 prepare_mass_production_start 校验请求
+pub fn start_mass_production<H: MassProductionHost>(
+    host: &H,
+    mass_state: &Arc<Mutex<MassProductionState>>,
+    request: MassProductionStartRequest,
+) -> Result<MassProductionSnapshot, String> {
+    let initial_ports = enumerate_ports()?;
+    let frozen_firmware = freeze_firmware_files(&request.files)?;
+    let unit_data = match (&request.unit_data, request.mode) {
+        (Some(template), MassProductionMode::Write) => {
+            Some(UnitDataRenderer::new(template.clone())?)
+        }
+        _ => None,
     };
-
-    if released_regular_connection {
-        append_mass_runtime_log(
-            &app_handle,
-            "INFO",
-            "released regular device connection before mass production start",
-        );
-    }
+    let csv_rows = unit_data.as_ref().and_then(UnitDataRenderer::capacity);
 
     {
         let mut locked = mass_state.lock().unwrap();
@@ -1193,19 +1841,58 @@ pub async fn mass_production_start(
             return Err("仍有端口任务在执行，请稍后再启动新的量产会话".to_string());
         }
 
+        // 上个会话的 worker 可能仍在写盘，其较旧的内容不会覆盖打开时写入的状态
+        let serial_pool = match (&request.serial, request.mode) {
+            (Some(serial), MassProductionMode::Write) => {
+                let (pool, stale) =
+                    SerialPool::open(&serial_pool_dir(&host.data_dir()?), &serial.pool)?;
+                if !stale.is_empty() {
+                    append_mass_runtime_log(
+                        host,
+                        "WARN",
+                        &format!(
+                            "serial pool {}: burned {} reservation(s) left by an unfinished session: {}",
+                            pool.name(),
+                            stale.len(),
+                            stale.join(", ")
+                        ),
+                    );
+                }
+                if pool.status().remaining == 0 {
+                    return Err(format!("序列号池 {} 已耗尽", pool.name()));
+                }
+                Some(pool)
+            }
+            _ => None,
+        };
+
         let session_id = locked.session_id.saturating_add(1);
-        locked.reset_for_start(request.clone(), session_id, now_millis());
+        locked.reset_for_start(
+            request,
+            frozen_firmware,
+            unit_data,
+            serial_pool,
+            session_id,
+            now_millis(),
+        );
         for port in initial_ports {
             locked.ports.insert(port.name.clone(), port);
         }
         scan_ports(&mut locked, true)?;
     }
 
-    dispatch_workers(&app_handle, &mass_state);
+    if let Some(rows) = csv_rows {
+        append_mass_runtime_log(
+            host,
+            "INFO",
+            &format!("per-unit data: {rows} CSV row(s) available"),
+        );
+    }
+    dispatch_workers(host, mass_state);
 
     let state_for_thread = mass_state.clone();
-    let app_handle_for_thread = app_handle.clone();
-    let handle = thread::spawn(move || supervisor_loop(app_handle_for_thread, state_for_thread));
+    let host_for_thread = host.clone();
+    let handle = thread::spawn(move || supervisor_loop(host_for_thread, state_for_thread));
 
     {
         let mut locked = mass_state.lock().unwrap();
@@ -1213,17 +1900,16 @@ pub async fn mass_production_start(
     }
 
     let snapshot = { mass_state.lock().unwrap().to_snapshot() };
-    emit_snapshot(&app_handle, &snapshot);
+    host.emit_snapshot(&snapshot);
     Ok(snapshot)
 }
 
-#[tauri::command]
-pub async fn mass_production_stop(
-    app_handle: AppHandle,
-    state: State<'_, Mutex<AppState>>,
-) -> Result<MassProductionSnapshot, String> {
-    append_mass_runtime_log(&app_handle, "INFO", "stop requested");
-    let mass_state = with_mass_state(&state)?;
+/// 停止量产会话：取消进行中的任务、清空队列并等待监督线程退出
+pub fn stop_mass_production<H: MassProductionHost>(
+    host: &H,
+    mass_state: &Arc<Mutex<MassProductionState>>,
+) -> MassProductionSnapshot {
+    append_mass_runtime_log(host, "INFO", "stop requested");
 
     let handle = {
         let mut locked = mass_state.lock().unwrap();
@@ -1233,7 +1919,8 @@ pub async fn mass_production_stop(
         for token in locked.active_cancel_tokens.values() {
             token.cancel();
         }
-        let queued_ports: Vec<String> = locked.queue.drain(..).collect();
+        let mut queued_ports: Vec<String> = locked.queue.drain(..).collect();
+        queued_ports.extend(locked.retry_queue.drain(..).map(|(port_name, _)| port_name));
         let now = now_millis();
         for port_name in queued_ports {
             let mut did_cancel = false;
@@ -1250,6 +1937,7 @@ pub async fn mass_production_stop(
                 locked.cancelled_count = locked.cancelled_count.saturating_add(1);
             }
         }
+        settle_all_retry_holds(&mut locked);
         if locked.active_ports.is_empty() {
             locked.ended_at = Some(now_millis());
         }
@@ -1259,6 +1947,7 @@ pub async fn mass_production_stop(
     if let Some(join_handle) = handle {
         let _ = join_handle.join();
     }
+    flush_retry_settlements(host, mass_state);
 
     for _ in 0..20 {
         if mass_state.lock().unwrap().active_ports.is_empty() {
@@ -1268,21 +1957,16 @@ pub async fn mass_production_stop(
     }
 
     let snapshot = { mass_state.lock().unwrap().to_snapshot() };
-    emit_snapshot(&app_handle, &snapshot);
-    Ok(snapshot)
+    host.emit_snapshot(&snapshot);
+    snapshot
 }
 
-pub fn mass_production_handle_hotplug_event<R: tauri::Runtime>(
-    app_handle: &AppHandle<R>,
+/// 处理 USB 热插拔事件：记录新接入设备的身份并重新扫描端口
+pub fn apply_mass_production_hotplug<H: MassProductionHost>(
+    host: &H,
+    mass_state: &Arc<Mutex<MassProductionState>>,
     connected_identities: Vec<PortIdentity>,
 ) {
-    let app_state = app_handle.state::<Mutex<AppState>>();
-    let Ok(app_state) = app_state.lock() else {
-        return;
-    };
-    let mass_state = app_state.mass_production.clone();
-    drop(app_state);
-
     {
         let mut locked = mass_state.lock().unwrap();
         if !locked.running {
@@ -1290,34 +1974,28 @@ pub fn mass_production_handle_hotplug_event<R: tauri::Runtime>(
         }
         locked.hotplug_connected.extend(connected_identities);
         if let Err(error) = scan_ports(&mut locked, false) {
-            append_mass_runtime_log(
-                app_handle,
-                "ERROR",
-                &format!("hotplug rescan failed: {error}"),
-            );
+            append_mass_runtime_log(host, "ERROR", &format!("hotplug rescan failed: {error}"));
             return;
         }
     }
 
-    dispatch_workers(app_handle, &mass_state);
+    dispatch_workers(host, mass_state);
     let snapshot = { mass_state.lock().unwrap().to_snapshot() };
-    emit_snapshot(app_handle, &snapshot);
+    host.emit_snapshot(&snapshot);
 }
 
-#[tauri::command]
-pub async fn mass_production_refresh(
-    app_handle: AppHandle,
-    state: State<'_, Mutex<AppState>>,
+/// 重新扫描端口，trigger_flash 为 true 时对就绪端口触发一次下载
+pub fn refresh_mass_production<H: MassProductionHost>(
+    host: &H,
+    mass_state: &Arc<Mutex<MassProductionState>>,
     trigger_flash: bool,
 ) -> Result<MassProductionSnapshot, String> {
     append_mass_runtime_log(
-        &app_handle,
+        host,
         "INFO",
         &format!("refresh requested: trigger_flash={trigger_flash}"),
     );
 
-    let mass_state = with_mass_state(&state)?;
-
     {
         let mut locked = mass_state.lock().unwrap();
         if trigger_flash {
@@ -1326,27 +2004,25 @@ pub async fn mass_production_refresh(
         scan_ports(&mut locked, trigger_flash)?;
     }
 
-    dispatch_workers(&app_handle, &mass_state);
+    dispatch_workers(host, mass_state);
 
     let snapshot = { mass_state.lock().unwrap().to_snapshot() };
-    emit_snapshot(&app_handle, &snapshot);
+    host.emit_snapshot(&snapshot);
     Ok(snapshot)
 }
 
-#[tauri::command]
-pub async fn mass_production_set_auto_download(
-    app_handle: AppHandle,
-    state: State<'_, Mutex<AppState>>,
+/// 更新插入自动下载开关，关闭时把排队中的端口退回就绪状态
+pub fn set_mass_production_auto_download<H: MassProductionHost>(
+    host: &H,
+    mass_state: &Arc<Mutex<MassProductionState>>,
     auto_download: bool,
 ) -> Result<MassProductionSnapshot, String> {
     append_mass_runtime_log(
-        &app_handle,
+        host,
         "INFO",
         &format!("set auto_download requested: auto_download={auto_download}"),
     );
 
-    let mass_state = with_mass_state(&state)?;
-
     {
         let mut locked = mass_state.lock().unwrap();
         let Some(request) = locked.request.as_mut() else {
@@ -1373,13 +2049,114 @@ pub async fn mass_production_set_auto_download(
         scan_ports(&mut locked, false)?;
     }
 
-    dispatch_workers(&app_handle, &mass_state);
+    dispatch_workers(host, mass_state);
 
     let snapshot = { mass_state.lock().unwrap().to_snapshot() };
-    emit_snapshot(&app_handle, &snapshot);
+    host.emit_snapshot(&snapshot);
     Ok(snapshot)
 }
 
+/// 从应用状态中取出量产状态，供 Tauri 命令以外的入口（如本地控制接口）使用
+pub fn mass_production_state_of<R: tauri::Runtime>(
+    app_handle: &AppHandle<R>,
+) -> Result<Arc<Mutex<MassProductionState>>, String> {
+    let app_state = app_handle.state::<Mutex<AppState>>();
+    let app_state = app_state
+        .lock()
+        .map_err(|e| format!("获取应用状态失败: {e}"))?;
+    Ok(app_state.mass_production.clone())
+}
+
+/// GUI 环境下启动量产：校验请求、释放普通模式连接后启动会话
+pub fn start_mass_production_for_app<R: tauri::Runtime>(
+    app_handle: &AppHandle<R>,
+    request: MassProductionStartRequest,
+) -> Result<MassProductionSnapshot, String> {
+    let mass_state = mass_production_state_of(app_handle)?;
+    let request = prepare_mass_production_start(&mass_state, request)?;
+    append_mass_runtime_log(
+        app_handle,
+        "INFO",
+        &format!(
+            "start requested: chip_model={} memory_type={} files={} auto_download={} max_concurrency={}",
+            request.chip_model,
+            request.memory_type,
+            request.files.len(),
+            request.auto_download,
+            request.max_concurrency
+        ),
+    );
+
+    let released_regular_connection = {
+        let app_state = app_handle.state::<Mutex<AppState>>();
+        let mut app_state = app_state
+            .lock()
+            .map_err(|e| format!("获取应用状态失败: {e}"))?;
+        release_connected_tool_for_mass_production(&mut app_state)?
+    };
+
+    if released_regular_connection {
+        append_mass_runtime_log(
+            app_handle,
+            "INFO",
+            "released regular device connection before mass production start",
+        );
+    }
+
+    start_mass_production(app_handle, &mass_state, request)
+}
+
+#[tauri::command]
+pub async fn mass_production_start(
+    app_handle: AppHandle,
+    request: MassProductionStartRequest,
+) -> Result<MassProductionSnapshot, String> {
+    start_mass_production_for_app(&app_handle, request)
+}
+
+#[tauri::command]
+pub async fn mass_production_stop(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<MassProductionSnapshot, String> {
+    let mass_state = with_mass_state(&state)?;
+    Ok(stop_mass_production(&app_handle, &mass_state))
+}
+
+pub fn mass_production_handle_hotplug_event<R: tauri::Runtime>(
+    app_handle: &AppHandle<R>,
+    connected_identities: Vec<PortIdentity>,
+) {
+    let app_state = app_handle.state::<Mutex<AppState>>();
+    let Ok(app_state) = app_state.lock() else {
+        return;
+    };
+    let mass_state = app_state.mass_production.clone();
+    drop(app_state);
+
+    apply_mass_production_hotplug(app_handle, &mass_state, connected_identities);
+}
+
+#[tauri::command]
+pub async fn mass_production_refresh(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    trigger_flash: bool,
+) -> Result<MassProductionSnapshot, String> {
+    let mass_state = with_mass_state(&state)?;
+    refresh_mass_production(&app_handle, &mass_state, trigger_flash)
+}
+
+#[tauri::command]
+pub async fn mass_production_set_auto_download(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    auto_download: bool,
+) -> Result<MassProductionSnapshot, String> {
+    let mass_state = with_mass_state(&state)?;
+    set_mass_production_auto_download(&app_handle, &mass_state, auto_download)
+}
+
 #[tauri::command]
 pub async fn mass_production_get_snapshot(
     state: State<'_, Mutex<AppState>>,
@@ -1432,6 +2209,78 @@ pub async fn mass_production_open_log_directory(app_handle: AppHandle) -> Result
     Ok(())
 }
 
+/// 导入号段或序列号列表；正在使用该池的量产会话结束前不允许修改
+#[tauri::command]
+pub async fn serial_pool_import(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    request: SerialPoolImportRequest,
+) -> Result<SerialPoolStatus, String> {
+    let mass_state = with_mass_state(&state)?;
+    let locked = mass_state.lock().unwrap();
+    let in_use = (locked.running || !locked.active_ports.is_empty())
+        && locked
+            .serial_pool
+            .as_ref()
+            .is_some_and(|pool| pool.name() == request.name);
+    if in_use {
+        return Err(format!("序列号池 {} 正在被量产会话使用", request.name));
+    }
+
+    import_serial_pool(&serial_pool_dir(&app_handle.data_dir()?), &request)
+}
+
+/// 查询序列号池剩余容量；当前会话正在使用的池直接返回内存中的状态
+#[tauri::command]
+pub async fn serial_pool_get_status(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    name: String,
+) -> Result<SerialPoolStatus, String> {
+    let mass_state = with_mass_state(&state)?;
+    if let Some(pool) = mass_state
+        .lock()
+        .unwrap()
+        .serial_pool
+        .as_ref()
+        .filter(|pool| pool.name() == name)
+    {
+        return Ok(pool.status());
+    }
+
+    load_serial_pool_status(&serial_pool_dir(&app_handle.data_dir()?), &name)
+}
+
+#[tauri::command]
+pub async fn serial_pool_list(app_handle: AppHandle) -> Result<Vec<SerialPoolStatus>, String> {
+    list_serial_pools(&serial_pool_dir(&app_handle.data_dir()?))
+}
+
+/// 按条件查询追溯记录
+#[tauri::command]
+pub async fn trace_query_records(
+    app_handle: AppHandle,
+    query: TraceQuery,
+) -> Result<TraceQueryResult, String> {
+    query_trace_records(&trace_records_path(&app_handle.data_dir()?), &query)
+}
+
+/// 将筛选后的追溯记录导出为 CSV 或 JSON，返回导出的条数
+#[tauri::command]
+pub async fn trace_export_records(
+    app_handle: AppHandle,
+    query: TraceQuery,
+    format: TraceExportFormat,
+    output_path: String,
+) -> Result<usize, String> {
+    export_trace_records(
+        &trace_records_path(&app_handle.data_dir()?),
+        &query,
+        format,
+        &output_path,
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1453,6 +2302,8 @@ mod tests {
             last_seen_at: 0,
             task_started_at: None,
             task_finished_at: None,
+            error_category: None,
+            attempt: 0,
         }
     }
 
@@ -1531,4 +2382,192 @@ mod tests {
         assert!(consume_hotplug_reconnect_candidate(&mut state, &scanned));
         assert!(state.hotplug_connected.is_empty());
     }
+
+    #[test]
+    fn retry_delay_backs_off_until_attempts_are_exhausted() {
+        let policy = MassProductionRetryPolicy {
+            max_attempts: 4,
+            backoff_ms: 500,
+            max_backoff_ms: 1500,
+            retry_on: vec![FlashErrorCategory::Sync],
+            retry_baud_rate: None,
+        };
+
+        assert_eq!(retry_delay(&policy, 1, FlashErrorCategory::Sync), Some(500));
+        assert_eq!(
+            retry_delay(&policy, 2, FlashErrorCategory::Sync),
+            Some(1000)
+        );
+        assert_eq!(
+            retry_delay(&policy, 3, FlashErrorCategory::Sync),
+            Some(1500)
+        );
+        assert_eq!(retry_delay(&policy, 4, FlashErrorCategory::Sync), None);
+        assert_eq!(retry_delay(&policy, 1, FlashErrorCategory::Write), None);
+    }
+
+    #[test]
+    fn due_retries_move_to_dispatch_queue() {
+        let mut state = MassProductionState::default();
+        state.ports.insert(
+            "COM1".to_string(),
+            test_port("COM1", MassProductionPortStatus::Queued),
+        );
+        state.ports.insert(
+            "COM2".to_string(),
+            test_port("COM2", MassProductionPortStatus::Queued),
+        );
+        state.retry_queue = vec![("COM1".to_string(), 1_000), ("COM2".to_string(), 3_000)];
+
+        promote_due_retries(&mut state, 2_000);
+        assert_eq!(state.queue, ["COM1"]);
+        assert_eq!(state.retry_queue, [("COM2".to_string(), 3_000)]);
+    }
+
+    #[test]
+    fn serial_is_burned_when_failure_follows_write() {
+        let dir = tempfile::tempdir().unwrap();
+        import_serial_pool(
+            dir.path(),
+            &SerialPoolImportRequest {
+                name: "line1".to_string(),
+                ranges: Vec::new(),
+                values: vec!["SN1".to_string(), "SN2".to_string()],
+                list_path: None,
+            },
+        )
+        .unwrap();
+        let (mut pool, _) = SerialPool::open(dir.path(), "line1").unwrap();
+        let release = SerialFailurePolicy::Release;
+
+        // 连接失败，序列号尚未写入设备，可以归还
+        let (first, _) = pool.reserve("COM3", 0).unwrap();
+        assert_eq!(
+            settle_serial(&mut pool, &first, false, false, release)
+                .unwrap()
+                .0,
+            "released"
+        );
+
+        // 写入后复位或校验失败，序列号可能已在设备上，不能再分给下一台
+        let (second, _) = pool.reserve("COM4", 0).unwrap();
+        assert_eq!(second, first);
+        assert_eq!(
+            settle_serial(&mut pool, &second, false, true, release)
+                .unwrap()
+                .0,
+            "burned"
+        );
+        assert_eq!(pool.reserve("COM5", 0).unwrap().0, "SN2");
+        assert_eq!(pool.status().burned, 1);
+        assert_eq!(pool.status().released, 0);
+    }
+
+    #[test]
+    fn retried_board_keeps_its_serial_and_unit_row() {
+        let dir = tempfile::tempdir().unwrap();
+        import_serial_pool(
+            dir.path(),
+            &SerialPoolImportRequest {
+                name: "line1".to_string(),
+                ranges: Vec::new(),
+                values: vec!["SN1".to_string(), "SN2".to_string()],
+                list_path: None,
+            },
+        )
+        .unwrap();
+        let mut state = MassProductionState {
+            serial_pool: Some(SerialPool::open(dir.path(), "line1").unwrap().0),
+            ..MassProductionState::default()
+        };
+        let outcome = |reservation: &AttemptReservation, is_success, retrying| AttemptOutcome {
+            unit_index: None,
+            serial: reservation.serial.clone().unwrap(),
+            serial_saved: true,
+            is_success,
+            retrying,
+            write_started: true,
+        };
+
+        // 写入后失败并安排重试，序列号留给同一块板子，不作废
+        let first = reserve_for_attempt(&mut state, "COM3", 0);
+        assert_eq!(first.serial, Ok(Some("SN1".to_string())));
+        let settlement = settle_attempt(&mut state, "COM3", outcome(&first, false, true));
+        assert!(settlement.serial.is_none());
+        assert_eq!(settlement.notes, ["serial SN1 held for retry"]);
+
+        // 其他端口拿到下一个序列号，重试沿用 SN1 并保留写入状态
+        let other = reserve_for_attempt(&mut state, "COM4", 0);
+        assert_eq!(other.serial, Ok(Some("SN2".to_string())));
+        let retry = reserve_for_attempt(&mut state, "COM3", 0);
+        assert_eq!(retry.serial, Ok(Some("SN1".to_string())));
+        assert!(retry.serial_write.is_none());
+        assert!(retry.write_started);
+
+        let settlement = settle_attempt(&mut state, "COM3", outcome(&retry, true, false));
+        let (value, settled) = settlement.serial.unwrap();
+        assert_eq!(value, "SN1");
+        assert_eq!(settled.unwrap().0, "committed");
+        let status = state.serial_pool.as_ref().unwrap().status();
+        assert_eq!(status.committed, 1);
+        assert_eq!(status.burned, 0);
+        assert_eq!(status.reserved, 1);
+        assert!(state.retry_holds.is_empty());
+    }
+
+    #[test]
+    fn unit_row_is_kept_for_retry_and_skipped_after_write() {
+        let mut state = MassProductionState {
+            unit_indices: UnitIndexAllocator::new(Some(4)),
+            ..MassProductionState::default()
+        };
+        let release = SerialFailurePolicy::Release;
+        let failed = |unit_index, retrying, write_started| AttemptOutcome {
+            unit_index: Some(unit_index),
+            serial: None,
+            serial_saved: true,
+            is_success: false,
+            retrying,
+            write_started,
+        };
+
+        // 重试的板子沿用同一行，不会换成下一行的 MAC
+        let first = state.unit_indices.allocate().unwrap();
+        let settlement = settle_attempt(&mut state, "COM3", failed(first, true, true));
+        assert_eq!(settlement.notes, ["unit #0 held for retry"]);
+        assert_eq!(state.unit_indices.allocate().unwrap(), 1);
+        let hold = state.retry_holds.remove("COM3").unwrap();
+        assert_eq!(hold.unit_index, Some(first));
+
+        // 写入前失败按策略归还，写入后失败的行不再使用
+        assert_eq!(
+            settle_unit_index(&mut state.unit_indices, 1, false, false, release),
+            "released"
+        );
+        assert_eq!(
+            settle_unit_index(&mut state.unit_indices, first, false, true, release),
+            "skipped"
+        );
+        assert_eq!(state.unit_indices.allocate().unwrap(), 1);
+        assert_eq!(state.unit_indices.allocate().unwrap(), 2);
+    }
+
+    #[test]
+    fn dropped_port_settles_its_retry_hold() {
+        let mut state = MassProductionState::default();
+        state.retry_holds.insert(
+            "COM3".to_string(),
+            RetryHold {
+                unit_index: Some(0),
+                serial: None,
+                write_started: true,
+            },
+        );
+        state.retry_queue = vec![("COM3".to_string(), 1_000)];
+
+        drop_queued_port(&mut state, "COM3");
+        assert!(state.retry_queue.is_empty());
+        assert!(state.retry_holds.is_empty());
+        assert_eq!(state.settlement_notes, ["COM3: unit #0 skipped"]);
+    }
 }
@@ -1,11 +1,19 @@
 pub mod archive;
 pub mod config;
 pub mod device;
+pub mod firmware;
 pub mod flash;
 pub mod mass_production;
+pub mod package;
+pub mod partition;
+pub mod remote_api;
 
 pub use archive::*;
 pub use config::*;
 pub use device::*;
+pub use firmware::*;
 pub use flash::*;
 pub use mass_production::*;
+pub use package::*;
+pub use partition::*;
+pub use remote_api::*;
@@ -0,0 +1,104 @@
+use crate::commands::resolve_sftool_param_config;
+use crate::state::AppState;
+use crate::types::{SfpkgBuildRequest, SfpkgInfo, SftoolParamParseResult};
+use crate::utils::{
+    build_package, extract_package_to_temp_dir, generate_package_signing_key,
+    load_trusted_package_keys, load_verified_manifest, parse_trusted_package_keys,
+    read_package_info, save_trusted_package_keys, TRUSTED_PACKAGE_KEYS_FILENAME,
+};
+use ed25519_dalek::VerifyingKey;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+fn trusted_keys_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    app_handle
+        .path()
+        .app_config_dir()
+        .map(|dir| dir.join(TRUSTED_PACKAGE_KEYS_FILENAME))
+        .map_err(|e| format!("获取配置目录失败: {}", e))
+}
+
+/// 导入固件包时用于校验清单签名的公钥
+pub fn trusted_package_keys(app_handle: &AppHandle) -> Result<Vec<VerifyingKey>, String> {
+    parse_trusted_package_keys(&load_trusted_package_keys(&trusted_keys_path(app_handle)?)?)
+}
+
+#[tauri::command]
+pub async fn build_firmware_package(
+    app_handle: AppHandle,
+    request: SfpkgBuildRequest,
+) -> Result<SfpkgInfo, String> {
+    build_package(&request)?;
+    read_package_info(&request.output_path, &trusted_package_keys(&app_handle)?)
+}
+
+#[tauri::command]
+pub async fn inspect_firmware_package(
+    app_handle: AppHandle,
+    package_path: String,
+) -> Result<SfpkgInfo, String> {
+    read_package_info(&package_path, &trusted_package_keys(&app_handle)?)
+}
+
+/// 解压并校验固件包，返回与 parse_sftool_param_file 相同的解析结果
+#[tauri::command]
+pub async fn import_firmware_package(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    package_path: String,
+    current_chip: Option<String>,
+    current_memory: Option<String>,
+) -> Result<SftoolParamParseResult, String> {
+    let trusted_keys = trusted_package_keys(&app_handle)?;
+    let package_dir = extract_package_to_temp_dir(&package_path)?;
+
+    // 将临时目录注册到 AppState，以便在应用退出时统一清理
+    {
+        let mut app_state = state
+            .lock()
+            .map_err(|e| format!("获取应用状态失败: {}", e))?;
+        app_state.register_temp_dir(package_dir.clone());
+    }
+
+    resolve_package_dir(&package_dir, &trusted_keys, current_chip, current_memory)
+}
+
+/// 生成签名私钥文件，返回需要加入受信任列表的公钥
+#[tauri::command]
+pub async fn create_package_signing_key(output_path: String) -> Result<String, String> {
+    generate_package_signing_key(&output_path)
+}
+
+#[tauri::command]
+pub async fn get_package_trusted_keys(app_handle: AppHandle) -> Result<Vec<String>, String> {
+    load_trusted_package_keys(&trusted_keys_path(&app_handle)?)
+}
+
+#[tauri::command]
+pub async fn set_package_trusted_keys(
+    app_handle: AppHandle,
+    keys: Vec<String>,
+) -> Result<Vec<String>, String> {
+    save_trusted_package_keys(&trusted_keys_path(&app_handle)?, &keys)
+}
+
+/// 校验已解压固件包中的清单签名与文件摘要，并解析为烧录配置
+pub fn resolve_package_dir(
+    package_dir: &Path,
+    trusted_keys: &[VerifyingKey],
+    current_chip: Option<String>,
+    current_memory: Option<String>,
+) -> Result<SftoolParamParseResult, String> {
+    let manifest = load_verified_manifest(package_dir, trusted_keys)?;
+    let stub_config_path = manifest
+        .stub_config
+        .as_ref()
+        .map(|path| package_dir.join(path).to_string_lossy().to_string());
+
+    let mut result =
+        resolve_sftool_param_config(manifest.config, package_dir, current_chip, current_memory)?;
+    result.stub_config_path = stub_config_path;
+
+    Ok(result)
+}
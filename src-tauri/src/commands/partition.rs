@@ -0,0 +1,33 @@
+use crate::types::{FileAddressRange, PartitionInfo, WriteFlashFileInfo};
+use crate::utils::{
+    check_images_fit, find_partition, load_partition_table_file, resolve_write_ranges,
+};
+
+/// 读取 SDK 分区表（ptab.json），返回所有区域的地址与大小
+#[tauri::command]
+pub async fn load_partition_table(path: String) -> Result<Vec<PartitionInfo>, String> {
+    load_partition_table_file(&path)
+}
+
+/// 按分区名查找地址与大小，供读取、区域擦除及写入计划使用
+#[tauri::command]
+pub async fn resolve_partition(path: String, name: String) -> Result<PartitionInfo, String> {
+    let partitions = load_partition_table_file(&path)?;
+    find_partition(&partitions, &name).cloned()
+}
+
+/// 检查写入列表中的每段镜像都落在所属分区内，返回解析出的地址范围
+#[tauri::command]
+pub async fn check_partition_fit(
+    path: String,
+    files: Vec<WriteFlashFileInfo>,
+) -> Result<Vec<FileAddressRange>, String> {
+    let partitions = load_partition_table_file(&path)?;
+    let ranges = resolve_write_ranges(
+        files
+            .iter()
+            .map(|file| (file.file_path.as_str(), file.address)),
+    )?;
+    check_images_fit(&partitions, &ranges)?;
+    Ok(ranges)
+}
@@ -0,0 +1,52 @@
+use crate::remote::{RemoteApiServer, DEFAULT_REMOTE_API_PORT};
+use crate::state::AppState;
+use crate::types::RemoteApiStatus;
+use std::sync::Mutex;
+use tauri::{AppHandle, State};
+
+fn remote_api_status(app_state: &AppState) -> RemoteApiStatus {
+    RemoteApiStatus {
+        is_running: app_state.remote_api.is_some(),
+        bind_address: app_state
+            .remote_api
+            .as_ref()
+            .map(|server| server.address().to_string()),
+    }
+}
+
+#[tauri::command]
+pub fn remote_api_start(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    port: Option<u16>,
+    token: String,
+) -> Result<RemoteApiStatus, String> {
+    let mut app_state = state.lock().map_err(|e| format!("获取应用状态失败: {e}"))?;
+
+    // 重新启动时先停止旧的监听，允许修改端口和令牌
+    if let Some(server) = app_state.remote_api.take() {
+        server.stop();
+    }
+
+    let server =
+        RemoteApiServer::start(app_handle, port.unwrap_or(DEFAULT_REMOTE_API_PORT), token)?;
+    app_state.remote_api = Some(server);
+
+    Ok(remote_api_status(&app_state))
+}
+
+#[tauri::command]
+pub fn remote_api_stop(state: State<'_, Mutex<AppState>>) -> Result<RemoteApiStatus, String> {
+    let mut app_state = state.lock().map_err(|e| format!("获取应用状态失败: {e}"))?;
+    if let Some(server) = app_state.remote_api.take() {
+        server.stop();
+    }
+
+    Ok(remote_api_status(&app_state))
+}
+
+#[tauri::command]
+pub fn remote_api_get_status(state: State<'_, Mutex<AppState>>) -> Result<RemoteApiStatus, String> {
+    let app_state = state.lock().map_err(|e| format!("获取应用状态失败: {e}"))?;
+    Ok(remote_api_status(&app_state))
+}
@@ -0,0 +1,641 @@
+use crate::types::{
+    DeviceConfig, MassProductionFilterField, MassProductionFilterRule, MassProductionMode,
+    MassProductionRetryPolicy, MassProductionSerialConfig, MassProductionWriteFileInfo,
+    PartitionInfo, ReadFlashFileInfo, ReadFlashRequest, SerialFailurePolicy,
+    SerialPoolImportRequest, SerialRange, UnitDataTemplate, WriteFlashFileInfo, WriteFlashRequest,
+};
+use crate::utils::{
+    check_images_fit, erase_granularity, find_partition, load_partition_table_file, parse_number,
+    resolve_write_ranges, validate_erase_regions,
+};
+use sftool_lib::EraseRegionFile;
+
+pub const USAGE: &str = "\
+用法: sftool-gui <子命令> [选项] [参数...]
+
+子命令:
+  write            写入固件          FILE[@ADDR|@PART][#HASH]...
+  read             读取 Flash        FILE@ADDR:SIZE|FILE@PART...
+  erase            擦除整片 Flash    --address ADDR
+  erase-region     擦除区域          ADDR:SIZE|PART...
+  mass-production  量产烧录          FILE[@ADDR|@PART][#HASH]...
+  serial-pool-import 新建序列号池或追加号段 NAME
+  help             显示本帮助
+
+HASH 为期望的 SHA-256 或 CRC32，可带 sha256: / crc32: 前缀，摘要不一致时拒绝写入
+
+设备选项:
+  -c, --chip <CHIP>            芯片型号，如 SF32LB52
+  -m, --memory <TYPE>          存储器类型 (nor/nand/sd)，默认 nor
+  -p, --port <PORT>            串口 (mass-production 不需要)
+  -b, --baud <BAUD>            波特率，默认 1000000
+      --stub-config <PATH>     Stub 配置 JSON
+      --external-stub <PATH>   外部 Stub 文件
+      --before <OP>            default_reset / no_reset / no_reset_no_sync，默认 default_reset
+      --after <OP>             soft_reset / no_reset，默认 no_reset
+      --ptab <PATH>            SDK 分区表 ptab.json；可用分区名 PART 代替地址，并检查镜像不超出分区
+
+写入选项:
+      --verify                 写入后校验
+      --no-compress            不压缩传输
+      --erase-all              写入前擦除全部
+      --no-address-check       不检查烧录地址是否超出 Flash 范围，用于未收录的芯片/存储器组合
+
+量产选项:
+      --verify-only            只回读校验已烧录的固件，不写入
+      --concurrency <N>        最大并发端口数，默认 8
+      --once                   仅烧录当前已连接的设备，完成后退出
+      --count <N>              完成 N 台后退出
+      --allow <FIELD=VALUE>    白名单规则，FIELD 为 vid_pid/serial_number/location_path/port_name
+      --deny <FIELD=VALUE>     黑名单规则
+      --log-dir <DIR>          量产日志目录，默认 ./sftool-logs
+      --unit-data <PATH>       出厂数据模板 JSON，为每台设备生成并写入独立数据
+      --serial-pool <NAME>     为每台设备预留序列号，池文件位于数据目录的 serial_pools 下
+      --serial-on-failure <P>  写入前失败的设备如何处理序列号 release/burn，默认 burn
+      --max-attempts <N>       同步超时、串口打开失败或断开时在原端口自动重试，共尝试 N 次
+      --retry-baud <BAUD>      重试时使用的波特率，默认沿用 --baud；单独使用时最多尝试 3 次
+      --data-dir <DIR>         序列号池和追溯记录所在目录，默认与 GUI 共用应用数据目录
+
+序列号池导入选项:
+      --serial-range <RANGE>   号段 [PREFIX:]START-END，按 START 的位数补零，如 SN:0001-0500
+      --serial-list <PATH>     每行一个序列号的文本文件，空行和 # 开头的行被忽略
+      --data-dir <DIR>         同量产选项；GUI 量产会话正在使用的池请在 GUI 中导入
+
+输出选项:
+      --json                   以 JSON Lines 格式输出进度与结果
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeviceArgs {
+    pub chip: String,
+    pub memory: String,
+    pub port: Option<String>,
+    pub baud: u32,
+    pub stub_config_path: String,
+    pub external_stub_path: String,
+    pub before_operation: String,
+    pub after_operation: String,
+    /// 关闭烧录地址范围检查
+    pub skip_address_check: bool,
+}
+
+impl DeviceArgs {
+    pub fn to_device_config(&self) -> Result<DeviceConfig, String> {
+        Ok(DeviceConfig {
+            chip_type: self.chip.clone(),
+            memory_type: self.memory.clone(),
+            port_name: self.port.clone().ok_or("缺少 --port 参数")?,
+            baud_rate: self.baud,
+            stub_config_path: self.stub_config_path.clone(),
+            external_stub_path: self.external_stub_path.clone(),
+            before_operation: self.before_operation.clone(),
+            after_operation: self.after_operation.clone(),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct MassProductionArgs {
+    pub files: Vec<MassProductionWriteFileInfo>,
+    pub mode: MassProductionMode,
+    pub verify: bool,
+    pub no_compress: bool,
+    pub erase_all: bool,
+    pub max_concurrency: u8,
+    pub once: bool,
+    pub count: Option<u32>,
+    pub whitelist: Vec<MassProductionFilterRule>,
+    pub blacklist: Vec<MassProductionFilterRule>,
+    pub log_dir: Option<String>,
+    /// 序列号池和追溯记录所在目录，None 时使用 GUI 的应用数据目录
+    pub data_dir: Option<String>,
+    pub unit_data: Option<UnitDataTemplate>,
+    pub serial: Option<MassProductionSerialConfig>,
+    pub retry: Option<MassProductionRetryPolicy>,
+}
+
+pub enum HeadlessCommand {
+    Write(WriteFlashRequest),
+    Read(ReadFlashRequest),
+    Erase {
+        address: u32,
+    },
+    EraseRegion(Vec<EraseRegionFile>),
+    MassProduction(MassProductionArgs),
+    SerialPoolImport {
+        request: SerialPoolImportRequest,
+        data_dir: Option<String>,
+    },
+    Help,
+}
+
+pub struct HeadlessArgs {
+    pub command: HeadlessCommand,
+    pub device: DeviceArgs,
+    pub format: OutputFormat,
+}
+
+pub fn is_headless_subcommand(arg: &str) -> bool {
+    matches!(
+        arg,
+        "write"
+            | "read"
+            | "erase"
+            | "erase-region"
+            | "mass-production"
+            | "serial-pool-import"
+            | "help"
+            | "--help"
+            | "-h"
+    )
+}
+
+pub fn parse_args(args: &[String]) -> Result<HeadlessArgs, String> {
+    let (subcommand, rest) = args.split_first().ok_or("缺少子命令")?;
+
+    let mut device = DeviceArgs {
+        chip: String::new(),
+        memory: "nor".to_string(),
+        port: None,
+        baud: 1_000_000,
+        stub_config_path: String::new(),
+        external_stub_path: String::new(),
+        before_operation: "default_reset".to_string(),
+        after_operation: "no_reset".to_string(),
+        skip_address_check: false,
+    };
+    let mut format = OutputFormat::Text;
+    let mut verify = false;
+    let mut mode = MassProductionMode::Write;
+    let mut no_compress = false;
+    let mut erase_all = false;
+    let mut address: Option<u32> = None;
+    let mut max_concurrency: u8 = 8;
+    let mut once = false;
+    let mut count: Option<u32> = None;
+    let mut whitelist = Vec::new();
+    let mut blacklist = Vec::new();
+    let mut log_dir: Option<String> = None;
+    let mut data_dir: Option<String> = None;
+    let mut ptab: Option<String> = None;
+    let mut unit_data_path: Option<String> = None;
+    let mut serial_pool: Option<String> = None;
+    let mut serial_on_failure = SerialFailurePolicy::default();
+    let mut max_attempts: Option<u32> = None;
+    let mut retry_baud: Option<u32> = None;
+    let mut serial_ranges = Vec::new();
+    let mut serial_list: Option<String> = None;
+    let mut positionals: Vec<String> = Vec::new();
+
+    let mut iter = rest.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| -> Result<String, String> {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("参数 {name} 缺少取值"))
+        };
+
+        match arg.as_str() {
+            "-c" | "--chip" => device.chip = value(arg)?,
+            "-m" | "--memory" => device.memory = value(arg)?,
+            "-p" | "--port" => device.port = Some(value(arg)?),
+            "-b" | "--baud" => {
+                let raw = value(arg)?;
+                device.baud = raw.parse().map_err(|_| format!("无效的波特率: {raw}"))?;
+            }
+            "--stub-config" => device.stub_config_path = value(arg)?,
+            "--external-stub" => device.external_stub_path = value(arg)?,
+            "--before" => device.before_operation = value(arg)?,
+            "--after" => device.after_operation = value(arg)?,
+            "--ptab" => ptab = Some(value(arg)?),
+            "--verify" => verify = true,
+            "--verify-only" => mode = MassProductionMode::Verify,
+            "--no-compress" => no_compress = true,
+            "--no-address-check" => device.skip_address_check = true,
+            "--erase-all" => erase_all = true,
+            "--address" => address = Some(parse_number(&value(arg)?)?),
+            "--concurrency" => {
+                let raw = value(arg)?;
+                max_concurrency = raw.parse().map_err(|_| format!("无效的并发数: {raw}"))?;
+            }
+            "--once" => once = true,
+            "--count" => {
+                let raw = value(arg)?;
+                count = Some(raw.parse().map_err(|_| format!("无效的数量: {raw}"))?);
+            }
+            "--allow" => whitelist.push(parse_filter_rule(&value(arg)?, whitelist.len())?),
+            "--deny" => blacklist.push(parse_filter_rule(&value(arg)?, blacklist.len())?),
+            "--log-dir" => log_dir = Some(value(arg)?),
+            "--data-dir" => data_dir = Some(value(arg)?),
+            "--serial-range" => serial_ranges.push(parse_serial_range(&value(arg)?)?),
+            "--serial-list" => serial_list = Some(value(arg)?),
+            "--unit-data" => unit_data_path = Some(value(arg)?),
+            "--serial-pool" => serial_pool = Some(value(arg)?),
+            "--max-attempts" => {
+                let raw = value(arg)?;
+                max_attempts = Some(raw.parse().map_err(|_| format!("无效的尝试次数: {raw}"))?);
+            }
+            "--retry-baud" => {
+                let raw = value(arg)?;
+                retry_baud = Some(raw.parse().map_err(|_| format!("无效的波特率: {raw}"))?);
+            }
+            "--serial-on-failure" => {
+                serial_on_failure = match value(arg)?.as_str() {
+                    "release" => SerialFailurePolicy::Release,
+                    "burn" => SerialFailurePolicy::Burn,
+                    other => return Err(format!("无效的序列号处理方式: {other}")),
+                }
+            }
+            "--json" => format = OutputFormat::Json,
+            other if other.starts_with('-') => return Err(format!("未知参数: {other}")),
+            other => positionals.push(other.to_string()),
+        }
+    }
+
+    let partitions = match &ptab {
+        Some(path) => load_partition_table_file(path)?,
+        None => Vec::new(),
+    };
+    let parse_write_files = || -> Result<Vec<WriteFlashFileInfo>, String> {
+        let files = parse_positionals(&positionals, |arg| parse_write_file_arg(arg, &partitions))?;
+        if ptab.is_some() {
+            let ranges = resolve_write_ranges(
+                files
+                    .iter()
+                    .map(|file| (file.file_path.as_str(), file.address)),
+            )?;
+            check_images_fit(&partitions, &ranges)?;
+        }
+        Ok(files)
+    };
+
+    let command = match subcommand.as_str() {
+        "help" | "--help" | "-h" => HeadlessCommand::Help,
+        "write" => HeadlessCommand::Write(WriteFlashRequest {
+            files: parse_write_files()?,
+            verify,
+            no_compress,
+            erase_all,
+        }),
+        "read" => HeadlessCommand::Read(ReadFlashRequest {
+            files: parse_positionals(&positionals, |arg| parse_read_file_arg(arg, &partitions))?,
+        }),
+        "erase" => HeadlessCommand::Erase {
+            address: address.ok_or("erase 需要 --address 参数")?,
+        },
+        "erase-region" => {
+            let regions =
+                parse_positionals(&positionals, |arg| parse_region_arg(arg, &partitions))?;
+            validate_erase_regions(
+                regions.iter().map(|region| (region.address, region.size)),
+                erase_granularity(&device.memory),
+            )?;
+            HeadlessCommand::EraseRegion(regions)
+        }
+        "mass-production" => HeadlessCommand::MassProduction(MassProductionArgs {
+            files: parse_write_files()?
+                .into_iter()
+                .map(|file| MassProductionWriteFileInfo {
+                    address: file.address,
+                    file_path: file.file_path,
+                    expected_hash: file.expected_hash,
+                })
+                .collect(),
+            mode,
+            verify,
+            no_compress,
+            erase_all,
+            max_concurrency,
+            once,
+            count,
+            whitelist,
+            blacklist,
+            log_dir,
+            data_dir,
+            unit_data: unit_data_path
+                .as_deref()
+                .map(load_unit_data_template)
+                .transpose()?,
+            serial: serial_pool.map(|pool| MassProductionSerialConfig {
+                pool,
+                on_failure: serial_on_failure,
+            }),
+            retry: (max_attempts.is_some() || retry_baud.is_some()).then(|| {
+                let defaults = MassProductionRetryPolicy::default();
+                MassProductionRetryPolicy {
+                    max_attempts: max_attempts.unwrap_or(defaults.max_attempts),
+                    retry_baud_rate: retry_baud,
+                    ..defaults
+                }
+            }),
+        }),
+        "serial-pool-import" => {
+            let [name] = positionals.as_slice() else {
+                return Err("serial-pool-import 需要且只需要一个池名称".to_string());
+            };
+            if serial_ranges.is_empty() && serial_list.is_none() {
+                return Err(
+                    "serial-pool-import 需要 --serial-range 或 --serial-list 参数".to_string(),
+                );
+            }
+            HeadlessCommand::SerialPoolImport {
+                request: SerialPoolImportRequest {
+                    name: name.clone(),
+                    ranges: serial_ranges,
+                    values: Vec::new(),
+                    list_path: serial_list,
+                },
+                data_dir,
+            }
+        }
+        other => return Err(format!("未知子命令: {other}")),
+    };
+
+    let needs_device = !matches!(
+        command,
+        HeadlessCommand::Help | HeadlessCommand::SerialPoolImport { .. }
+    );
+    if needs_device && device.chip.trim().is_empty() {
+        return Err("缺少 --chip 参数".to_string());
+    }
+
+    Ok(HeadlessArgs {
+        command,
+        device,
+        format,
+    })
+}
+
+fn parse_positionals<T>(
+    positionals: &[String],
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> Result<Vec<T>, String> {
+    if positionals.is_empty() {
+        return Err("缺少文件或区域参数".to_string());
+    }
+
+    positionals.iter().map(|arg| parse(arg)).collect()
+}
+
+fn load_unit_data_template(path: &str) -> Result<UnitDataTemplate, String> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| format!("读取出厂数据模板 {path} 失败: {e}"))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析出厂数据模板 {path} 失败: {e}"))
+}
+
+/// 不是数值时按 --ptab 分区表中的分区名解析
+fn lookup_partition<'a>(
+    name: &str,
+    partitions: &'a [PartitionInfo],
+) -> Result<&'a PartitionInfo, String> {
+    if partitions.is_empty() {
+        return Err(format!(
+            "无效的地址: {name}（按分区名指定需要 --ptab 参数）"
+        ));
+    }
+    find_partition(partitions, name)
+}
+
+/// FILE、FILE@ADDR 或 FILE@PART，可再跟 #HASH；未指定地址时交由 parse_file_info 自动识别 HEX/ELF
+fn parse_write_file_arg(
+    arg: &str,
+    partitions: &[PartitionInfo],
+) -> Result<WriteFlashFileInfo, String> {
+    // 文件名本身可能带 #，只有后缀是合法摘要时才拆出
+    let (arg, expected_hash) = match arg.rsplit_once('#') {
+        Some((rest, hash)) if is_hash_spec(hash) => (rest, Some(hash.to_string())),
+        _ => (arg, None),
+    };
+    let (file_path, address) = match arg.rsplit_once('@') {
+        Some((path, address)) => {
+            let address = parse_number(address)
+                .or_else(|_| lookup_partition(address, partitions).map(|p| p.address))?;
+            (path.to_string(), address)
+        }
+        None => (arg.to_string(), 0),
+    };
+
+    Ok(WriteFlashFileInfo {
+        address,
+        file_path,
+        expected_hash,
+    })
+}
+
+/// 64 位十六进制的 SHA-256 或 8 位的 CRC32，可带 sha256: / crc32: 和 0x 前缀
+fn is_hash_spec(spec: &str) -> bool {
+    let lower = spec.to_ascii_lowercase();
+    let (lengths, value): (&[usize], &str) = if let Some(value) = lower.strip_prefix("sha256:") {
+        (&[64], value)
+    } else if let Some(value) = lower.strip_prefix("crc32:") {
+        (&[8], value)
+    } else {
+        (&[64, 8], lower.as_str())
+    };
+    let value = value.strip_prefix("0x").unwrap_or(value);
+    lengths.contains(&value.len()) && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// FILE@ADDR:SIZE 或 FILE@PART
+fn parse_read_file_arg(
+    arg: &str,
+    partitions: &[PartitionInfo],
+) -> Result<ReadFlashFileInfo, String> {
+    let (path, range) = arg
+        .rsplit_once('@')
+        .ok_or_else(|| format!("读取参数格式应为 FILE@ADDR:SIZE 或 FILE@PART: {arg}"))?;
+    let region = parse_region_arg(range, partitions)?;
+
+    Ok(ReadFlashFileInfo {
+        file_path: path.to_string(),
+        address: region.address,
+        size: region.size,
+    })
+}
+
+/// ADDR:SIZE 或 PART
+fn parse_region_arg(arg: &str, partitions: &[PartitionInfo]) -> Result<EraseRegionFile, String> {
+    let Some((address, size)) = arg.split_once(':') else {
+        let partition = lookup_partition(arg, partitions)?;
+        return Ok(EraseRegionFile {
+            address: partition.address,
+            size: partition.size,
+        });
+    };
+
+    Ok(EraseRegionFile {
+        address: parse_number(address)?,
+        size: parse_number(size)?,
+    })
+}
+
+/// [PREFIX:]START-END，补零宽度取 START 的位数
+fn parse_serial_range(arg: &str) -> Result<SerialRange, String> {
+    let (prefix, numbers) = arg.rsplit_once(':').unwrap_or(("", arg));
+    let (start, end) = numbers
+        .split_once('-')
+        .ok_or_else(|| format!("号段格式应为 [PREFIX:]START-END: {arg}"))?;
+    let parse = |raw: &str| -> Result<u64, String> {
+        if raw.is_empty() || !raw.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(format!("无效的号段: {arg}"));
+        }
+        raw.parse().map_err(|_| format!("无效的号段: {arg}"))
+    };
+
+    Ok(SerialRange {
+        prefix: prefix.to_string(),
+        width: start.len() as u32,
+        start: parse(start)?,
+        end: parse(end)?,
+    })
+}
+
+fn parse_filter_rule(arg: &str, index: usize) -> Result<MassProductionFilterRule, String> {
+    let (field, value) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("过滤规则格式应为 FIELD=VALUE: {arg}"))?;
+
+    let field = match field {
+        "vid_pid" => MassProductionFilterField::VidPid,
+        "serial_number" => MassProductionFilterField::SerialNumber,
+        "location_path" => MassProductionFilterField::LocationPath,
+        "port_name" => MassProductionFilterField::PortName,
+        other => return Err(format!("未知的过滤字段: {other}")),
+    };
+
+    Ok(MassProductionFilterRule {
+        id: format!("cli-{index}"),
+        field,
+        value: value.to_string(),
+        enabled: true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_write_files_with_and_without_address() {
+        let parsed = parse_args(&to_args(&[
+            "write",
+            "--chip",
+            "SF32LB52",
+            "--port",
+            "COM3",
+            "--verify",
+            "app.elf",
+            "boot.bin@0x12010000",
+        ]))
+        .unwrap();
+
+        let HeadlessCommand::Write(request) = parsed.command else {
+            panic!("expected write command");
+        };
+        assert!(request.verify);
+        assert_eq!(request.files[0].address, 0);
+        assert_eq!(request.files[1].file_path, "boot.bin");
+        assert_eq!(request.files[1].address, 0x1201_0000);
+    }
+
+    #[test]
+    fn parses_expected_hash_suffix() {
+        let sha256 = "a".repeat(64);
+        let file =
+            parse_write_file_arg(&format!("app.bin@0x12020000#sha256:{sha256}"), &[]).unwrap();
+        assert_eq!(file.file_path, "app.bin");
+        assert_eq!(file.address, 0x1202_0000);
+        assert_eq!(file.expected_hash, Some(format!("sha256:{sha256}")));
+
+        let file = parse_write_file_arg("app.elf#0xCBF43926", &[]).unwrap();
+        assert_eq!(file.file_path, "app.elf");
+        assert_eq!(file.expected_hash.as_deref(), Some("0xCBF43926"));
+
+        // 不是摘要的 # 后缀属于文件名
+        let file = parse_write_file_arg("build#1/app.bin@0x0", &[]).unwrap();
+        assert_eq!(file.file_path, "build#1/app.bin");
+        assert_eq!(file.expected_hash, None);
+        assert!(parse_write_file_arg("app.bin@0x0#sha256:1234", &[]).is_err());
+    }
+
+    #[test]
+    fn parses_read_and_region_arguments() {
+        let read = parse_read_file_arg("dump.bin@0x12000000:0x1000", &[]).unwrap();
+        assert_eq!(read.address, 0x1200_0000);
+        assert_eq!(read.size, 0x1000);
+
+        let region = parse_region_arg("0x12A00000:4096", &[]).unwrap();
+        assert_eq!(region.address, 0x12A0_0000);
+        assert_eq!(region.size, 4096);
+    }
+
+    #[test]
+    fn resolves_partition_names() {
+        let partitions = [PartitionInfo {
+            name: "FS_ROOT_REGION".to_string(),
+            tags: vec!["FS_ROOT_REGION".to_string()],
+            memory: "flash2".to_string(),
+            address: 0x12A0_0000,
+            size: 0x20_0000,
+        }];
+
+        let region = parse_region_arg("fs_root", &partitions).unwrap();
+        assert_eq!(region.address, 0x12A0_0000);
+        assert_eq!(region.size, 0x20_0000);
+        let read = parse_read_file_arg("fs.bin@fs_root", &partitions).unwrap();
+        assert_eq!(read.size, 0x20_0000);
+        let file = parse_write_file_arg("fs.bin@FS_ROOT_REGION", &partitions).unwrap();
+        assert_eq!(file.address, 0x12A0_0000);
+        assert!(parse_region_arg("fs_root", &[]).is_err());
+    }
+
+    #[test]
+    fn parses_serial_pool_import_without_device() {
+        let parsed = parse_args(&to_args(&[
+            "serial-pool-import",
+            "line1",
+            "--serial-range",
+            "SN:0001-0500",
+            "--serial-range",
+            "9-12",
+            "--data-dir",
+            "/tmp/sftool",
+        ]))
+        .unwrap();
+
+        let HeadlessCommand::SerialPoolImport { request, data_dir } = parsed.command else {
+            panic!("expected serial-pool-import command");
+        };
+        assert_eq!(request.name, "line1");
+        assert_eq!(
+            request.ranges[0],
+            SerialRange {
+                prefix: "SN".to_string(),
+                width: 4,
+                start: 1,
+                end: 500,
+            }
+        );
+        assert_eq!(request.ranges[1].prefix, "");
+        assert_eq!(request.ranges[1].end, 12);
+        assert_eq!(data_dir.as_deref(), Some("/tmp/sftool"));
+
+        assert!(parse_args(&to_args(&["serial-pool-import", "line1"])).is_err());
+        assert!(parse_serial_range("SN:A1-20").is_err());
+    }
+
+    #[test]
+    fn rejects_missing_chip() {
+        assert!(parse_args(&to_args(&["write", "app.bin@0x0"])).is_err());
+    }
+}
@@ -0,0 +1,274 @@
+//! 无窗口的命令行模式，复用 GUI 的工具工厂与量产流程，供 CI 和产线测试台调用
+
+pub mod args;
+pub mod output;
+
+pub use args::*;
+pub use output::*;
+
+use crate::commands::{
+    apply_mass_production_hotplug, prepare_mass_production_start,
+    should_soft_reset_after_operation, start_mass_production, stop_mass_production,
+};
+use crate::state::MassProductionState;
+use crate::types::{MassProductionStartRequest, SerialPoolImportRequest};
+use crate::utils::{
+    configured_flash_size, create_tool_instance_with_progress, extract_connected_identities,
+    import_serial_pool, serial_pool_dir, spawn_serial_port_watcher, validate_write_plan,
+    verify_expected_hashes, FlashBounds,
+};
+use sftool_lib::progress::ProgressSinkArc;
+use sftool_lib::{CancelToken, EraseFlashParams, EraseRegionParams};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const EXIT_SUCCESS: i32 = 0;
+const EXIT_FAILURE: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const MASS_PRODUCTION_POLL_INTERVAL_MS: u64 = 500;
+
+/// 若命令行参数是 headless 子命令则执行并返回退出码，否则返回 None 交由 GUI 启动
+pub fn run_from_env() -> Option<i32> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let first = args.first()?;
+    if !is_headless_subcommand(first) {
+        return None;
+    }
+
+    attach_parent_console();
+    Some(run(&args))
+}
+
+/// release 版在 Windows 上使用 windows 子系统，不会继承启动它的终端；
+/// 附加到父进程的控制台，否则 headless 模式的输出全部丢失
+#[cfg(windows)]
+fn attach_parent_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+    // 从资源管理器启动或输出已重定向时附加失败，保持原有的标准句柄即可
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
+
+pub fn run(args: &[String]) -> i32 {
+    let parsed = match parse_args(args) {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            eprintln!("{error}\n\n{USAGE}");
+            return EXIT_USAGE;
+        }
+    };
+
+    let printer = Arc::new(ConsolePrinter::new(parsed.format));
+    let result = match parsed.command {
+        HeadlessCommand::Help => {
+            println!("{USAGE}");
+            return EXIT_SUCCESS;
+        }
+        HeadlessCommand::MassProduction(mass_args) => {
+            run_mass_production(&parsed.device, mass_args, printer.clone())
+        }
+        HeadlessCommand::SerialPoolImport { request, data_dir } => {
+            run_serial_pool_import(&request, data_dir.as_deref(), &printer)
+        }
+        command => run_device_command(&parsed.device, command, printer.clone()),
+    };
+
+    printer.print_result(&result);
+    if result.is_ok() {
+        EXIT_SUCCESS
+    } else {
+        EXIT_FAILURE
+    }
+}
+
+fn run_device_command(
+    device: &DeviceArgs,
+    command: HeadlessCommand,
+    printer: Arc<ConsolePrinter>,
+) -> Result<(), String> {
+    let device_config = device.to_device_config()?;
+
+    // 与 GUI 一致校验文件摘要，在连接设备前完成
+    if let HeadlessCommand::Write(request) = &command {
+        verify_expected_hashes(
+            request
+                .files
+                .iter()
+                .map(|file| (file.file_path.as_str(), file.expected_hash.as_deref())),
+        )?;
+    }
+
+    let progress_callback: ProgressSinkArc = Arc::new(ConsoleProgressSink::new(printer));
+
+    let mut tool =
+        create_tool_instance_with_progress(&device_config, progress_callback, CancelToken::new())?;
+
+    match command {
+        HeadlessCommand::Write(request) => {
+            let params = request.to_write_flash_params()?;
+            validate_write_plan(
+                request
+                    .files
+                    .iter()
+                    .map(|file| (file.file_path.as_str(), file.address)),
+                &device_config.chip_type,
+                &device_config.memory_type,
+                FlashBounds {
+                    flash_size: configured_flash_size(
+                        &device_config.stub_config_path,
+                        &device_config.memory_type,
+                    )?,
+                    skip_check: device.skip_address_check,
+                },
+            )?;
+            tool.write_flash(&params)
+                .map_err(|e| format!("写入 Flash 失败: {}", e))?;
+        }
+        HeadlessCommand::Read(request) => {
+            let params = request.to_read_flash_params();
+            tool.read_flash(&params)
+                .map_err(|e| format!("读取 Flash 失败: {}", e))?;
+        }
+        HeadlessCommand::Erase { address } => {
+            tool.erase_flash(&EraseFlashParams { address })
+                .map_err(|e| format!("擦除 Flash 失败: {}", e))?;
+        }
+        HeadlessCommand::EraseRegion(regions) => {
+            tool.erase_region(&EraseRegionParams { regions })
+                .map_err(|e| format!("擦除区域失败: {}", e))?;
+        }
+        HeadlessCommand::MassProduction(_)
+        | HeadlessCommand::SerialPoolImport { .. }
+        | HeadlessCommand::Help => unreachable!(),
+    }
+
+    if should_soft_reset_after_operation(&device_config.after_operation)? {
+        tool.soft_reset()
+            .map_err(|e| format!("下载后软复位失败: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// 未指定 --data-dir 时使用 GUI 的应用数据目录（Tauri 的 app_data_dir），与 GUI 共用序列号池和追溯记录
+fn resolve_data_dir(data_dir: Option<&str>) -> Result<PathBuf, String> {
+    if let Some(dir) = data_dir {
+        return Ok(PathBuf::from(dir));
+    }
+    let config: serde_json::Value = serde_json::from_str(include_str!("../../tauri.conf.json"))
+        .map_err(|e| format!("解析应用配置失败: {e}"))?;
+    let identifier = config["identifier"]
+        .as_str()
+        .ok_or("应用配置缺少 identifier")?;
+    dirs::data_dir()
+        .map(|dir| dir.join(identifier))
+        .ok_or_else(|| "获取数据目录失败，请使用 --data-dir 指定".to_string())
+}
+
+fn run_serial_pool_import(
+    request: &SerialPoolImportRequest,
+    data_dir: Option<&str>,
+    printer: &ConsolePrinter,
+) -> Result<(), String> {
+    let dir = serial_pool_dir(&resolve_data_dir(data_dir)?);
+    let status = import_serial_pool(&dir, request)?;
+    printer.print_serial_pool_status(&status);
+    Ok(())
+}
+
+fn run_mass_production(
+    device: &DeviceArgs,
+    args: MassProductionArgs,
+    printer: Arc<ConsolePrinter>,
+) -> Result<(), String> {
+    let log_dir = match &args.log_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => std::env::current_dir()
+            .map_err(|e| format!("获取当前目录失败: {e}"))?
+            .join("sftool-logs"),
+    };
+    let host = ConsoleHost::new(
+        printer,
+        log_dir,
+        resolve_data_dir(args.data_dir.as_deref())?,
+    );
+    let mass_state = Arc::new(Mutex::new(MassProductionState::default()));
+
+    let request = MassProductionStartRequest {
+        chip_model: device.chip.clone(),
+        memory_type: device.memory.clone(),
+        baud_rate: Some(device.baud),
+        stub_config_path: device.stub_config_path.clone(),
+        external_stub_path: device.external_stub_path.clone(),
+        before_operation: device.before_operation.clone(),
+        after_operation: device.after_operation.clone(),
+        files: args.files,
+        mode: args.mode,
+        unit_data: args.unit_data,
+        serial: args.serial,
+        retry: args.retry,
+        skip_address_check: device.skip_address_check,
+        verify: args.verify,
+        no_compress: args.no_compress,
+        erase_all: args.erase_all,
+        auto_download: !args.once,
+        max_concurrency: args.max_concurrency,
+        is_filter_enabled: !args.whitelist.is_empty() || !args.blacklist.is_empty(),
+        whitelist: args.whitelist,
+        blacklist: args.blacklist,
+    };
+
+    let request = prepare_mass_production_start(&mass_state, request)?;
+    start_mass_production(&host, &mass_state, request)?;
+
+    // 与 GUI 一致：同名端口重新插入后会被识别为新设备并重新排队
+    let watcher_host = host.clone();
+    let watcher_state = mass_state.clone();
+    spawn_serial_port_watcher(move |previous_ports, ports| {
+        let connected_identities = extract_connected_identities(previous_ports, ports);
+        apply_mass_production_hotplug(&watcher_host, &watcher_state, connected_identities);
+        Ok(())
+    });
+
+    loop {
+        thread::sleep(Duration::from_millis(MASS_PRODUCTION_POLL_INTERVAL_MS));
+        let snapshot = { mass_state.lock().unwrap().to_snapshot() };
+        let idle = snapshot.active_count == 0 && snapshot.queued_count == 0;
+        let finished = snapshot.success_count.saturating_add(snapshot.failed_count);
+
+        if args.once && idle {
+            break;
+        }
+
+        // 固件原文件被修改等原因中止会话后，等进行中的设备结束即退出
+        if snapshot.abort_reason.is_some() && snapshot.active_count == 0 {
+            break;
+        }
+
+        if let Some(count) = args.count {
+            if finished >= count && idle {
+                break;
+            }
+        }
+    }
+
+    let snapshot = stop_mass_production(&host, &mass_state);
+    if let Some(reason) = snapshot.abort_reason {
+        return Err(format!("量产已中止: {reason}"));
+    }
+    if snapshot.failed_count > 0 {
+        return Err(format!(
+            "量产结束: 成功 {} 台，失败 {} 台",
+            snapshot.success_count, snapshot.failed_count
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,318 @@
+use crate::commands::{MassProductionHost, MASS_PRODUCTION_RUNTIME_LOG_DIRNAME};
+use crate::headless::args::OutputFormat;
+use crate::logging::AppLogEntry;
+use crate::progress::ProgressEventTranslator;
+use crate::types::{
+    MassProductionPortStatus, MassProductionProgressEvent, MassProductionSnapshot,
+    SerialPoolStatus, TauriProgressEvent, TauriProgressOperation, TauriProgressStatus,
+};
+use serde_json::json;
+use sftool_lib::progress::{ProgressEvent, ProgressSink};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+struct BarProgress {
+    current: u64,
+    total: u64,
+    last_decile: u64,
+}
+
+/// 终端输出：text 模式输出可读文本，json 模式每行输出一个 JSON 对象
+pub struct ConsolePrinter {
+    format: OutputFormat,
+    bars: Mutex<HashMap<(String, u64), BarProgress>>,
+    port_statuses: Mutex<HashMap<String, MassProductionPortStatus>>,
+    last_summary: Mutex<Option<(u32, u32, u32, u32, u32)>>,
+}
+
+impl ConsolePrinter {
+    pub fn new(format: OutputFormat) -> Self {
+        Self {
+            format,
+            bars: Mutex::new(HashMap::new()),
+            port_statuses: Mutex::new(HashMap::new()),
+            last_summary: Mutex::new(None),
+        }
+    }
+
+    fn write_line(&self, line: &str) {
+        let mut stdout = std::io::stdout().lock();
+        let _ = writeln!(stdout, "{line}");
+        let _ = stdout.flush();
+    }
+
+    fn write_json(&self, value: serde_json::Value) {
+        self.write_line(&value.to_string());
+    }
+
+    pub fn print_progress(&self, port_name: Option<&str>, event: &TauriProgressEvent) {
+        if self.format == OutputFormat::Json {
+            self.write_json(json!({
+                "type": "progress",
+                "port_name": port_name,
+                "event": event,
+            }));
+            return;
+        }
+
+        let prefix = port_name
+            .map(|name| format!("[{name}] "))
+            .unwrap_or_default();
+        let key = (port_name.unwrap_or_default().to_string(), event.id);
+
+        match event.event_type.as_str() {
+            "start" => {
+                if let Some(total) = event.total {
+                    self.bars.lock().unwrap().insert(
+                        key,
+                        BarProgress {
+                            current: event.current.unwrap_or(0),
+                            total,
+                            last_decile: 0,
+                        },
+                    );
+                }
+                self.write_line(&format!(
+                    "{prefix}{} ...",
+                    describe_operation(&event.operation)
+                ));
+            }
+            "increment" => {
+                let mut bars = self.bars.lock().unwrap();
+                let Some(bar) = bars.get_mut(&key) else {
+                    return;
+                };
+                bar.current = bar.current.saturating_add(event.current.unwrap_or(0));
+                if bar.total == 0 {
+                    return;
+                }
+                let decile = (bar.current.saturating_mul(10) / bar.total).min(10);
+                if decile > bar.last_decile {
+                    bar.last_decile = decile;
+                    drop(bars);
+                    self.write_line(&format!(
+                        "{prefix}{} {}%",
+                        describe_operation(&event.operation),
+                        decile * 10
+                    ));
+                }
+            }
+            "finish" => {
+                self.bars.lock().unwrap().remove(&key);
+                let status = match &event.status {
+                    Some(TauriProgressStatus::Success) | None => "完成".to_string(),
+                    Some(TauriProgressStatus::Retry) => "重试".to_string(),
+                    Some(TauriProgressStatus::Skipped) => "跳过".to_string(),
+                    Some(TauriProgressStatus::Required) => "需要下载".to_string(),
+                    Some(TauriProgressStatus::NotFound) => "未找到".to_string(),
+                    Some(TauriProgressStatus::Failed(message)) => format!("失败: {message}"),
+                    Some(TauriProgressStatus::Aborted) => "已中止".to_string(),
+                };
+                self.write_line(&format!(
+                    "{prefix}{} {status}",
+                    describe_operation(&event.operation)
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    pub fn print_snapshot(&self, snapshot: &MassProductionSnapshot) {
+        let summary = (
+            snapshot.queued_count,
+            snapshot.active_count,
+            snapshot.success_count,
+            snapshot.failed_count,
+            snapshot.cancelled_count,
+        );
+
+        if self.format == OutputFormat::Json {
+            let mut statuses = self.port_statuses.lock().unwrap();
+            let changed = snapshot
+                .ports
+                .iter()
+                .any(|port| statuses.get(&port.name) != Some(&port.status));
+            let mut last_summary = self.last_summary.lock().unwrap();
+            if !changed && *last_summary == Some(summary) {
+                return;
+            }
+            *last_summary = Some(summary);
+            statuses.clear();
+            for port in &snapshot.ports {
+                statuses.insert(port.name.clone(), port.status.clone());
+            }
+            drop(statuses);
+            drop(last_summary);
+
+            self.write_json(json!({
+                "type": "snapshot",
+                "snapshot": snapshot,
+            }));
+            return;
+        }
+
+        {
+            let mut statuses = self.port_statuses.lock().unwrap();
+            for port in &snapshot.ports {
+                if statuses.get(&port.name) == Some(&port.status) {
+                    continue;
+                }
+                statuses.insert(port.name.clone(), port.status.clone());
+                let message = port.message.clone().unwrap_or_default();
+                self.write_line(&format!("[{}] {:?} {}", port.name, port.status, message));
+            }
+        }
+
+        let mut last_summary = self.last_summary.lock().unwrap();
+        if *last_summary != Some(summary) {
+            *last_summary = Some(summary);
+            self.write_line(&format!(
+                "排队 {} / 进行中 {} / 成功 {} / 失败 {} / 取消 {}",
+                summary.0, summary.1, summary.2, summary.3, summary.4
+            ));
+        }
+    }
+
+    pub fn print_log(&self, entry: &AppLogEntry) {
+        if self.format == OutputFormat::Json {
+            self.write_json(json!({
+                "type": "log",
+                "entry": entry,
+            }));
+            return;
+        }
+
+        let port = entry
+            .port
+            .as_ref()
+            .map(|port| format!("[{port}] "))
+            .unwrap_or_default();
+        eprintln!("{port}{}", entry.message);
+    }
+
+    pub fn print_serial_pool_status(&self, status: &SerialPoolStatus) {
+        if self.format == OutputFormat::Json {
+            self.write_json(json!({
+                "type": "serial_pool",
+                "status": status,
+            }));
+            return;
+        }
+
+        self.write_line(&format!(
+            "序列号池 {}: 剩余 {} / 预留 {} / 已使用 {} / 作废 {}",
+            status.name, status.remaining, status.reserved, status.committed, status.burned
+        ));
+    }
+
+    pub fn print_result(&self, result: &Result<(), String>) {
+        if self.format == OutputFormat::Json {
+            self.write_json(match result {
+                Ok(()) => json!({ "type": "result", "ok": true }),
+                Err(error) => json!({ "type": "result", "ok": false, "error": error }),
+            });
+            return;
+        }
+
+        match result {
+            Ok(()) => self.write_line("操作完成"),
+            Err(error) => eprintln!("操作失败: {error}"),
+        }
+    }
+}
+
+fn describe_operation(operation: &TauriProgressOperation) -> String {
+    match operation {
+        TauriProgressOperation::Connect => "连接设备".to_string(),
+        TauriProgressOperation::DownloadStub { .. } => "下载 Stub".to_string(),
+        TauriProgressOperation::EraseFlash { address, .. } => {
+            format!("擦除 Flash 0x{address:08X}")
+        }
+        TauriProgressOperation::EraseRegion { address, len, .. } => {
+            format!("擦除区域 0x{address:08X} ({len} 字节)")
+        }
+        TauriProgressOperation::EraseAllRegions => "擦除全部区域".to_string(),
+        TauriProgressOperation::Verify { address, len } => {
+            format!("校验 0x{address:08X} ({len} 字节)")
+        }
+        TauriProgressOperation::CheckRedownload { address, size } => {
+            format!("检查 0x{address:08X} ({size} 字节)")
+        }
+        TauriProgressOperation::WriteFlash { address, size } => {
+            format!("写入 0x{address:08X} ({size} 字节)")
+        }
+        TauriProgressOperation::ReadFlash { address, size } => {
+            format!("读取 0x{address:08X} ({size} 字节)")
+        }
+        TauriProgressOperation::Unknown => "处理中".to_string(),
+    }
+}
+
+/// 单设备操作的终端进度回调
+pub struct ConsoleProgressSink {
+    printer: Arc<ConsolePrinter>,
+    translator: ProgressEventTranslator,
+}
+
+impl ConsoleProgressSink {
+    pub fn new(printer: Arc<ConsolePrinter>) -> Self {
+        Self {
+            printer,
+            translator: ProgressEventTranslator::new(),
+        }
+    }
+}
+
+impl ProgressSink for ConsoleProgressSink {
+    fn on_event(&self, event: ProgressEvent) {
+        let event = self.translator.translate(event);
+        self.printer.print_progress(None, &event);
+    }
+}
+
+/// headless 量产宿主：事件输出到终端，日志写入指定目录，序列号池和追溯记录位于数据目录
+#[derive(Clone)]
+pub struct ConsoleHost {
+    printer: Arc<ConsolePrinter>,
+    log_dir: PathBuf,
+    data_dir: PathBuf,
+}
+
+impl ConsoleHost {
+    pub fn new(printer: Arc<ConsolePrinter>, log_dir: PathBuf, data_dir: PathBuf) -> Self {
+        Self {
+            printer,
+            log_dir,
+            data_dir,
+        }
+    }
+}
+
+impl MassProductionHost for ConsoleHost {
+    fn emit_snapshot(&self, snapshot: &MassProductionSnapshot) {
+        self.printer.print_snapshot(snapshot);
+    }
+
+    fn emit_progress(&self, payload: &MassProductionProgressEvent) {
+        self.printer
+            .print_progress(Some(&payload.port_name), &payload.event);
+    }
+
+    fn emit_log(&self, entry: AppLogEntry) {
+        self.printer.print_log(&entry);
+    }
+
+    fn config_dir(&self) -> Result<PathBuf, String> {
+        Ok(self.log_dir.clone())
+    }
+
+    fn data_dir(&self) -> Result<PathBuf, String> {
+        Ok(self.data_dir.clone())
+    }
+
+    fn runtime_log_dir(&self) -> Result<PathBuf, String> {
+        Ok(self.log_dir.join(MASS_PRODUCTION_RUNTIME_LOG_DIRNAME))
+    }
+}
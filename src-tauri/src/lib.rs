@@ -2,8 +2,10 @@
 
 pub mod app;
 pub mod commands;
+pub mod headless;
 pub mod logging;
 pub mod progress;
+pub mod remote;
 pub mod state;
 pub mod types;
 pub mod utils;
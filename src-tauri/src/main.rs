@@ -2,5 +2,10 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // 带子命令启动时以 headless 模式运行，不创建窗口
+    if let Some(exit_code) = sftool_gui_lib::headless::run_from_env() {
+        std::process::exit(exit_code);
+    }
+
     sftool_gui_lib::run()
 }
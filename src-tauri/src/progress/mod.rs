@@ -1,3 +1,5 @@
 pub mod tauri_callback;
+pub mod translator;
 
 pub use tauri_callback::*;
+pub use translator::*;
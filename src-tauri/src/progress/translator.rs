@@ -0,0 +1,126 @@
+use crate::types::{
+    FlashErrorCategory, TauriProgressContext, TauriProgressEvent, TauriProgressOperation,
+    TauriProgressStatus, TauriProgressType,
+};
+use sftool_lib::progress::ProgressEvent;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// 将 sftool-lib 的进度事件转换为前端/终端共用的 TauriProgressEvent，
+/// 并记录每个进度条的上下文，以便 increment/finish 事件也能带上操作信息
+#[derive(Default)]
+pub struct ProgressEventTranslator {
+    contexts: Mutex<HashMap<u64, TauriProgressContext>>,
+    /// 最近开始的操作所处阶段，操作失败时用于归类错误
+    stage: Mutex<Option<FlashErrorCategory>>,
+}
+
+impl ProgressEventTranslator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn failure_stage(&self) -> Option<FlashErrorCategory> {
+        *self.stage.lock().unwrap()
+    }
+
+    /// 新操作开始前清除上一次操作留下的阶段
+    pub fn reset_failure_stage(&self) {
+        *self.stage.lock().unwrap() = None;
+    }
+
+    fn record_stage(&self, operation: &TauriProgressOperation) {
+        if let Some(stage) = operation.failure_stage() {
+            *self.stage.lock().unwrap() = Some(stage);
+        }
+    }
+
+    pub fn translate(&self, event: ProgressEvent) -> TauriProgressEvent {
+        match event {
+            ProgressEvent::Start { id, ctx } => {
+                let current = ctx.current;
+                let context = TauriProgressContext::from(ctx);
+                let total = total_from_progress_type(&context.progress_type);
+                self.record_stage(&context.operation);
+                self.contexts.lock().unwrap().insert(id.0, context.clone());
+
+                TauriProgressEvent {
+                    id: id.0,
+                    event_type: "start".to_string(),
+                    step: context.step,
+                    progress_type: context.progress_type,
+                    operation: context.operation,
+                    current,
+                    total,
+                    status: None,
+                }
+            }
+            ProgressEvent::Update { id, ctx } => {
+                let context = TauriProgressContext::from(ctx);
+                self.record_stage(&context.operation);
+                self.contexts.lock().unwrap().insert(id.0, context.clone());
+
+                TauriProgressEvent {
+                    id: id.0,
+                    event_type: "update".to_string(),
+                    step: context.step,
+                    progress_type: context.progress_type,
+                    operation: context.operation,
+                    current: None,
+                    total: None,
+                    status: None,
+                }
+            }
+            ProgressEvent::Advance { id, delta } => {
+                let context = self.contexts.lock().unwrap().get(&id.0).cloned();
+                let (step, progress_type, operation) = split_context(context);
+
+                TauriProgressEvent {
+                    id: id.0,
+                    event_type: "increment".to_string(),
+                    step,
+                    progress_type,
+                    operation,
+                    current: Some(delta),
+                    total: None,
+                    status: None,
+                }
+            }
+            ProgressEvent::Finish { id, status } => {
+                let context = self.contexts.lock().unwrap().remove(&id.0);
+                let (step, progress_type, operation) = split_context(context);
+
+                TauriProgressEvent {
+                    id: id.0,
+                    event_type: "finish".to_string(),
+                    step,
+                    progress_type,
+                    operation,
+                    current: None,
+                    total: None,
+                    status: Some(TauriProgressStatus::from(status)),
+                }
+            }
+        }
+    }
+}
+
+fn split_context(
+    context: Option<TauriProgressContext>,
+) -> (i32, TauriProgressType, TauriProgressOperation) {
+    match context {
+        Some(ctx) => (ctx.step, ctx.progress_type, ctx.operation),
+        None => (
+            0,
+            TauriProgressType::Spinner,
+            TauriProgressOperation::Unknown,
+        ),
+    }
+}
+
+pub fn total_from_progress_type(progress_type: &TauriProgressType) -> Option<u64> {
+    match progress_type {
+        TauriProgressType::Spinner => None,
+        TauriProgressType::Bar { total } => Some(*total),
+    }
+}
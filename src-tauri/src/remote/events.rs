@@ -0,0 +1,243 @@
+use crate::types::{MassProductionProgressEvent, MassProductionSnapshot};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Mutex;
+
+/// 订阅过滤条件，未设置的字段不参与过滤
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EventFilter {
+    pub session_id: Option<u64>,
+    pub port_name: Option<String>,
+}
+
+impl EventFilter {
+    pub fn from_query(query: &HashMap<String, String>) -> Result<Self, String> {
+        let session_id = query
+            .get("session_id")
+            .filter(|value| !value.is_empty())
+            .map(|value| {
+                value
+                    .parse::<u64>()
+                    .map_err(|_| format!("session_id 无效: {value}"))
+            })
+            .transpose()?;
+        let port_name = query.get("port").filter(|value| !value.is_empty()).cloned();
+
+        Ok(Self {
+            session_id,
+            port_name,
+        })
+    }
+
+    fn matches_session(&self, session_id: u64) -> bool {
+        self.session_id.is_none() || self.session_id == Some(session_id)
+    }
+
+    /// 按端口过滤时只保留该端口的条目，汇总计数保持不变
+    pub fn filter_snapshot(
+        &self,
+        snapshot: &MassProductionSnapshot,
+    ) -> Option<MassProductionSnapshot> {
+        if !self.matches_session(snapshot.session_id) {
+            return None;
+        }
+
+        let mut snapshot = snapshot.clone();
+        if let Some(port_name) = &self.port_name {
+            snapshot.ports.retain(|port| &port.name == port_name);
+        }
+        Some(snapshot)
+    }
+
+    fn matches_progress(&self, payload: &MassProductionProgressEvent) -> bool {
+        self.matches_session(payload.session_id)
+            && (self.port_name.is_none()
+                || self.port_name.as_deref() == Some(payload.port_name.as_str()))
+    }
+}
+
+/// 每个订阅者最多积压的消息数；客户端读得太慢时断开，重连后从最新快照开始
+const SUBSCRIBER_QUEUE_LIMIT: usize = 256;
+
+pub fn snapshot_message(snapshot: &MassProductionSnapshot) -> String {
+    json!({ "type": "snapshot", "payload": snapshot }).to_string()
+}
+
+pub fn progress_message(payload: &MassProductionProgressEvent) -> String {
+    json!({ "type": "progress", "payload": payload }).to_string()
+}
+
+struct Subscriber {
+    id: u64,
+    filter: EventFilter,
+    sender: SyncSender<String>,
+}
+
+impl Subscriber {
+    /// 返回 false 表示应移除该订阅者：连接已关闭或积压已满
+    fn deliver(&self, message: String) -> bool {
+        match self.sender.try_send(message) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                eprintln!(
+                    "Remote API event subscriber {} fell behind, disconnecting",
+                    self.id
+                );
+                false
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    }
+}
+
+/// 量产事件广播中心，GUI 发出快照/进度事件的同时推送给事件流订阅者
+#[derive(Default)]
+pub struct RemoteEventHub {
+    next_id: AtomicU64,
+    subscribers: Mutex<Vec<Subscriber>>,
+}
+
+impl RemoteEventHub {
+    pub fn subscribe(&self, filter: EventFilter) -> (u64, Receiver<String>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = mpsc::sync_channel(SUBSCRIBER_QUEUE_LIMIT);
+        self.subscribers
+            .lock()
+            .unwrap()
+            .push(Subscriber { id, filter, sender });
+        (id, receiver)
+    }
+
+    pub fn unsubscribe(&self, id: u64) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.id != id);
+    }
+
+    pub fn publish_snapshot(&self, snapshot: &MassProductionSnapshot) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(
+            |subscriber| match subscriber.filter.filter_snapshot(snapshot) {
+                Some(snapshot) => subscriber.deliver(snapshot_message(&snapshot)),
+                None => true,
+            },
+        );
+    }
+
+    pub fn publish_progress(&self, payload: &MassProductionProgressEvent) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        if subscribers.is_empty() {
+            return;
+        }
+
+        let message = progress_message(payload);
+        subscribers.retain(|subscriber| {
+            !subscriber.filter.matches_progress(payload) || subscriber.deliver(message.clone())
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EventFilter, RemoteEventHub, SUBSCRIBER_QUEUE_LIMIT};
+    use crate::types::{
+        MassProductionPortInfo, MassProductionPortStatus, MassProductionProgressEvent,
+        MassProductionSnapshot, TauriProgressEvent, TauriProgressOperation, TauriProgressType,
+    };
+
+    fn port(name: &str) -> MassProductionPortInfo {
+        MassProductionPortInfo {
+            id: name.to_string(),
+            name: name.to_string(),
+            port_type: "usb".to_string(),
+            vid: None,
+            pid: None,
+            serial_number: None,
+            location_path: None,
+            chip: None,
+            status: MassProductionPortStatus::Flashing,
+            progress: 0,
+            message: None,
+            is_allowed: true,
+            last_seen_at: 0,
+            task_started_at: None,
+            task_finished_at: None,
+            error_category: None,
+            attempt: 0,
+        }
+    }
+
+    fn progress(session_id: u64, port_name: &str) -> MassProductionProgressEvent {
+        MassProductionProgressEvent {
+            session_id,
+            port_name: port_name.to_string(),
+            event: TauriProgressEvent {
+                id: 1,
+                event_type: "start".to_string(),
+                step: 0,
+                progress_type: TauriProgressType::Spinner,
+                operation: TauriProgressOperation::Connect,
+                current: None,
+                total: None,
+                status: None,
+            },
+        }
+    }
+
+    #[test]
+    fn filters_by_session_and_port() {
+        let hub = RemoteEventHub::default();
+        let (_, all) = hub.subscribe(EventFilter::default());
+        let (_, com3) = hub.subscribe(EventFilter {
+            session_id: Some(2),
+            port_name: Some("COM3".to_string()),
+        });
+
+        hub.publish_progress(&progress(1, "COM3"));
+        hub.publish_progress(&progress(2, "COM4"));
+        hub.publish_progress(&progress(2, "COM3"));
+
+        assert_eq!(all.try_iter().count(), 3);
+        let received: Vec<String> = com3.try_iter().collect();
+        assert_eq!(received.len(), 1);
+        assert!(received[0].contains("\"session_id\":2"));
+        assert!(received[0].contains("\"port_name\":\"COM3\""));
+
+        let snapshot = MassProductionSnapshot {
+            session_id: 2,
+            ports: vec![port("COM3"), port("COM4")],
+            ..MassProductionSnapshot::default()
+        };
+        hub.publish_snapshot(&snapshot);
+        let message = com3.try_recv().unwrap();
+        assert!(message.contains("COM3"));
+        assert!(!message.contains("COM4"));
+    }
+
+    #[test]
+    fn drops_disconnected_subscribers() {
+        let hub = RemoteEventHub::default();
+        let (_, receiver) = hub.subscribe(EventFilter::default());
+        drop(receiver);
+
+        hub.publish_progress(&progress(1, "COM3"));
+        assert!(hub.subscribers.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn disconnects_subscribers_that_fall_behind() {
+        let hub = RemoteEventHub::default();
+        let (_, receiver) = hub.subscribe(EventFilter::default());
+
+        for _ in 0..=SUBSCRIBER_QUEUE_LIMIT {
+            hub.publish_progress(&progress(1, "COM3"));
+        }
+        assert!(hub.subscribers.lock().unwrap().is_empty());
+        // 已积压的消息仍可读完，之后连接随发送端一起关闭
+        assert_eq!(receiver.try_iter().count(), SUBSCRIBER_QUEUE_LIMIT);
+        assert!(receiver.recv().is_err());
+    }
+}
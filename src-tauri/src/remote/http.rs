@@ -0,0 +1,309 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+const MAX_HEADER_LINES: usize = 64;
+/// 请求行和每行请求头的长度上限
+const MAX_LINE_BYTES: u64 = 8 * 1024;
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+/// 读取请求行和全部请求头的总时限，请求体另有同样的时限
+const READ_TIMEOUT_SECS: u64 = 10;
+
+/// 最小化的 HTTP/1.1 请求，仅覆盖本地控制接口需要的部分
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(|value| value.as_str())
+    }
+
+    pub fn bearer_token(&self) -> Option<&str> {
+        let value = self.header("authorization")?;
+        let (scheme, token) = value.split_once(' ')?;
+        if scheme.eq_ignore_ascii_case("bearer") {
+            Some(token.trim())
+        } else {
+            None
+        }
+    }
+}
+
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub fn json<T: Serialize>(status: u16, value: &T) -> Self {
+        match serde_json::to_vec(value) {
+            Ok(body) => Self { status, body },
+            Err(e) => Self::error(500, format!("序列化响应失败: {e}")),
+        }
+    }
+
+    pub fn error(status: u16, message: impl Into<String>) -> Self {
+        let body = serde_json::json!({ "error": message.into() });
+        Self {
+            status,
+            body: body.to_string().into_bytes(),
+        }
+    }
+
+    pub fn write_to(&self, stream: &mut TcpStream) -> std::io::Result<()> {
+        let head = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status,
+            status_text(self.status),
+            self.body.len()
+        );
+        stream.write_all(head.as_bytes())?;
+        stream.write_all(&self.body)?;
+        stream.flush()
+    }
+}
+
+fn status_text(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        408 => "Request Timeout",
+        413 => "Payload Too Large",
+        414 => "URI Too Long",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
+
+/// 每次读取前把套接字超时设为距截止时间的剩余时长，逐字节拖延的客户端也会在截止时间后断开
+struct DeadlineReader<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+
+impl Read for DeadlineReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let remaining = self
+            .deadline
+            .checked_duration_since(Instant::now())
+            .filter(|remaining| !remaining.is_zero())
+            .ok_or(ErrorKind::TimedOut)?;
+        self.stream.set_read_timeout(Some(remaining))?;
+        let mut stream = self.stream;
+        stream.read(buf)
+    }
+}
+
+fn read_error(context: &str, error: std::io::Error) -> HttpResponse {
+    match error.kind() {
+        ErrorKind::TimedOut | ErrorKind::WouldBlock => {
+            HttpResponse::error(408, format!("{context}超时"))
+        }
+        _ => HttpResponse::error(400, format!("{context}失败: {error}")),
+    }
+}
+
+/// 读取一行，超过 MAX_LINE_BYTES 仍未遇到换行时返回 too_long 状态码
+fn read_limited_line<R: BufRead>(
+    reader: &mut R,
+    context: &str,
+    too_long: u16,
+) -> Result<String, HttpResponse> {
+    let mut line = String::new();
+    reader
+        .take(MAX_LINE_BYTES)
+        .read_line(&mut line)
+        .map_err(|e| read_error(context, e))?;
+    if !line.ends_with('\n') && line.len() as u64 >= MAX_LINE_BYTES {
+        return Err(HttpResponse::error(too_long, format!("{context}过长")));
+    }
+    Ok(line)
+}
+
+/// 从连接中读取一个请求；请求格式不合法时返回可直接回写的错误响应
+pub fn read_request(stream: &TcpStream) -> Result<HttpRequest, HttpResponse> {
+    let timeout = Duration::from_secs(READ_TIMEOUT_SECS);
+    let mut reader = BufReader::new(DeadlineReader {
+        stream,
+        deadline: Instant::now() + timeout,
+    });
+
+    let request_line = read_limited_line(&mut reader, "读取请求行", 414)?;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts
+        .next()
+        .ok_or_else(|| HttpResponse::error(400, "请求行为空"))?
+        .to_string();
+    let target = parts
+        .next()
+        .ok_or_else(|| HttpResponse::error(400, "请求行缺少路径"))?;
+    let (path, query) = split_target(target);
+
+    let mut headers = HashMap::new();
+    let mut header_lines = 0;
+    loop {
+        let line = read_limited_line(&mut reader, "读取请求头", 431)?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        header_lines += 1;
+        if header_lines > MAX_HEADER_LINES {
+            return Err(HttpResponse::error(431, "请求头过多"));
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let content_length = headers
+        .get("content-length")
+        .map(|value| value.parse::<usize>())
+        .transpose()
+        .map_err(|_| HttpResponse::error(400, "Content-Length 无效"))?
+        .unwrap_or(0);
+    if content_length > MAX_BODY_BYTES {
+        return Err(HttpResponse::error(413, "请求体过大"));
+    }
+
+    let mut body = vec![0u8; content_length];
+    reader.get_mut().deadline = Instant::now() + timeout;
+    reader
+        .read_exact(&mut body)
+        .map_err(|e| read_error("读取请求体", e))?;
+
+    Ok(HttpRequest {
+        method,
+        path,
+        query,
+        headers,
+        body,
+    })
+}
+
+fn split_target(target: &str) -> (String, HashMap<String, String>) {
+    let (path, raw_query) = target.split_once('?').unwrap_or((target, ""));
+    let query = raw_query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect();
+
+    (path.to_string(), query)
+}
+
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'%' if index + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[index + 1..index + 3]).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(value) => {
+                        decoded.push(value);
+                        index += 3;
+                    }
+                    Err(_) => {
+                        decoded.push(b'%');
+                        index += 1;
+                    }
+                }
+            }
+            b'+' => {
+                decoded.push(b' ');
+                index += 1;
+            }
+            other => {
+                decoded.push(other);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// 固定耗时比较，避免通过响应时间推测令牌
+pub fn constant_time_eq(left: &str, right: &str) -> bool {
+    let left = left.as_bytes();
+    let right = right.as_bytes();
+    if left.len() != right.len() {
+        return false;
+    }
+
+    left.iter()
+        .zip(right.iter())
+        .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+        == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{constant_time_eq, read_request, split_target, MAX_HEADER_LINES};
+    use std::io::Write;
+    use std::net::{TcpListener, TcpStream};
+
+    /// 发送原始请求并返回 read_request 的结果状态码，成功时为 200
+    fn status_for(raw: &[u8]) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        client.write_all(raw).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        match read_request(&server) {
+            Ok(_) => 200,
+            Err(response) => response.status,
+        }
+    }
+
+    #[test]
+    fn splits_path_and_decodes_query() {
+        let (path, query) = split_target("/api/v1/events?port=COM%203&session_id=7");
+        assert_eq!(path, "/api/v1/events");
+        assert_eq!(query.get("port").map(String::as_str), Some("COM 3"));
+        assert_eq!(query.get("session_id").map(String::as_str), Some("7"));
+    }
+
+    #[test]
+    fn limits_line_length_and_header_count() {
+        assert_eq!(status_for(b"GET /api HTTP/1.1\r\nHost: x\r\n\r\n"), 200);
+
+        let long_target = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(9000));
+        assert_eq!(status_for(long_target.as_bytes()), 414);
+
+        let long_header = format!("GET / HTTP/1.1\r\nX-Pad: {}\r\n\r\n", "a".repeat(9000));
+        assert_eq!(status_for(long_header.as_bytes()), 431);
+
+        let many_headers = format!(
+            "GET / HTTP/1.1\r\n{}\r\n",
+            "X-Pad: a\r\n".repeat(MAX_HEADER_LINES + 1)
+        );
+        assert_eq!(status_for(many_headers.as_bytes()), 431);
+    }
+
+    #[test]
+    fn compares_tokens() {
+        assert!(constant_time_eq("secret", "secret"));
+        assert!(!constant_time_eq("secret", "secreT"));
+        assert!(!constant_time_eq("secret", "secret2"));
+    }
+}
@@ -0,0 +1,7 @@
+pub mod events;
+pub mod http;
+pub mod server;
+pub mod websocket;
+
+pub use events::*;
+pub use server::*;
@@ -0,0 +1,338 @@
+use crate::commands::{
+    mass_production_state_of, refresh_mass_production, set_mass_production_auto_download,
+    start_mass_production_for_app, stop_mass_production,
+};
+use crate::remote::events::RemoteEventHub;
+use crate::remote::http::{constant_time_eq, read_request, HttpRequest, HttpResponse};
+use crate::remote::websocket::{is_upgrade_request, serve_event_stream};
+use crate::types::{MassProductionStartRequest, RemoteAutoDownloadRequest, RemoteRefreshRequest};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+
+pub const DEFAULT_REMOTE_API_PORT: u16 = 23819;
+const ACCEPT_POLL_INTERVAL_MS: u64 = 100;
+const EVENTS_PATH: &str = "/api/v1/events";
+/// 同时处理的连接数上限，包括事件流；每个连接占用一个线程
+const MAX_CONNECTIONS: usize = 32;
+
+/// 正在处理的连接；用于限制并发数，停止服务时关闭仍打开的连接
+#[derive(Default)]
+struct Connections {
+    next_id: AtomicU64,
+    open: Mutex<HashMap<u64, TcpStream>>,
+}
+
+impl Connections {
+    /// 已达上限或无法复制连接句柄时返回 None
+    fn register(self: &Arc<Self>, stream: &TcpStream) -> Option<ConnectionGuard> {
+        let mut open = self.open.lock().unwrap();
+        if open.len() >= MAX_CONNECTIONS {
+            return None;
+        }
+        let handle = stream.try_clone().ok()?;
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        open.insert(id, handle);
+        Some(ConnectionGuard {
+            connections: self.clone(),
+            id,
+        })
+    }
+
+    /// 关闭后阻塞中的读写立即返回，事件流的读写线程随之退出
+    fn shutdown_all(&self) {
+        for (_, stream) in self.open.lock().unwrap().drain() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+struct ConnectionGuard {
+    connections: Arc<Connections>,
+    id: u64,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.connections.open.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// 仅监听 127.0.0.1 的量产控制接口，所有请求都需要 Bearer 令牌
+pub struct RemoteApiServer {
+    address: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+    connections: Arc<Connections>,
+    accept_thread: Option<JoinHandle<()>>,
+}
+
+impl RemoteApiServer {
+    pub fn start<R: Runtime>(
+        app_handle: AppHandle<R>,
+        port: u16,
+        token: String,
+    ) -> Result<Self, String> {
+        if token.trim().is_empty() {
+            return Err("远程控制接口令牌不能为空".to_string());
+        }
+
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .map_err(|e| format!("远程控制接口监听端口 {port} 失败: {e}"))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("设置远程控制接口非阻塞模式失败: {e}"))?;
+        let address = listener
+            .local_addr()
+            .map_err(|e| format!("获取远程控制接口地址失败: {e}"))?;
+
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let shutdown_for_thread = shutdown.clone();
+        let connections = Arc::new(Connections::default());
+        let connections_for_thread = connections.clone();
+        let token = Arc::new(token);
+
+        let accept_thread = thread::spawn(move || {
+            while !shutdown_for_thread.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => match connections_for_thread.register(&stream) {
+                        Some(guard) => {
+                            let app_handle = app_handle.clone();
+                            let token = token.clone();
+                            thread::spawn(move || {
+                                let _guard = guard;
+                                handle_connection(app_handle, &token, stream);
+                            });
+                        }
+                        None => reject_connection(stream),
+                    },
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                        thread::sleep(Duration::from_millis(ACCEPT_POLL_INTERVAL_MS));
+                    }
+                    Err(e) => {
+                        eprintln!("Remote API accept failed: {e}");
+                        thread::sleep(Duration::from_millis(ACCEPT_POLL_INTERVAL_MS));
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            address,
+            shutdown,
+            connections,
+            accept_thread: Some(accept_thread),
+        })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    pub fn stop(mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.accept_thread.take() {
+            let _ = handle.join();
+        }
+        self.connections.shutdown_all();
+    }
+}
+
+/// 在接受线程中直接回复，不等待客户端发送请求
+fn reject_connection(mut stream: TcpStream) {
+    let _ = stream.set_nonblocking(true);
+    let _ = HttpResponse::error(503, "连接数已达上限，请稍后重试").write_to(&mut stream);
+    let _ = stream.shutdown(Shutdown::Both);
+}
+
+fn handle_connection<R: Runtime>(app_handle: AppHandle<R>, token: &str, mut stream: TcpStream) {
+    let _ = stream.set_nonblocking(false);
+
+    let response = match read_request(&stream) {
+        Ok(request) => match authorize(&request, token) {
+            Ok(()) if request.path == EVENTS_PATH => {
+                serve_events(&app_handle, &request, stream);
+                return;
+            }
+            Ok(()) => route(&app_handle, &request),
+            Err(response) => response,
+        },
+        Err(response) => response,
+    };
+
+    if let Err(e) = response.write_to(&mut stream) {
+        eprintln!("Remote API failed to write response: {e}");
+    }
+}
+
+// 浏览器的 WebSocket 无法设置请求头，升级请求允许通过 ?token= 传递令牌
+fn authorize(request: &HttpRequest, token: &str) -> Result<(), HttpResponse> {
+    let provided = request.bearer_token().or_else(|| {
+        is_upgrade_request(request)
+            .then(|| request.query.get("token").map(String::as_str))
+            .flatten()
+    });
+
+    match provided {
+        Some(provided) if constant_time_eq(provided, token) => Ok(()),
+        _ => Err(HttpResponse::error(401, "缺少或无效的访问令牌")),
+    }
+}
+
+fn serve_events<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    request: &HttpRequest,
+    mut stream: TcpStream,
+) {
+    if request.method != "GET" || !is_upgrade_request(request) {
+        let _ = HttpResponse::error(400, "事件流需要 WebSocket 升级请求").write_to(&mut stream);
+        return;
+    }
+
+    let Some(hub) = app_handle.try_state::<RemoteEventHub>() else {
+        let _ = HttpResponse::error(500, "事件广播未初始化").write_to(&mut stream);
+        return;
+    };
+    let initial_snapshot = mass_production_state_of(app_handle)
+        .ok()
+        .map(|mass_state| mass_state.lock().unwrap().to_snapshot());
+
+    serve_event_stream(&hub, initial_snapshot, request, stream);
+}
+
+fn parse_body<T: DeserializeOwned>(request: &HttpRequest) -> Result<T, HttpResponse> {
+    serde_json::from_slice(&request.body)
+        .map_err(|e| HttpResponse::error(400, format!("请求体 JSON 无效: {e}")))
+}
+
+fn route<R: Runtime>(app_handle: &AppHandle<R>, request: &HttpRequest) -> HttpResponse {
+    let mass_state = match mass_production_state_of(app_handle) {
+        Ok(mass_state) => mass_state,
+        Err(e) => return HttpResponse::error(500, e),
+    };
+
+    let result = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/api/v1/mass-production/snapshot") => Ok(mass_state.lock().unwrap().to_snapshot()),
+        ("POST", "/api/v1/mass-production/start") => {
+            match parse_body::<MassProductionStartRequest>(request) {
+                Ok(body) => start_mass_production_for_app(app_handle, body),
+                Err(response) => return response,
+            }
+        }
+        ("POST", "/api/v1/mass-production/stop") => {
+            Ok(stop_mass_production(app_handle, &mass_state))
+        }
+        ("POST", "/api/v1/mass-production/refresh") => {
+            let body = if request.body.is_empty() {
+                RemoteRefreshRequest {
+                    trigger_flash: false,
+                }
+            } else {
+                match parse_body::<RemoteRefreshRequest>(request) {
+                    Ok(body) => body,
+                    Err(response) => return response,
+                }
+            };
+            refresh_mass_production(app_handle, &mass_state, body.trigger_flash)
+        }
+        ("POST", "/api/v1/mass-production/auto-download") => {
+            match parse_body::<RemoteAutoDownloadRequest>(request) {
+                Ok(body) => {
+                    set_mass_production_auto_download(app_handle, &mass_state, body.auto_download)
+                }
+                Err(response) => return response,
+            }
+        }
+        (_, path) if path.starts_with("/api/v1/mass-production/") => {
+            return HttpResponse::error(405, "不支持的请求方法");
+        }
+        _ => return HttpResponse::error(404, "接口不存在"),
+    };
+
+    match result {
+        Ok(snapshot) => HttpResponse::json(200, &snapshot),
+        Err(e) => HttpResponse::error(409, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{authorize, Connections, MAX_CONNECTIONS};
+    use crate::remote::http::HttpRequest;
+    use std::collections::HashMap;
+    use std::io::Read;
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::Arc;
+
+    fn request_with_authorization(value: Option<&str>) -> HttpRequest {
+        let mut headers = HashMap::new();
+        if let Some(value) = value {
+            headers.insert("authorization".to_string(), value.to_string());
+        }
+
+        HttpRequest {
+            method: "GET".to_string(),
+            path: "/api/v1/mass-production/snapshot".to_string(),
+            query: HashMap::new(),
+            headers,
+            body: Vec::new(),
+        }
+    }
+
+    fn request_with_query_token(token: &str, upgrade: bool) -> HttpRequest {
+        let mut request = request_with_authorization(None);
+        request.path = "/api/v1/events".to_string();
+        request.query.insert("token".to_string(), token.to_string());
+        if upgrade {
+            request
+                .headers
+                .insert("upgrade".to_string(), "websocket".to_string());
+        }
+        request
+    }
+
+    #[test]
+    fn requires_matching_bearer_token() {
+        assert!(authorize(&request_with_authorization(Some("Bearer abc")), "abc").is_ok());
+        assert!(authorize(&request_with_authorization(Some("bearer abc")), "abc").is_ok());
+        assert!(authorize(&request_with_authorization(Some("Bearer abd")), "abc").is_err());
+        assert!(authorize(&request_with_authorization(Some("Basic abc")), "abc").is_err());
+        assert!(authorize(&request_with_authorization(None), "abc").is_err());
+    }
+
+    #[test]
+    fn accepts_query_token_only_for_websocket_upgrade() {
+        assert!(authorize(&request_with_query_token("abc", true), "abc").is_ok());
+        assert!(authorize(&request_with_query_token("abd", true), "abc").is_err());
+        assert!(authorize(&request_with_query_token("abc", false), "abc").is_err());
+    }
+
+    #[test]
+    fn limits_connections_and_closes_them_on_shutdown() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let connections = Arc::new(Connections::default());
+
+        let mut clients = Vec::new();
+        let mut guards = Vec::new();
+        for _ in 0..MAX_CONNECTIONS {
+            clients.push(TcpStream::connect(address).unwrap());
+            let (stream, _) = listener.accept().unwrap();
+            guards.push(connections.register(&stream).unwrap());
+        }
+        let _extra = TcpStream::connect(address).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        assert!(connections.register(&stream).is_none());
+
+        guards.pop();
+        assert!(connections.register(&stream).is_some());
+
+        connections.shutdown_all();
+        let mut buf = [0u8; 1];
+        assert_eq!(clients[0].read(&mut buf).unwrap(), 0);
+    }
+}
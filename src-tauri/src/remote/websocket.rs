@@ -0,0 +1,216 @@
+use crate::remote::events::{snapshot_message, EventFilter, RemoteEventHub};
+use crate::remote::http::{HttpRequest, HttpResponse};
+use crate::types::MassProductionSnapshot;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use sha1::{Digest, Sha1};
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+const MAX_CLIENT_FRAME_BYTES: u64 = 64 * 1024;
+const SEND_POLL_INTERVAL_MS: u64 = 500;
+
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+pub fn is_upgrade_request(request: &HttpRequest) -> bool {
+    request
+        .header("upgrade")
+        .is_some_and(|value| value.eq_ignore_ascii_case("websocket"))
+}
+
+pub fn accept_key(client_key: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(client_key.trim().as_bytes());
+    hasher.update(HANDSHAKE_GUID.as_bytes());
+    BASE64.encode(hasher.finalize())
+}
+
+/// 服务端发往客户端的帧不加掩码
+pub fn encode_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// 读取一个客户端帧，返回 (opcode, 去掩码后的负载)
+fn read_frame(stream: &mut TcpStream) -> std::io::Result<(u8, Vec<u8>)> {
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header)?;
+    let opcode = header[0] & 0x0F;
+    let masked = header[1] & 0x80 != 0;
+
+    let len = match header[1] & 0x7F {
+        126 => {
+            let mut bytes = [0u8; 2];
+            stream.read_exact(&mut bytes)?;
+            u16::from_be_bytes(bytes) as u64
+        }
+        127 => {
+            let mut bytes = [0u8; 8];
+            stream.read_exact(&mut bytes)?;
+            u64::from_be_bytes(bytes)
+        }
+        len => len as u64,
+    };
+    if len > MAX_CLIENT_FRAME_BYTES {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "client frame too large",
+        ));
+    }
+
+    let mut mask = [0u8; 4];
+    if masked {
+        stream.read_exact(&mut mask)?;
+    }
+
+    let mut payload = vec![0u8; len as usize];
+    stream.read_exact(&mut payload)?;
+    if masked {
+        for (index, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[index % 4];
+        }
+    }
+
+    Ok((opcode, payload))
+}
+
+fn write_frame(writer: &Mutex<TcpStream>, opcode: u8, payload: &[u8]) -> std::io::Result<()> {
+    let mut stream = writer.lock().unwrap();
+    stream.write_all(&encode_frame(opcode, payload))?;
+    stream.flush()
+}
+
+/// 完成握手后持续推送事件，直到客户端断开或关闭
+pub fn serve_event_stream(
+    hub: &RemoteEventHub,
+    initial_snapshot: Option<MassProductionSnapshot>,
+    request: &HttpRequest,
+    mut stream: TcpStream,
+) {
+    let filter = match EventFilter::from_query(&request.query) {
+        Ok(filter) => filter,
+        Err(e) => {
+            let _ = HttpResponse::error(400, e).write_to(&mut stream);
+            return;
+        }
+    };
+    let Some(client_key) = request.header("sec-websocket-key") else {
+        let _ = HttpResponse::error(400, "缺少 Sec-WebSocket-Key").write_to(&mut stream);
+        return;
+    };
+
+    let handshake = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(client_key)
+    );
+    if stream.write_all(handshake.as_bytes()).is_err() {
+        return;
+    }
+    let _ = stream.set_read_timeout(None);
+
+    let mut reader = match stream.try_clone() {
+        Ok(reader) => reader,
+        Err(e) => {
+            eprintln!("Remote API failed to clone event stream: {e}");
+            return;
+        }
+    };
+    let writer = Arc::new(Mutex::new(stream));
+    let closed = Arc::new(AtomicBool::new(false));
+
+    // 客户端帧只处理 ping/close，其余内容忽略
+    let reader_writer = writer.clone();
+    let reader_closed = closed.clone();
+    thread::spawn(move || {
+        loop {
+            match read_frame(&mut reader) {
+                Ok((OPCODE_PING, payload)) => {
+                    if write_frame(&reader_writer, OPCODE_PONG, &payload).is_err() {
+                        break;
+                    }
+                }
+                Ok((OPCODE_CLOSE, _)) => {
+                    let _ = write_frame(&reader_writer, OPCODE_CLOSE, &[]);
+                    break;
+                }
+                Ok(_) => {}
+                Err(_) => break,
+            }
+        }
+        reader_closed.store(true, Ordering::Relaxed);
+        let _ = reader.shutdown(Shutdown::Both);
+    });
+
+    let (subscriber_id, receiver) = hub.subscribe(filter.clone());
+
+    // 先推送一次当前快照，订阅者无需再单独轮询初始状态
+    if let Some(snapshot) = initial_snapshot
+        .as_ref()
+        .and_then(|snapshot| filter.filter_snapshot(snapshot))
+    {
+        let _ = write_frame(&writer, OPCODE_TEXT, snapshot_message(&snapshot).as_bytes());
+    }
+
+    while !closed.load(Ordering::Relaxed) {
+        match receiver.recv_timeout(Duration::from_millis(SEND_POLL_INTERVAL_MS)) {
+            Ok(message) => {
+                if write_frame(&writer, OPCODE_TEXT, message.as_bytes()).is_err() {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    hub.unsubscribe(subscriber_id);
+    let _ = writer.lock().unwrap().shutdown(Shutdown::Both);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{accept_key, encode_frame, OPCODE_TEXT};
+
+    #[test]
+    fn computes_rfc6455_accept_key() {
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn encodes_frame_lengths() {
+        assert_eq!(encode_frame(OPCODE_TEXT, b"hi"), vec![0x81, 2, b'h', b'i']);
+
+        let medium = encode_frame(OPCODE_TEXT, &[0u8; 300]);
+        assert_eq!(&medium[..4], &[0x81, 126, 0x01, 0x2C]);
+        assert_eq!(medium.len(), 304);
+
+        let large = encode_frame(OPCODE_TEXT, &[0u8; 70_000]);
+        assert_eq!(large[1], 127);
+        assert_eq!(&large[2..10], &70_000u64.to_be_bytes());
+    }
+}
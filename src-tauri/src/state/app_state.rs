@@ -1,15 +1,25 @@
+use crate::progress::TauriProgressCallback;
+use crate::remote::RemoteApiServer;
 use crate::state::MassProductionState;
-use crate::types::DeviceConfig;
-use sftool_lib::SifliTool;
+use crate::types::{DeviceConfig, OperationError};
+use sftool_lib::{CancelToken, SifliTool};
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
 pub struct AppState {
     pub device_config: Option<DeviceConfig>,
     pub sftool: Option<Arc<Mutex<Box<dyn SifliTool>>>>,
+    /// 创建 sftool 时传入的取消令牌，用于中止单设备操作
+    pub cancel_token: Option<CancelToken>,
+    /// 创建 sftool 时传入的进度回调，写入失败时据此判断出错阶段
+    pub progress_callback: Option<Arc<TauriProgressCallback>>,
+    /// 正在执行的单设备操作，同一时间只允许一个
+    pub running_operation: Option<&'static str>,
     pub mass_production: Arc<Mutex<MassProductionState>>,
     /// 临时目录列表，这些目录由后端创建并在应用退出时清理
     pub retained_temp_dirs: Vec<PathBuf>,
+    /// 本地量产控制接口，默认关闭
+    pub remote_api: Option<RemoteApiServer>,
 }
 
 impl Default for AppState {
@@ -17,8 +27,12 @@ impl Default for AppState {
         AppState {
             device_config: None,
             sftool: None,
+            cancel_token: None,
+            progress_callback: None,
+            running_operation: None,
             mass_production: Arc::new(Mutex::new(MassProductionState::default())),
             retained_temp_dirs: Vec::new(),
+            remote_api: None,
         }
     }
 }
@@ -27,6 +41,38 @@ impl AppState {
     pub fn clear_device_connection(&mut self) {
         self.device_config = None;
         self.sftool = None;
+        self.cancel_token = None;
+        self.progress_callback = None;
+    }
+
+    /// 没有单设备操作进行中时才允许连接、断开等会替换工具实例的操作
+    pub fn ensure_idle(&self) -> Result<(), OperationError> {
+        match self.running_operation {
+            Some(running) => Err(OperationError::busy(running)),
+            None => Ok(()),
+        }
+    }
+
+    /// 标记操作开始；已有操作进行中时立即返回 busy 错误
+    pub fn mark_running(&mut self, name: &'static str) -> Result<(), OperationError> {
+        self.ensure_idle()?;
+        self.running_operation = Some(name);
+        Ok(())
+    }
+
+    /// 标记操作开始并返回要使用的工具实例；已有操作进行中时立即返回 busy 错误
+    pub fn begin_operation(
+        &mut self,
+        name: &'static str,
+    ) -> Result<Arc<Mutex<Box<dyn SifliTool>>>, OperationError> {
+        self.ensure_idle()?;
+        let sftool = self.sftool.clone().ok_or("设备未连接，请先连接设备")?;
+        self.running_operation = Some(name);
+        Ok(sftool)
+    }
+
+    pub fn finish_operation(&mut self) {
+        self.running_operation = None;
     }
 
     pub fn register_temp_dir(&mut self, path: PathBuf) {
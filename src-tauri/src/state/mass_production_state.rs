@@ -1,9 +1,13 @@
 use crate::types::{
-    MassProductionPortInfo, MassProductionPortStatus, MassProductionSnapshot,
-    MassProductionStartRequest,
+    FlashErrorCategory, FlashErrorCount, MassProductionPortInfo, MassProductionPortStatus,
+    MassProductionSnapshot, MassProductionStartRequest,
+};
+use crate::utils::{
+    FrozenFirmware, SerialPool, SerialPoolWrite, UnitDataRenderer, UnitIndexAllocator,
 };
 use sftool_lib::CancelToken;
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
 use std::thread::JoinHandle;
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -25,6 +29,15 @@ impl PortIdentity {
     }
 }
 
+/// 安排了重试的端口为同一块板子保留的设备序号和序列号，直到最终成功或失败才结算
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RetryHold {
+    pub unit_index: Option<u64>,
+    pub serial: Option<String>,
+    /// 之前的尝试已经开始写入
+    pub write_started: bool,
+}
+
 pub struct MassProductionState {
     pub running: bool,
     pub pending_trigger_flash: bool,
@@ -34,14 +47,42 @@ pub struct MassProductionState {
     pub active_ports: HashSet<String>,
     pub active_cancel_tokens: HashMap<String, CancelToken>,
     pub session_id: u64,
+    /// 会话的持久标识，写入追溯记录；session_id 每次启动程序都会从 1 重新计数
+    pub session_key: String,
     pub started_at: Option<u64>,
     pub ended_at: Option<u64>,
     pub manual_stopped: bool,
     pub success_count: u32,
     pub cancelled_count: u32,
     pub failed_count: u32,
+    pub failure_counts: HashMap<FlashErrorCategory, u32>,
+    /// 等待自动重试的端口及到期时间；到期后移入 queue
+    pub retry_queue: Vec<(String, u64)>,
+    pub retry_count: u32,
     pub hotplug_connected: Vec<PortIdentity>,
     pub supervisor_thread: Option<JoinHandle<()>>,
+    /// 本次会话锁定的固件副本，所有 worker 从副本写入
+    pub frozen_firmware: Option<FrozenFirmware>,
+    pub abort_reason: Option<String>,
+    /// 出厂数据模板，未配置或校验模式下为 None
+    pub unit_data: Option<Arc<UnitDataRenderer>>,
+    /// 设备序号分配，CSV 数据行按序号取用
+    pub unit_indices: UnitIndexAllocator,
+    /// 等待重试的端口保留的设备序号和序列号
+    pub retry_holds: HashMap<String, RetryHold>,
+    /// 本次会话使用的序列号池；变更在内存中生效，由 worker 释放状态锁后写盘
+    pub serial_pool: Option<SerialPool>,
+    /// 端口离开重试队列时在锁内结算产生的池内容和日志，由监督线程或停止流程在锁外写出
+    pub pending_pool_write: Option<SerialPoolWrite>,
+    pub settlement_notes: Vec<String>,
+}
+
+/// 启动时间加随机后缀，重启程序后也不会与历史会话重复
+fn new_session_key(started_at: u64) -> String {
+    let mut suffix = [0u8; 4];
+    // 取随机数失败时仍有毫秒级的启动时间区分会话
+    let _ = getrandom::getrandom(&mut suffix);
+    format!("{started_at}-{:08x}", u32::from_be_bytes(suffix))
 }
 
 impl Default for MassProductionState {
@@ -55,14 +96,26 @@ impl Default for MassProductionState {
             active_ports: HashSet::new(),
             active_cancel_tokens: HashMap::new(),
             session_id: 0,
+            session_key: String::new(),
             started_at: None,
             ended_at: None,
             manual_stopped: false,
             success_count: 0,
             cancelled_count: 0,
             failed_count: 0,
+            failure_counts: HashMap::new(),
+            retry_queue: Vec::new(),
+            retry_count: 0,
             hotplug_connected: Vec::new(),
             supervisor_thread: None,
+            frozen_firmware: None,
+            abort_reason: None,
+            unit_data: None,
+            unit_indices: UnitIndexAllocator::default(),
+            retry_holds: HashMap::new(),
+            serial_pool: None,
+            pending_pool_write: None,
+            settlement_notes: Vec::new(),
         }
     }
 }
@@ -71,26 +124,55 @@ impl MassProductionState {
     pub fn reset_for_start(
         &mut self,
         request: MassProductionStartRequest,
+        frozen_firmware: FrozenFirmware,
+        unit_data: Option<UnitDataRenderer>,
+        serial_pool: Option<SerialPool>,
         session_id: u64,
         started_at: u64,
     ) {
         self.running = true;
         self.pending_trigger_flash = true;
         self.request = Some(request);
+        self.frozen_firmware = Some(frozen_firmware);
+        self.unit_indices =
+            UnitIndexAllocator::new(unit_data.as_ref().and_then(UnitDataRenderer::capacity));
+        self.unit_data = unit_data.map(Arc::new);
+        self.retry_holds.clear();
+        self.serial_pool = serial_pool;
+        self.pending_pool_write = None;
+        self.settlement_notes.clear();
+        self.abort_reason = None;
         self.ports.clear();
         self.queue.clear();
         self.active_ports.clear();
         self.active_cancel_tokens.clear();
         self.session_id = session_id;
+        self.session_key = new_session_key(started_at);
         self.started_at = Some(started_at);
         self.ended_at = None;
         self.manual_stopped = false;
         self.success_count = 0;
         self.cancelled_count = 0;
         self.failed_count = 0;
+        self.failure_counts.clear();
+        self.retry_queue.clear();
+        self.retry_count = 0;
         self.hotplug_connected.clear();
     }
 
+    fn failure_counts(&self) -> Vec<FlashErrorCount> {
+        let mut counts: Vec<FlashErrorCount> = self
+            .failure_counts
+            .iter()
+            .map(|(category, count)| FlashErrorCount {
+                category: *category,
+                count: *count,
+            })
+            .collect();
+        counts.sort_by(|a, b| b.count.cmp(&a.count).then(a.category.cmp(&b.category)));
+        counts
+    }
+
     pub fn to_snapshot(&self) -> MassProductionSnapshot {
         let mut ports: Vec<MassProductionPortInfo> = self.ports.values().cloned().collect();
         ports.sort_by(|a, b| a.name.cmp(&b.name));
@@ -117,11 +199,13 @@ impl MassProductionState {
             is_running: self.running,
             is_enabled,
             session_id: self.session_id,
+            session_key: self.session_key.clone(),
             started_at: self.started_at,
             ended_at: self.ended_at,
             manual_stopped: self.manual_stopped,
             chip_model: self.request.as_ref().map(|r| r.chip_model.clone()),
             memory_type: self.request.as_ref().map(|r| r.memory_type.clone()),
+            mode: self.request.as_ref().map(|r| r.mode).unwrap_or_default(),
             auto_download: self
                 .request
                 .as_ref()
@@ -139,6 +223,15 @@ impl MassProductionState {
             failed_count: self.failed_count,
             total_count,
             ports,
+            frozen_files: self
+                .frozen_firmware
+                .as_ref()
+                .map(FrozenFirmware::infos)
+                .unwrap_or_default(),
+            abort_reason: self.abort_reason.clone(),
+            serial_pool: self.serial_pool.as_ref().map(SerialPool::status),
+            failure_counts: self.failure_counts(),
+            retry_count: self.retry_count,
         }
     }
 }
@@ -0,0 +1,113 @@
+use crate::types::{ExtractedFile, SftoolParamParseResult};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const DEFAULT_MAX_TOTAL_SIZE: u64 = 1024 * 1024 * 1024;
+const DEFAULT_MAX_ENTRIES: usize = 10_000;
+
+/// 解压限制，防止压缩炸弹
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct ArchiveLimits {
+    /// 解压后所有文件的总字节数上限
+    pub max_total_size: u64,
+    /// 条目数量上限（包含目录）
+    pub max_entries: usize,
+}
+
+impl Default for ArchiveLimits {
+    fn default() -> Self {
+        Self {
+            max_total_size: DEFAULT_MAX_TOTAL_SIZE,
+            max_entries: DEFAULT_MAX_ENTRIES,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveErrorKind {
+    NotFound,
+    PathTraversal,
+    Symlink,
+    UnsupportedFormat,
+    TooManyEntries,
+    TooLarge,
+    Io,
+}
+
+/// 解压失败原因；拒绝解压时 entry 为触发拒绝的条目名
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ArchiveExtractError {
+    pub kind: ArchiveErrorKind,
+    pub entry: Option<String>,
+    pub message: String,
+}
+
+impl ArchiveExtractError {
+    pub fn new(kind: ArchiveErrorKind, entry: Option<&str>, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            entry: entry.map(str::to_string),
+            message: message.into(),
+        }
+    }
+
+    pub fn io(entry: Option<&str>, message: impl Into<String>) -> Self {
+        Self::new(ArchiveErrorKind::Io, entry, message)
+    }
+}
+
+impl fmt::Display for ArchiveExtractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ArchiveExtractError {}
+
+impl From<ArchiveExtractError> for String {
+    fn from(error: ArchiveExtractError) -> Self {
+        error.message
+    }
+}
+
+/// 解压目录中识别到的烧录配置来源
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveRecipeSource {
+    /// sftool_param.json
+    SftoolParam,
+    /// .sfpkg 固件包清单
+    PackageManifest,
+    /// SiFli SDK 的 build_* 构建目录
+    SdkBuild,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveExtractResult {
+    /// 解压出的全部文件；识别到配置时已填入配置中的地址
+    pub files: Vec<ExtractedFile>,
+    #[serde(
+        rename = "recipeSource",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub recipe_source: Option<ArchiveRecipeSource>,
+    /// 配置文件、清单或构建目录的路径
+    #[serde(
+        rename = "recipePath",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub recipe_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipe: Option<SftoolParamParseResult>,
+    /// 识别到配置但解析失败时的原因，此时仍返回解压出的文件
+    #[serde(
+        rename = "recipeError",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub recipe_error: Option<String>,
+}
@@ -1,32 +1,89 @@
+use crate::types::{DeviceConfig, MassProductionStartRequest, WriteFlashRequest};
 use serde::{Deserialize, Serialize};
 
-// sftool_param.json 相关类型定义
+// sftool_param.json 相关类型定义，未设置的可选字段导出时省略
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SftoolParamFile {
     pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub address: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WriteFlashCommand {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub verify: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub erase_all: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub no_compress: Option<bool>,
     pub files: Vec<SftoolParamFile>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SftoolParamReadFile {
+    pub path: String,
+    pub address: String,
+    pub size: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadFlashCommand {
+    pub files: Vec<SftoolParamReadFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EraseFlashCommand {
+    pub address: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SftoolParamRegion {
+    pub address: String,
+    pub size: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EraseRegionCommand {
+    pub regions: Vec<SftoolParamRegion>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SftoolParamConfig {
     pub chip: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub memory: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub port: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub baud: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub before: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub after: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub connect_attempts: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub compat: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub quiet: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub write_flash: Option<WriteFlashCommand>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_flash: Option<ReadFlashCommand>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub erase_flash: Option<EraseFlashCommand>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub erase_region: Option<EraseRegionCommand>,
+}
+
+impl SftoolParamConfig {
+    pub fn has_any_command(&self) -> bool {
+        self.write_flash.is_some()
+            || self.read_flash.is_some()
+            || self.erase_flash.is_some()
+            || self.erase_region.is_some()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -52,15 +109,45 @@ pub struct ConfigValidationResult {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ExtractedFile {
     pub path: String,
+    /// ELF/HEX 文件未指定地址时为空，由文件自身决定
     pub address: String,
     pub name: String,
     pub size: u64,
 }
 
+/// read_flash 中的读取任务，输出路径已按配置文件所在目录解析
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExtractedReadFile {
+    pub path: String,
+    pub address: String,
+    pub size: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum SftoolParamExportSource {
+    WriteFlash {
+        request: WriteFlashRequest,
+        device: DeviceConfig,
+    },
+    MassProduction {
+        request: MassProductionStartRequest,
+    },
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SftoolParamParseResult {
     pub config: SftoolParamConfig,
     pub validation: ConfigValidationResult,
     #[serde(rename = "extractedFiles")]
     pub extracted_files: Vec<ExtractedFile>,
+    #[serde(rename = "readFiles", default)]
+    pub read_files: Vec<ExtractedReadFile>,
+    /// 固件包中附带的 Stub 配置文件，普通 sftool_param.json 没有此项
+    #[serde(
+        rename = "stubConfigPath",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub stub_config_path: Option<String>,
 }
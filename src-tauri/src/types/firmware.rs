@@ -0,0 +1,152 @@
+use crate::types::WriteFlashFileInfo;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FirmwareFormat {
+    Bin,
+    Hex,
+    Elf,
+}
+
+/// 固件中一段会被写入 Flash 的连续数据
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct FirmwareSegment {
+    pub start_address: u32,
+    pub length: u64,
+    /// 段数据在文件中的偏移；HEX 为文本格式，没有对应的二进制偏移
+    pub file_offset: Option<u64>,
+}
+
+impl FirmwareSegment {
+    pub fn end_address(&self) -> u64 {
+        self.start_address as u64 + self.length
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FirmwareImageInfo {
+    pub file_path: String,
+    pub format: FirmwareFormat,
+    pub file_size: u64,
+    pub entry_point: Option<u32>,
+    pub segments: Vec<FirmwareSegment>,
+    /// 所有段的数据总长度
+    pub total_size: u64,
+    pub lowest_address: Option<u32>,
+    /// 最后一个字节之后的地址（不含）
+    pub highest_address: Option<u64>,
+}
+
+/// 写入计划中某个文件（或其中一个段）占用的地址范围，end 不含
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct FileAddressRange {
+    pub file_path: String,
+    pub start: u64,
+    pub end: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct FileOverlap {
+    pub first: FileAddressRange,
+    pub second: FileAddressRange,
+}
+
+/// 文件内容摘要；crc32 与 sha256 均为小写十六进制
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct FileHash {
+    pub file_path: String,
+    pub size: u64,
+    pub crc32: String,
+    pub sha256: String,
+    /// 请求中给出的期望摘要
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_hash: Option<String>,
+    /// 未给出期望摘要时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matches: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MergeImagesRequest {
+    /// 与 WriteFlashRequest 相同的文件列表，BIN 需指定地址，HEX/ELF 地址为 0
+    pub files: Vec<WriteFlashFileInfo>,
+    pub output_path: String,
+    /// 合并镜像的起始地址，缺省为所有段中的最低地址
+    #[serde(default)]
+    pub base_address: Option<u32>,
+    /// 同时输出 Intel HEX 的路径
+    #[serde(default)]
+    pub hex_output_path: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MergeImagesResult {
+    pub output_path: String,
+    pub hex_output_path: Option<String>,
+    pub base_address: u32,
+    pub size: u64,
+    /// 各文件在合并镜像中占用的地址范围
+    pub segments: Vec<FileAddressRange>,
+    pub crc32: String,
+    pub sha256: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompareFlashDumpRequest {
+    /// read_flash 读出的文件
+    pub dump_path: String,
+    /// 读出文件对应的 Flash 起始地址
+    pub dump_address: u32,
+    /// 源镜像，格式与 WriteFlashRequest 相同
+    pub files: Vec<WriteFlashFileInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DumpRegionStatus {
+    Match,
+    Mismatch,
+    /// 源镜像超出读出范围，或读出范围内没有源镜像
+    Uncovered,
+}
+
+/// 对比报告中的一段地址范围，end 不含
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DumpRegionReport {
+    /// 读出范围内没有源镜像覆盖的区域为空
+    pub file_path: Option<String>,
+    pub start: u64,
+    pub end: u64,
+    pub status: DumpRegionStatus,
+    /// 第一个不同字节的 Flash 地址
+    pub first_mismatch_address: Option<u64>,
+    /// 第一个不同字节在读出文件中的偏移
+    pub first_mismatch_offset: Option<u64>,
+    pub mismatch_bytes: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FlashDumpComparison {
+    pub dump_path: String,
+    pub dump_address: u32,
+    pub dump_size: u64,
+    /// 按地址排序
+    pub regions: Vec<DumpRegionReport>,
+    pub matched_bytes: u64,
+    pub mismatched_bytes: u64,
+    /// 源镜像中未被读出范围覆盖的字节数
+    pub uncovered_bytes: u64,
+    /// 所有源镜像均被覆盖且内容一致
+    pub is_match: bool,
+}
+
+/// 回读校验的单个文件结果
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct VerifyFlashFileResult {
+    pub file_path: String,
+    pub passed: bool,
+    pub checked_bytes: u64,
+    pub mismatch_bytes: u64,
+    pub first_mismatch_address: Option<u64>,
+}
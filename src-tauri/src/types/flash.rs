@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use sftool_lib::{utils::Utils, ReadFlashFile, ReadFlashParams, WriteFlashFile, WriteFlashParams};
+use std::fmt;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WriteFlashRequest {
@@ -12,6 +14,9 @@ pub struct WriteFlashRequest {
 pub struct WriteFlashFileInfo {
     pub address: u32,
     pub file_path: String,
+    /// 期望的 SHA-256 或 CRC32，不一致时拒绝写入
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_hash: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -25,3 +30,248 @@ pub struct ReadFlashFileInfo {
     pub address: u32,
     pub size: u32,
 }
+
+/// peek_flash 单次最多读取的字节数
+pub const PEEK_FLASH_MAX_SIZE: u32 = 64 * 1024;
+
+/// peek_flash 读到内存的一段 Flash，data 为 base64 编码
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PeekFlashResult {
+    pub address: u32,
+    pub size: u32,
+    pub data: String,
+}
+
+/// 待擦除的区域，一次调用可包含多个
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct EraseRegionInfo {
+    pub address: u32,
+    pub size: u32,
+}
+
+/// 烧录失败的类别，用于统计良率和失败原因排行
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum FlashErrorCategory {
+    /// 串口打开失败、被占用或权限不足
+    PortOpen,
+    /// 同步/握手超时，芯片未响应
+    Sync,
+    StubDownload,
+    Erase,
+    Write,
+    VerifyMismatch,
+    Cancelled,
+    Panic,
+    Disconnect,
+    /// 芯片型号、Stub 配置等本地参数无效，尚未接触设备
+    Config,
+    Other,
+}
+
+/// 带类别的烧录错误；从 String 转换得到的错误类别为 Other，可按出错阶段归类
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct FlashError {
+    pub category: FlashErrorCategory,
+    pub message: String,
+}
+
+impl FlashErrorCategory {
+    /// 与序列化名称一致，用于日志和导出
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FlashErrorCategory::PortOpen => "port_open",
+            FlashErrorCategory::Sync => "sync",
+            FlashErrorCategory::StubDownload => "stub_download",
+            FlashErrorCategory::Erase => "erase",
+            FlashErrorCategory::Write => "write",
+            FlashErrorCategory::VerifyMismatch => "verify_mismatch",
+            FlashErrorCategory::Cancelled => "cancelled",
+            FlashErrorCategory::Panic => "panic",
+            FlashErrorCategory::Disconnect => "disconnect",
+            FlashErrorCategory::Config => "config",
+            FlashErrorCategory::Other => "other",
+        }
+    }
+}
+
+impl FlashError {
+    pub fn new(category: FlashErrorCategory, message: impl Into<String>) -> Self {
+        Self {
+            category,
+            message: message.into(),
+        }
+    }
+
+    /// 未归类的错误按出错时进度回调报告的阶段归类
+    pub fn or_stage(self, stage: Option<FlashErrorCategory>) -> Self {
+        match (self.category, stage) {
+            (FlashErrorCategory::Other, Some(stage)) => Self {
+                category: stage,
+                ..self
+            },
+            _ => self,
+        }
+    }
+}
+
+impl fmt::Display for FlashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for FlashError {}
+
+impl From<String> for FlashError {
+    fn from(message: String) -> Self {
+        Self::new(FlashErrorCategory::Other, message)
+    }
+}
+
+impl From<&str> for FlashError {
+    fn from(message: &str) -> Self {
+        Self::new(FlashErrorCategory::Other, message)
+    }
+}
+
+impl From<FlashError> for String {
+    fn from(error: FlashError) -> Self {
+        error.message
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationErrorKind {
+    Cancelled,
+    Busy,
+    Failed,
+}
+
+/// 单设备烧录/读取/擦除操作的错误；用户取消时 kind 为 cancelled，
+/// 已有操作进行中时 kind 为 busy，operation 为正在执行的操作
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct OperationError {
+    pub kind: OperationErrorKind,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<FlashErrorCategory>,
+}
+
+impl OperationError {
+    pub fn cancelled() -> Self {
+        Self {
+            kind: OperationErrorKind::Cancelled,
+            message: "操作已取消，请重新连接设备".to_string(),
+            operation: None,
+            category: None,
+        }
+    }
+
+    pub fn busy(operation: &str) -> Self {
+        Self {
+            kind: OperationErrorKind::Busy,
+            message: format!("设备正在{}，请等待当前操作完成", operation),
+            operation: Some(operation.to_string()),
+            category: None,
+        }
+    }
+
+    pub fn failed(message: impl Into<String>) -> Self {
+        Self {
+            kind: OperationErrorKind::Failed,
+            message: message.into(),
+            operation: None,
+            category: None,
+        }
+    }
+}
+
+impl fmt::Display for OperationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for OperationError {}
+
+impl From<String> for OperationError {
+    fn from(message: String) -> Self {
+        Self::failed(message)
+    }
+}
+
+impl From<&str> for OperationError {
+    fn from(message: &str) -> Self {
+        Self::failed(message)
+    }
+}
+
+impl From<FlashError> for OperationError {
+    fn from(error: FlashError) -> Self {
+        Self {
+            category: Some(error.category),
+            ..Self::failed(error.message)
+        }
+    }
+}
+
+impl From<OperationError> for String {
+    fn from(error: OperationError) -> Self {
+        error.message
+    }
+}
+
+/// 使用 Utils::parse_file_info 解析单个待写入文件
+///
+/// 地址为 0 时可能是 ELF/HEX 文件，交给 parse_file_info 自动检测；
+/// 否则使用 file@address 格式
+pub fn parse_write_file(file_path: &str, address: u32) -> Result<Vec<WriteFlashFile>, String> {
+    let file_string = if address == 0 {
+        file_path.to_string()
+    } else {
+        format!("{}@0x{:08X}", file_path, address)
+    };
+
+    Utils::parse_file_info(&file_string).map_err(|e| format!("解析文件 {} 失败: {}", file_path, e))
+}
+
+impl WriteFlashRequest {
+    pub fn to_write_flash_params(&self) -> Result<WriteFlashParams, String> {
+        let mut files = Vec::new();
+        for file_info in &self.files {
+            // 检查文件是否存在
+            if !std::path::Path::new(&file_info.file_path).exists() {
+                return Err(format!("文件不存在: {}", file_info.file_path));
+            }
+
+            files.extend(parse_write_file(&file_info.file_path, file_info.address)?);
+        }
+
+        Ok(WriteFlashParams {
+            files,
+            verify: self.verify,
+            no_compress: self.no_compress,
+            erase_all: self.erase_all,
+        })
+    }
+}
+
+impl ReadFlashRequest {
+    pub fn to_read_flash_params(&self) -> ReadFlashParams {
+        let files = self
+            .files
+            .iter()
+            .map(|file_info| ReadFlashFile {
+                file_path: file_info.file_path.clone(),
+                address: file_info.address,
+                size: file_info.size,
+            })
+            .collect();
+
+        ReadFlashParams { files }
+    }
+}
@@ -1,4 +1,7 @@
-use crate::types::TauriProgressEvent;
+use crate::types::{
+    FlashErrorCategory, MassProductionSerialConfig, SerialPoolStatus, TauriProgressEvent,
+    UnitDataTemplate,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
@@ -35,6 +38,80 @@ pub enum MassProductionPortStatus {
 pub struct MassProductionWriteFileInfo {
     pub address: u32,
     pub file_path: String,
+    /// 期望的 SHA-256 或 CRC32，不一致时拒绝启动量产
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_hash: Option<String>,
+}
+
+/// 量产模式：写入固件，或只回读校验设备上已有的固件
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MassProductionMode {
+    #[default]
+    Write,
+    Verify,
+}
+
+/// 量产启动时锁定的固件文件；所有设备都从 frozen_path 的副本写入
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct MassProductionFrozenFile {
+    pub file_path: String,
+    pub frozen_path: String,
+    pub address: u32,
+    pub size: u64,
+    pub crc32: String,
+    pub sha256: String,
+}
+
+fn default_max_attempts() -> u32 {
+    3
+}
+
+fn default_backoff_ms() -> u64 {
+    1000
+}
+
+fn default_max_backoff_ms() -> u64 {
+    10_000
+}
+
+fn default_retry_on() -> Vec<FlashErrorCategory> {
+    vec![
+        FlashErrorCategory::PortOpen,
+        FlashErrorCategory::Sync,
+        FlashErrorCategory::Disconnect,
+    ]
+}
+
+/// 失败设备的自动重试策略；重试在原端口上进行，无需重新插拔
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct MassProductionRetryPolicy {
+    /// 每台设备最多尝试的次数，包括第一次
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// 第 N 次重试前等待 backoff_ms × 2^(N-1)，不超过 max_backoff_ms
+    #[serde(default = "default_backoff_ms")]
+    pub backoff_ms: u64,
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// 可以重试的失败类别
+    #[serde(default = "default_retry_on")]
+    pub retry_on: Vec<FlashErrorCategory>,
+    /// 重试时改用的波特率，未设置时沿用原波特率
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_baud_rate: Option<u32>,
+}
+
+impl Default for MassProductionRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            backoff_ms: default_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+            retry_on: default_retry_on(),
+            retry_baud_rate: None,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -47,6 +124,19 @@ pub struct MassProductionStartRequest {
     pub before_operation: String,
     pub after_operation: String,
     pub files: Vec<MassProductionWriteFileInfo>,
+    #[serde(default)]
+    pub mode: MassProductionMode,
+    /// 每台设备单独渲染的出厂数据，仅在写入模式下生效
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit_data: Option<UnitDataTemplate>,
+    /// 每台设备从序列号池预留一个序列号，仅在写入模式下生效
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub serial: Option<MassProductionSerialConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<MassProductionRetryPolicy>,
+    /// 关闭烧录地址范围检查，用于未收录 Flash 地址映射的芯片/存储器组合
+    #[serde(default)]
+    pub skip_address_check: bool,
     pub verify: bool,
     pub no_compress: bool,
     pub erase_all: bool,
@@ -74,6 +164,12 @@ pub struct MassProductionPortInfo {
     pub last_seen_at: u64,
     pub task_started_at: Option<u64>,
     pub task_finished_at: Option<u64>,
+    /// 最近一次失败的类别，成功或取消时为 None
+    #[serde(default)]
+    pub error_category: Option<FlashErrorCategory>,
+    /// 当前设备已开始的尝试次数，重新插拔或手动刷新后归零
+    #[serde(default)]
+    pub attempt: u32,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -81,11 +177,16 @@ pub struct MassProductionSnapshot {
     pub is_running: bool,
     pub is_enabled: bool,
     pub session_id: u64,
+    /// 会话的持久标识，可用于查询追溯记录
+    #[serde(default)]
+    pub session_key: String,
     pub started_at: Option<u64>,
     pub ended_at: Option<u64>,
     pub manual_stopped: bool,
     pub chip_model: Option<String>,
     pub memory_type: Option<String>,
+    #[serde(default)]
+    pub mode: MassProductionMode,
     pub auto_download: bool,
     pub max_concurrency: u8,
     pub queued_count: u32,
@@ -95,6 +196,26 @@ pub struct MassProductionSnapshot {
     pub failed_count: u32,
     pub total_count: u32,
     pub ports: Vec<MassProductionPortInfo>,
+    #[serde(default)]
+    pub frozen_files: Vec<MassProductionFrozenFile>,
+    /// 会话被中止的原因，例如固件原文件在量产中被修改
+    #[serde(default)]
+    pub abort_reason: Option<String>,
+    #[serde(default)]
+    pub serial_pool: Option<SerialPoolStatus>,
+    /// 各失败类别的次数，按次数从多到少排列；每次失败的尝试都计入，
+    /// 包括随后重试成功的，因此合计可能大于 failed_count
+    #[serde(default)]
+    pub failure_counts: Vec<FlashErrorCount>,
+    /// 已安排的自动重试次数；等待重试的设备计入 queued_count
+    #[serde(default)]
+    pub retry_count: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct FlashErrorCount {
+    pub category: FlashErrorCategory,
+    pub count: u32,
 }
 
 impl Default for MassProductionSnapshot {
@@ -103,11 +224,13 @@ impl Default for MassProductionSnapshot {
             is_running: false,
             is_enabled: false,
             session_id: 0,
+            session_key: String::new(),
             started_at: None,
             ended_at: None,
             manual_stopped: false,
             chip_model: None,
             memory_type: None,
+            mode: MassProductionMode::Write,
             auto_download: false,
             max_concurrency: 8,
             queued_count: 0,
@@ -117,12 +240,18 @@ impl Default for MassProductionSnapshot {
             failed_count: 0,
             total_count: 0,
             ports: Vec::new(),
+            frozen_files: Vec::new(),
+            abort_reason: None,
+            serial_pool: None,
+            failure_counts: Vec::new(),
+            retry_count: 0,
         }
     }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MassProductionProgressEvent {
+    pub session_id: u64,
     pub port_name: String,
     pub event: TauriProgressEvent,
 }
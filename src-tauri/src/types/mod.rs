@@ -1,13 +1,29 @@
+pub mod archive;
 pub mod config;
 pub mod device;
+pub mod firmware;
 pub mod flash;
 pub mod mass_production;
+pub mod package;
+pub mod partition;
 pub mod progress;
+pub mod remote_api;
+pub mod serial_pool;
 pub mod stub_config_spec;
+pub mod traceability;
+pub mod unit_data;
 
+pub use archive::*;
 pub use config::*;
 pub use device::*;
+pub use firmware::*;
 pub use flash::*;
 pub use mass_production::*;
+pub use package::*;
+pub use partition::*;
 pub use progress::*;
+pub use remote_api::*;
+pub use serial_pool::*;
 pub use stub_config_spec::*;
+pub use traceability::*;
+pub use unit_data::*;
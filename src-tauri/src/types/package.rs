@@ -0,0 +1,81 @@
+use crate::types::SftoolParamConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const SFPKG_FORMAT_VERSION: u32 = 1;
+pub const SFPKG_MANIFEST_NAME: &str = "manifest.json";
+pub const SFPKG_SIGNATURE_NAME: &str = "manifest.sig";
+pub const SFPKG_EXTENSION: &str = "sfpkg";
+
+/// 固件包清单：在 sftool_param.json 的基础上增加格式版本、Stub 配置与文件摘要。
+///
+/// 清单中的路径均相对于包根目录。
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SfpkgManifest {
+    pub format_version: u32,
+    #[serde(flatten)]
+    pub config: SftoolParamConfig,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stub_config: Option<String>,
+    /// 包内相对路径 -> SHA-256（小写十六进制）
+    pub sha256: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SfpkgBuildFile {
+    pub file_path: String,
+    /// 写入地址，ELF/HEX 文件可留空由文件自身决定
+    pub address: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SfpkgBuildRequest {
+    pub output_path: String,
+    pub chip: String,
+    pub memory: Option<String>,
+    pub files: Vec<SfpkgBuildFile>,
+    #[serde(default)]
+    pub verify: bool,
+    #[serde(default)]
+    pub erase_all: bool,
+    #[serde(default)]
+    pub no_compress: bool,
+    pub stub_config_path: Option<String>,
+    /// Ed25519 私钥文件，内容为 32 字节种子的 base64
+    pub signing_key_path: String,
+}
+
+/// 清单的分离签名，覆盖 manifest.json 的原始字节
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SfpkgSignature {
+    /// 签名者的 Ed25519 公钥（base64）
+    pub public_key: String,
+    /// 64 字节签名（base64）
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SfpkgSignatureStatus {
+    Unsigned,
+    /// 签名与清单不符
+    Invalid,
+    /// 签名有效，但公钥不在受信任列表中
+    Untrusted,
+    Trusted,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SfpkgEntryInfo {
+    pub path: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SfpkgInfo {
+    pub package_path: String,
+    pub manifest: SfpkgManifest,
+    pub signature: Option<SfpkgSignature>,
+    pub signature_status: SfpkgSignatureStatus,
+    pub entries: Vec<SfpkgEntryInfo>,
+}
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+/// SDK 分区表（ptab.json）中的一个区域；address 为 mem 基地址加偏移
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PartitionInfo {
+    /// 第一个 tag，没有 tag 时为 "<mem>@<address>"
+    pub name: String,
+    pub tags: Vec<String>,
+    pub memory: String,
+    pub address: u32,
+    pub size: u32,
+}
+
+impl PartitionInfo {
+    pub fn end(&self) -> u64 {
+        self.address as u64 + self.size as u64
+    }
+}
@@ -1,3 +1,4 @@
+use crate::types::FlashErrorCategory;
 use serde::{Deserialize, Serialize};
 use sftool_lib::progress;
 
@@ -102,6 +103,23 @@ pub struct TauriProgressEvent {
     pub status: Option<TauriProgressStatus>,
 }
 
+impl TauriProgressOperation {
+    /// 在该操作进行中失败时对应的错误类别；读取等操作没有对应的类别
+    pub fn failure_stage(&self) -> Option<FlashErrorCategory> {
+        match self {
+            TauriProgressOperation::Connect => Some(FlashErrorCategory::Sync),
+            TauriProgressOperation::DownloadStub { .. } => Some(FlashErrorCategory::StubDownload),
+            TauriProgressOperation::EraseFlash { .. }
+            | TauriProgressOperation::EraseRegion { .. }
+            | TauriProgressOperation::EraseAllRegions => Some(FlashErrorCategory::Erase),
+            TauriProgressOperation::Verify { .. } => Some(FlashErrorCategory::VerifyMismatch),
+            TauriProgressOperation::CheckRedownload { .. }
+            | TauriProgressOperation::WriteFlash { .. } => Some(FlashErrorCategory::Write),
+            TauriProgressOperation::ReadFlash { .. } | TauriProgressOperation::Unknown => None,
+        }
+    }
+}
+
 impl From<progress::ProgressType> for TauriProgressType {
     fn from(value: progress::ProgressType) -> Self {
         match value {
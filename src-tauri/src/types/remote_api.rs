@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RemoteApiStatus {
+    pub is_running: bool,
+    pub bind_address: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RemoteRefreshRequest {
+    #[serde(default)]
+    pub trigger_flash: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RemoteAutoDownloadRequest {
+    pub auto_download: bool,
+}
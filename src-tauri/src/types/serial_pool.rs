@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+/// 号段 [start, end]，生成的序列号为 prefix + 补零到 width 位的十进制数
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SerialRange {
+    #[serde(default)]
+    pub prefix: String,
+    #[serde(default)]
+    pub width: u32,
+    pub start: u64,
+    pub end: u64,
+}
+
+/// 设备失败或取消时如何处理已预留的序列号
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SerialFailurePolicy {
+    /// 归还到池中，下次优先分配；已开始写入的设备仍会作废
+    Release,
+    /// 作废，永不再分配
+    #[default]
+    Burn,
+}
+
+/// 新建序列号池或向已有的池追加号段/列表
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SerialPoolImportRequest {
+    pub name: String,
+    #[serde(default)]
+    pub ranges: Vec<SerialRange>,
+    #[serde(default)]
+    pub values: Vec<String>,
+    /// 每行一个序列号的文本文件，空行和 # 开头的行被忽略
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub list_path: Option<String>,
+}
+
+/// 量产会话使用的序列号池
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MassProductionSerialConfig {
+    pub pool: String,
+    #[serde(default)]
+    pub on_failure: SerialFailurePolicy,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SerialPoolStatus {
+    pub name: String,
+    /// 尚可分配的数量，包括已归还的序列号
+    pub remaining: u64,
+    pub reserved: u64,
+    pub committed: u64,
+    pub burned: u64,
+    pub released: u64,
+}
@@ -146,6 +146,12 @@ impl AddressValue {
     }
 }
 
+impl FlashSpec {
+    pub fn capacity(&self) -> Result<u32, String> {
+        self.capacity_bytes.to_u32("capacity_bytes")
+    }
+}
+
 impl SizeValue {
     fn to_u32(&self, label: &str) -> Result<u32, String> {
         match self {
@@ -0,0 +1,86 @@
+use crate::types::{FlashErrorCategory, MassProductionMode};
+use serde::{Deserialize, Serialize};
+
+fn default_attempt() -> u32 {
+    1
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceResult {
+    Success,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TraceFirmwareFile {
+    pub file_path: String,
+    pub address: u32,
+    pub sha256: String,
+}
+
+/// 一次烧录尝试的追溯记录，按完成顺序追加到记录文件，写入后不再修改
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TraceRecord {
+    pub session_id: u64,
+    /// 会话的持久标识；session_id 每次启动程序都会重新计数，不能跨启动区分会话
+    #[serde(default)]
+    pub session_key: String,
+    pub port_name: String,
+    /// 同一台设备的第几次尝试，从 1 开始
+    #[serde(default = "default_attempt")]
+    pub attempt: u32,
+    pub vid: Option<String>,
+    pub pid: Option<String>,
+    pub usb_serial: Option<String>,
+    pub location_path: Option<String>,
+    pub chip_model: String,
+    pub memory_type: String,
+    pub mode: MassProductionMode,
+    pub firmware: Vec<TraceFirmwareFile>,
+    /// 序列号池为该设备预留的序列号
+    #[serde(default)]
+    pub serial: Option<String>,
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub result: TraceResult,
+    #[serde(default)]
+    pub error_category: Option<FlashErrorCategory>,
+    pub error: Option<String>,
+}
+
+/// 查询结果；格式错误的行会被跳过，不影响其它记录
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TraceQueryResult {
+    pub records: Vec<TraceRecord>,
+    /// 被跳过的行号，从 1 开始
+    pub skipped_lines: Vec<usize>,
+}
+
+/// 所有条件均为可选，未设置的条件不参与过滤
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TraceQuery {
+    #[serde(default)]
+    pub session_key: Option<String>,
+    /// 匹配端口名、USB 序列号或设备序列号
+    #[serde(default)]
+    pub device: Option<String>,
+    #[serde(default)]
+    pub result: Option<TraceResult>,
+    /// 按完成时间过滤的毫秒时间戳，[since, until)
+    #[serde(default)]
+    pub since: Option<u64>,
+    #[serde(default)]
+    pub until: Option<u64>,
+    /// 只返回最新的 limit 条
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceExportFormat {
+    Csv,
+    Json,
+}
@@ -0,0 +1,83 @@
+use crate::types::SerialFailurePolicy;
+use serde::{Deserialize, Serialize};
+
+fn default_fill_byte() -> u8 {
+    0xFF
+}
+
+fn default_step() -> u64 {
+    1
+}
+
+/// 量产时为每台设备生成的出厂数据镜像，渲染后追加到写入列表
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct UnitDataTemplate {
+    pub address: u32,
+    pub size: u32,
+    /// 字段之间未覆盖的字节
+    #[serde(default = "default_fill_byte")]
+    pub fill_byte: u8,
+    /// 第一行为列名，第 N 台设备使用第 N 条数据行
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub csv_path: Option<String>,
+    pub fields: Vec<UnitDataField>,
+    /// 设备失败时已分配的设备序号（CSV 数据行）如何处理，含义与序列号池相同；
+    /// 安排了重试的端口始终沿用原序号
+    #[serde(default)]
+    pub on_failure: SerialFailurePolicy,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct UnitDataField {
+    pub name: String,
+    /// 相对模板起始地址的偏移
+    pub offset: u32,
+    pub size: u32,
+    pub source: UnitDataSource,
+    pub encoding: UnitDataEncoding,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UnitDataSource {
+    /// start + step × 设备序号，序号从 0 开始按分配顺序递增
+    Counter {
+        #[serde(default)]
+        start: u64,
+        #[serde(default = "default_step")]
+        step: u64,
+    },
+    CsvColumn {
+        column: String,
+    },
+    Port {
+        field: UnitDataPortField,
+    },
+    Constant {
+        value: String,
+    },
+    /// 会话序列号池为该设备预留的序列号
+    Serial,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UnitDataPortField {
+    PortName,
+    SerialNumber,
+    LocationPath,
+    VidPid,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UnitDataEncoding {
+    /// 文本原样写入，不足部分补 0
+    Ascii,
+    /// 十六进制文本转为字节，忽略 ':' '-' 和空格，长度必须与字段一致
+    Hex,
+    /// 无符号整数，小端
+    UintLe,
+    /// 无符号整数，大端
+    UintBe,
+}
@@ -0,0 +1,445 @@
+use crate::types::{ArchiveErrorKind, ArchiveExtractError, ArchiveLimits, ExtractedFile};
+use bzip2::read::BzDecoder;
+use flate2::read::GzDecoder;
+use std::fs::{self, File};
+use std::io::{BufReader, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use tempfile::tempdir;
+use unarc_rs::unified::ArchiveFormat;
+use walkdir::WalkDir;
+
+/// 解压到新建的临时目录并返回目录路径；目录不会自动删除，调用方需注册到 AppState 统一清理
+pub fn extract_archive_to_temp_dir(
+    archive_path: &str,
+    limits: ArchiveLimits,
+) -> Result<PathBuf, ArchiveExtractError> {
+    let path = Path::new(archive_path);
+    if !path.exists() {
+        return Err(ArchiveExtractError::new(
+            ArchiveErrorKind::NotFound,
+            None,
+            format!("文件不存在: {}", archive_path),
+        ));
+    }
+
+    // 创建临时目录，并转出 TempDir 的自动删除控制权，让目录在应用关闭前保留
+    let dir =
+        tempdir().map_err(|e| ArchiveExtractError::io(None, format!("无法创建临时目录: {}", e)))?;
+    let dest_path: PathBuf = dir.keep();
+
+    if let Err(e) = extract_archive_into(archive_path, &dest_path, limits) {
+        let _ = fs::remove_dir_all(&dest_path);
+        return Err(e);
+    }
+
+    Ok(dest_path)
+}
+
+/// 将条目名转换为安全的相对路径：统一分隔符，拒绝绝对路径、盘符与 `..`
+pub fn sanitize_entry_name(entry_name: &str) -> Result<PathBuf, ArchiveExtractError> {
+    let normalized = entry_name.replace('\\', "/");
+    let mut relative = PathBuf::new();
+
+    for component in Path::new(&normalized).components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(ArchiveExtractError::new(
+                    ArchiveErrorKind::PathTraversal,
+                    Some(entry_name),
+                    format!("压缩文件条目路径越界: {}", entry_name),
+                ));
+            }
+        }
+    }
+
+    // Windows 盘符（如 C:foo）在非 Windows 平台上不会被识别为 Prefix
+    if normalized.contains(':') {
+        return Err(ArchiveExtractError::new(
+            ArchiveErrorKind::PathTraversal,
+            Some(entry_name),
+            format!("压缩文件条目路径越界: {}", entry_name),
+        ));
+    }
+
+    Ok(relative)
+}
+
+/// 从解压根目录到目标路径逐级检查，已存在的路径不能是符号链接
+fn ensure_no_symlink(
+    dest_root: &Path,
+    relative: &Path,
+    entry_name: &str,
+) -> Result<(), ArchiveExtractError> {
+    let mut current = dest_root.to_path_buf();
+    for part in relative.components() {
+        current.push(part);
+        if let Ok(metadata) = fs::symlink_metadata(&current) {
+            if metadata.file_type().is_symlink() {
+                return Err(ArchiveExtractError::new(
+                    ArchiveErrorKind::Symlink,
+                    Some(entry_name),
+                    format!("压缩文件条目指向符号链接: {}", entry_name),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn link_entry_error(entry_name: &str) -> ArchiveExtractError {
+    ArchiveExtractError::new(
+        ArchiveErrorKind::Symlink,
+        Some(entry_name),
+        format!("压缩文件包含链接条目: {}", entry_name),
+    )
+}
+
+fn ensure_no_zip_links(file: File) -> Result<(), ArchiveExtractError> {
+    let mut archive = zip::ZipArchive::new(BufReader::new(file))
+        .map_err(|e| ArchiveExtractError::io(None, format!("打开压缩文件失败: {}", e)))?;
+    for index in 0..archive.len() {
+        let entry = archive
+            .by_index_raw(index)
+            .map_err(|e| ArchiveExtractError::io(None, format!("读取压缩文件条目失败: {}", e)))?;
+        if entry.is_symlink() {
+            return Err(link_entry_error(entry.name()));
+        }
+    }
+    Ok(())
+}
+
+/// 符号链接、硬链接以及设备文件等特殊条目一律拒绝
+fn ensure_no_tar_links(reader: impl Read) -> Result<(), ArchiveExtractError> {
+    let mut archive = tar::Archive::new(reader);
+    let entries = archive
+        .entries()
+        .map_err(|e| ArchiveExtractError::io(None, format!("打开压缩文件失败: {}", e)))?;
+    for entry in entries {
+        let entry = entry
+            .map_err(|e| ArchiveExtractError::io(None, format!("读取压缩文件条目失败: {}", e)))?;
+        let entry_type = entry.header().entry_type();
+        let is_regular = entry_type.is_file()
+            || entry_type.is_dir()
+            || entry_type.is_contiguous()
+            || entry_type.is_pax_global_extensions()
+            || entry_type.is_pax_local_extensions()
+            || entry_type.is_gnu_longname()
+            || entry_type.is_gnu_longlink();
+        if !is_regular {
+            return Err(link_entry_error(&String::from_utf8_lossy(
+                &entry.path_bytes(),
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// unarc-rs 不还原链接，而是把链接条目当作普通文件写出；解压前按格式读取条目类型，
+/// 发现链接即拒绝整个压缩文件。rar、7z 等无法读取条目类型的格式直接拒绝
+fn ensure_no_link_entries(archive_path: &str) -> Result<(), ArchiveExtractError> {
+    let lower = archive_path.to_lowercase();
+    let open = || {
+        File::open(archive_path)
+            .map_err(|e| ArchiveExtractError::io(None, format!("打开压缩文件失败: {}", e)))
+    };
+
+    if lower.ends_with(".zip") {
+        ensure_no_zip_links(open()?)
+    } else if lower.ends_with(".tar") {
+        ensure_no_tar_links(BufReader::new(open()?))
+    } else if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+        ensure_no_tar_links(GzDecoder::new(BufReader::new(open()?)))
+    } else if lower.ends_with(".tar.bz2") || lower.ends_with(".tbz2") || lower.ends_with(".tbz") {
+        ensure_no_tar_links(BzDecoder::new(BufReader::new(open()?)))
+    } else {
+        Err(ArchiveExtractError::new(
+            ArchiveErrorKind::UnsupportedFormat,
+            None,
+            format!(
+                "不支持的压缩格式，请使用 zip、tar、tar.gz 或 tar.bz2: {}",
+                archive_path
+            ),
+        ))
+    }
+}
+
+/// 创建目录后再用规范化路径确认仍位于解压根目录内
+fn ensure_contained(
+    canonical_root: &Path,
+    path: &Path,
+    entry_name: &str,
+) -> Result<(), ArchiveExtractError> {
+    let canonical = path.canonicalize().map_err(|e| {
+        ArchiveExtractError::io(
+            Some(entry_name),
+            format!("{}: 解析路径失败: {}", entry_name, e),
+        )
+    })?;
+    if !canonical.starts_with(canonical_root) {
+        return Err(ArchiveExtractError::new(
+            ArchiveErrorKind::PathTraversal,
+            Some(entry_name),
+            format!("压缩文件条目路径越界: {}", entry_name),
+        ));
+    }
+    Ok(())
+}
+
+/// 写入时累计字节数，超过剩余额度立即失败
+struct LimitedWriter<W: Write> {
+    inner: W,
+    remaining: u64,
+    exceeded: bool,
+}
+
+impl<W: Write> LimitedWriter<W> {
+    fn new(inner: W, remaining: u64) -> Self {
+        Self {
+            inner,
+            remaining,
+            exceeded: false,
+        }
+    }
+}
+
+impl<W: Write> Write for LimitedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if buf.len() as u64 > self.remaining {
+            self.exceeded = true;
+            return Err(std::io::Error::other("extracted size limit exceeded"));
+        }
+        let written = self.inner.write(buf)?;
+        self.remaining -= written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn extract_archive_into(
+    archive_path: &str,
+    dest_path: &Path,
+    limits: ArchiveLimits,
+) -> Result<(), ArchiveExtractError> {
+    let canonical_root = dest_path
+        .canonicalize()
+        .map_err(|e| ArchiveExtractError::io(None, format!("无法解析临时目录: {}", e)))?;
+    ensure_no_link_entries(archive_path)?;
+
+    // 使用 unarc-rs 解压，格式已在链接检查中限定为 zip、tar、tar.gz、tar.bz2
+    let mut archive = ArchiveFormat::open_path(archive_path)
+        .map_err(|e| ArchiveExtractError::io(None, format!("打开压缩文件失败: {}", e)))?;
+
+    let mut entry_count = 0usize;
+    let mut remaining_size = limits.max_total_size;
+
+    // 遍历压缩文件中的所有条目
+    while let Some(entry) = archive
+        .next_entry()
+        .map_err(|e| ArchiveExtractError::io(None, format!("读取压缩文件条目失败: {}", e)))?
+    {
+        let entry_name = entry.name();
+
+        entry_count += 1;
+        if entry_count > limits.max_entries {
+            return Err(ArchiveExtractError::new(
+                ArchiveErrorKind::TooManyEntries,
+                Some(entry_name),
+                format!("压缩文件条目数量超过上限 {}", limits.max_entries),
+            ));
+        }
+
+        let relative = sanitize_entry_name(entry_name)?;
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+        ensure_no_symlink(&canonical_root, &relative, entry_name)?;
+        let outpath = canonical_root.join(&relative);
+
+        // 判断是否是目录（目录名以 / 结尾）
+        if entry_name.ends_with('/') || entry_name.ends_with('\\') {
+            // 创建目录
+            fs::create_dir_all(&outpath).map_err(|e| {
+                ArchiveExtractError::io(Some(entry_name), format!("创建目录失败: {}", e))
+            })?;
+            ensure_contained(&canonical_root, &outpath, entry_name)?;
+        } else {
+            // 创建父目录
+            if let Some(parent) = outpath.parent() {
+                if !parent.exists() {
+                    fs::create_dir_all(parent).map_err(|e| {
+                        ArchiveExtractError::io(Some(entry_name), format!("创建目录失败: {}", e))
+                    })?;
+                }
+                ensure_contained(&canonical_root, parent, entry_name)?;
+            }
+
+            // 如果输出路径已经存在且是目录，跳过
+            if outpath.exists() && outpath.is_dir() {
+                println!("跳过目录路径: {:?}", outpath);
+                continue;
+            }
+
+            // 提取文件
+            println!("Extracting file to: {:?} ... ", outpath);
+            let outfile = fs::File::create(&outpath).map_err(|e| {
+                ArchiveExtractError::io(
+                    Some(entry_name),
+                    format!("{}: 创建文件失败: {}", entry_name, e),
+                )
+            })?;
+            let mut writer = LimitedWriter::new(outfile, remaining_size);
+            let result = archive.read_to(&entry, &mut writer);
+            if writer.exceeded {
+                return Err(ArchiveExtractError::new(
+                    ArchiveErrorKind::TooLarge,
+                    Some(entry_name),
+                    format!(
+                        "解压 {} 时超过总大小上限 {} 字节",
+                        entry_name, limits.max_total_size
+                    ),
+                ));
+            }
+            result.map_err(|e| {
+                ArchiveExtractError::io(Some(entry_name), format!("解压文件失败: {}", e))
+            })?;
+            remaining_size = writer.remaining;
+        }
+    }
+
+    Ok(())
+}
+
+/// 遍历解压目录，收集文件信息
+pub fn collect_extracted_files(dest_path: &Path) -> Vec<ExtractedFile> {
+    let mut extracted = Vec::new();
+    for entry in WalkDir::new(dest_path).into_iter() {
+        match entry {
+            Ok(entry) => {
+                let p = entry.path();
+                if p.is_file() {
+                    if let Ok(meta) = fs::metadata(p) {
+                        let name = p
+                            .file_name()
+                            .and_then(|n| n.to_str())
+                            .unwrap_or("")
+                            .to_string();
+                        extracted.push(ExtractedFile {
+                            path: p.to_string_lossy().to_string(),
+                            address: "".to_string(),
+                            name,
+                            size: meta.len(),
+                        });
+                    }
+                }
+            }
+            Err(err) => {
+                eprintln!(
+                    "遍历临时目录 '{}' 时出错: {}",
+                    dest_path.to_string_lossy(),
+                    err
+                );
+            }
+        }
+    }
+
+    extracted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extract_archive_to_temp_dir, sanitize_entry_name, LimitedWriter};
+    use crate::types::{ArchiveErrorKind, ArchiveLimits};
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    #[test]
+    fn rejects_traversal_entries() {
+        assert_eq!(
+            sanitize_entry_name("fw/./app.bin").unwrap(),
+            PathBuf::from("fw/app.bin")
+        );
+
+        for name in [
+            "../../x",
+            "fw/../../x",
+            "/etc/passwd",
+            "..\\..\\x",
+            "C:/x",
+            "C:x",
+        ] {
+            let error = sanitize_entry_name(name).unwrap_err();
+            assert_eq!(error.kind, ArchiveErrorKind::PathTraversal, "{name}");
+            assert_eq!(error.entry.as_deref(), Some(name));
+        }
+    }
+
+    #[test]
+    fn limited_writer_stops_at_quota() {
+        let mut writer = LimitedWriter::new(Vec::new(), 4);
+        assert!(writer.write_all(b"abc").is_ok());
+        assert!(writer.write_all(b"de").is_err());
+        assert!(writer.exceeded);
+        assert_eq!(writer.inner, b"abc");
+    }
+
+    #[test]
+    fn rejects_link_entries() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let tar_path = dir.path().join("firmware.tar");
+        let mut builder = tar::Builder::new(File::create(&tar_path).unwrap());
+        let data = [0xAAu8; 16];
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "fw/app.bin", &data[..])
+            .unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder
+            .append_link(&mut header, "fw/passwd", "/etc/passwd")
+            .unwrap();
+        builder.finish().unwrap();
+        drop(builder);
+
+        let zip_path = dir.path().join("firmware.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&zip_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        writer.start_file("fw/app.bin", options).unwrap();
+        writer.write_all(&data).unwrap();
+        writer
+            .add_symlink("fw/passwd", "/etc/passwd", options)
+            .unwrap();
+        writer.finish().unwrap();
+
+        for path in [tar_path, zip_path] {
+            let error =
+                extract_archive_to_temp_dir(&path.to_string_lossy(), ArchiveLimits::default())
+                    .unwrap_err();
+            assert_eq!(error.kind, ArchiveErrorKind::Symlink, "{}", path.display());
+            assert_eq!(error.entry.as_deref(), Some("fw/passwd"));
+        }
+    }
+
+    #[test]
+    fn rejects_formats_without_link_check() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["firmware.rar", "firmware.7z", "firmware.tar.xz"] {
+            let path = dir.path().join(name);
+            File::create(&path).unwrap();
+            let error =
+                extract_archive_to_temp_dir(&path.to_string_lossy(), ArchiveLimits::default())
+                    .unwrap_err();
+            assert_eq!(error.kind, ArchiveErrorKind::UnsupportedFormat, "{name}");
+        }
+    }
+}
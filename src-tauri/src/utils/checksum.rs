@@ -0,0 +1,134 @@
+use crate::types::FileHash;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::Read;
+
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+const SHA256_HEX_LEN: usize = 64;
+const CRC32_HEX_LEN: usize = 8;
+
+/// 计算文件的 SHA-256，返回小写十六进制字符串
+pub fn sha256_file(file_path: &str) -> Result<String, String> {
+    Ok(hash_file(file_path)?.sha256)
+}
+
+/// 一次读取同时计算 CRC32、SHA-256 与文件大小
+pub fn hash_file(file_path: &str) -> Result<FileHash, String> {
+    let mut file =
+        File::open(file_path).map_err(|e| format!("无法打开文件 {}: {}", file_path, e))?;
+    let mut sha256 = Sha256::new();
+    let mut crc32 = crc32fast::Hasher::new();
+    let mut size = 0u64;
+    let mut buffer = vec![0u8; READ_BUFFER_SIZE];
+
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| format!("读取文件 {} 失败: {}", file_path, e))?;
+        if read == 0 {
+            break;
+        }
+        sha256.update(&buffer[..read]);
+        crc32.update(&buffer[..read]);
+        size += read as u64;
+    }
+
+    Ok(FileHash {
+        file_path: file_path.to_string(),
+        size,
+        crc32: format!("{:08x}", crc32.finalize()),
+        sha256: hex::encode(sha256.finalize()),
+        expected_hash: None,
+        matches: None,
+    })
+}
+
+/// 按长度区分期望值：64 位十六进制为 SHA-256，8 位为 CRC32（可带 0x 前缀）；
+/// 也接受 `sha256:` / `crc32:` 前缀
+fn hash_matches(hash: &FileHash, expected: &str) -> Result<bool, String> {
+    let trimmed = expected.trim().to_lowercase();
+    let (algorithm, value) = match trimmed.split_once(':') {
+        Some((algorithm, value)) => (Some(algorithm.trim().to_string()), value.trim()),
+        None => (None, trimmed.as_str()),
+    };
+    let value = value.strip_prefix("0x").unwrap_or(value);
+
+    if !value.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("无效的期望摘要: {}", expected));
+    }
+
+    match (algorithm.as_deref(), value.len()) {
+        (None | Some("sha256"), SHA256_HEX_LEN) => Ok(hash.sha256 == value),
+        (None | Some("crc32"), CRC32_HEX_LEN) => Ok(hash.crc32 == value),
+        _ => Err(format!("无效的期望摘要: {}", expected)),
+    }
+}
+
+/// 计算摘要并与期望值比较；expected 为空时只计算
+pub fn hash_and_check(file_path: &str, expected: Option<&str>) -> Result<FileHash, String> {
+    let mut hash = hash_file(file_path)?;
+    if let Some(expected) = expected.filter(|value| !value.trim().is_empty()) {
+        hash.matches = Some(hash_matches(&hash, expected)?);
+        hash.expected_hash = Some(expected.trim().to_string());
+    }
+    Ok(hash)
+}
+
+/// 校验写入列表中带期望摘要的文件，任一不一致即返回错误
+pub fn verify_expected_hashes<'a>(
+    files: impl IntoIterator<Item = (&'a str, Option<&'a str>)>,
+) -> Result<(), String> {
+    for (file_path, expected) in files {
+        let hash = hash_and_check(file_path, expected)?;
+        if hash.matches == Some(false) {
+            return Err(format!(
+                "文件摘要不一致，拒绝写入: {}（期望 {}，实际 SHA-256 {}，CRC32 {}）",
+                file_path,
+                hash.expected_hash.unwrap_or_default(),
+                hash.sha256,
+                hash.crc32
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hash_and_check, verify_expected_hashes};
+
+    #[test]
+    fn computes_and_checks_hashes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.bin");
+        std::fs::write(&path, b"123456789").unwrap();
+        let path = path.to_string_lossy().to_string();
+
+        let hash = hash_and_check(&path, None).unwrap();
+        assert_eq!(hash.size, 9);
+        assert_eq!(hash.crc32, "cbf43926");
+        assert_eq!(
+            hash.sha256,
+            "15e2b0d3c33891ebb0f1ef609ec419420c20e320ce94c65fbc8c3312448eb225"
+        );
+        assert_eq!(hash.matches, None);
+
+        assert_eq!(
+            hash_and_check(&path, Some("0xCBF43926")).unwrap().matches,
+            Some(true)
+        );
+        assert_eq!(
+            hash_and_check(
+                &path,
+                Some("sha256:15E2B0D3C33891EBB0F1EF609EC419420C20E320CE94C65FBC8C3312448EB225")
+            )
+            .unwrap()
+            .matches,
+            Some(true)
+        );
+        assert!(hash_and_check(&path, Some("crc32:1234")).is_err());
+
+        assert!(verify_expected_hashes([(path.as_str(), Some("cbf43926"))]).is_ok());
+        assert!(verify_expected_hashes([(path.as_str(), Some("00000000"))]).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use crate::types::{
+    TauriProgressEvent, TauriProgressOperation, TauriProgressStatus, TauriProgressType,
+    VerifyFlashFileResult, WriteFlashFileInfo,
+};
+use crate::utils::{collect_image_segments, compare_dump_segments, ImageSegment};
+use sftool_lib::{ReadFlashFile, ReadFlashParams, SifliTool};
+use std::fs;
+
+/// 校验进度使用独立的 id 区间，避免与 sftool-lib 分配的进度条 id 冲突
+const VERIFY_PROGRESS_ID_BASE: u64 = 1 << 48;
+
+fn verify_event(
+    id: u64,
+    event_type: &str,
+    segment: &ImageSegment,
+    current: Option<u64>,
+    status: Option<TauriProgressStatus>,
+) -> TauriProgressEvent {
+    let len = segment.range.end - segment.range.start;
+    TauriProgressEvent {
+        id,
+        event_type: event_type.to_string(),
+        step: 0,
+        progress_type: TauriProgressType::Bar { total: len },
+        operation: TauriProgressOperation::Verify {
+            address: segment.range.start as u32,
+            len: len as u32,
+        },
+        current,
+        total: (event_type == "start").then_some(len),
+        status,
+    }
+}
+
+/// 通过 read_flash 把一段 Flash 读到临时文件，再载入内存
+pub fn read_flash_window(
+    tool: &mut dyn SifliTool,
+    address: u32,
+    size: u32,
+) -> Result<Vec<u8>, String> {
+    let read_dir = tempfile::tempdir().map_err(|e| format!("无法创建临时目录: {}", e))?;
+    let dump_path = read_dir.path().join(format!("flash_{address:08X}.bin"));
+    let params = ReadFlashParams {
+        files: vec![ReadFlashFile {
+            file_path: dump_path.to_string_lossy().to_string(),
+            address,
+            size,
+        }],
+    };
+
+    tool.read_flash(&params)
+        .map_err(|e| format!("回读 Flash 失败: {}", e))?;
+    fs::read(&dump_path).map_err(|e| format!("读取回读数据失败: {}", e))
+}
+
+/// 只回读源镜像覆盖的地址范围并逐字节比较，不写入设备
+pub fn verify_device_flash(
+    tool: &mut dyn SifliTool,
+    files: &[WriteFlashFileInfo],
+    mut emit: impl FnMut(TauriProgressEvent),
+) -> Result<Vec<VerifyFlashFileResult>, String> {
+    if files.is_empty() {
+        return Err("未配置固件文件，无法校验".to_string());
+    }
+
+    let segments = collect_image_segments(files)?;
+
+    let mut results: Vec<VerifyFlashFileResult> = files
+        .iter()
+        .map(|file| VerifyFlashFileResult {
+            file_path: file.file_path.clone(),
+            passed: true,
+            checked_bytes: 0,
+            mismatch_bytes: 0,
+            first_mismatch_address: None,
+        })
+        .collect();
+
+    for (index, segment) in segments.iter().enumerate() {
+        let id = VERIFY_PROGRESS_ID_BASE + index as u64;
+        let len = segment.range.end - segment.range.start;
+        emit(verify_event(id, "start", segment, Some(0), None));
+
+        let dump = match read_flash_window(tool, segment.range.start as u32, len as u32) {
+            Ok(dump) => dump,
+            Err(e) => {
+                emit(verify_event(
+                    id,
+                    "finish",
+                    segment,
+                    None,
+                    Some(TauriProgressStatus::Failed(e.clone())),
+                ));
+                return Err(e);
+            }
+        };
+
+        let regions = compare_dump_segments(
+            segment.range.start as u32,
+            &dump,
+            std::slice::from_ref(segment),
+        );
+        let mismatch_bytes: u64 = regions.iter().map(|region| region.mismatch_bytes).sum();
+        let first_mismatch_address = regions
+            .iter()
+            .find_map(|region| region.first_mismatch_address);
+
+        if let Some(result) = results
+            .iter_mut()
+            .find(|result| result.file_path == segment.range.file_path)
+        {
+            result.checked_bytes += len;
+            result.mismatch_bytes += mismatch_bytes;
+            if result.first_mismatch_address.is_none() {
+                result.first_mismatch_address = first_mismatch_address;
+            }
+            result.passed = result.mismatch_bytes == 0;
+        }
+
+        emit(verify_event(id, "increment", segment, Some(len), None));
+        let status = if mismatch_bytes == 0 {
+            TauriProgressStatus::Success
+        } else {
+            TauriProgressStatus::Failed(format!("{} 字节不一致", mismatch_bytes))
+        };
+        emit(verify_event(id, "finish", segment, None, Some(status)));
+    }
+
+    Ok(results)
+}
+
+/// 汇总校验失败的文件，全部通过时返回 None
+pub fn describe_verify_failures(results: &[VerifyFlashFileResult]) -> Option<String> {
+    let failed: Vec<String> = results
+        .iter()
+        .filter(|result| !result.passed)
+        .map(|result| match result.first_mismatch_address {
+            Some(address) => format!(
+                "{}（{} 字节不一致，首个差异地址 0x{:08X}）",
+                result.file_path, result.mismatch_bytes, address
+            ),
+            None => result.file_path.clone(),
+        })
+        .collect();
+
+    if failed.is_empty() {
+        None
+    } else {
+        Some(format!("校验失败: {}", failed.join("; ")))
+    }
+}
@@ -0,0 +1,190 @@
+use crate::types::{
+    CompareFlashDumpRequest, DumpRegionReport, DumpRegionStatus, FlashDumpComparison,
+};
+use crate::utils::{collect_image_segments, ImageSegment};
+use std::fs;
+
+fn region(
+    file_path: Option<&str>,
+    start: u64,
+    end: u64,
+    status: DumpRegionStatus,
+) -> DumpRegionReport {
+    DumpRegionReport {
+        file_path: file_path.map(str::to_string),
+        start,
+        end,
+        status,
+        first_mismatch_address: None,
+        first_mismatch_offset: None,
+        mismatch_bytes: 0,
+    }
+}
+
+/// 逐段对比源镜像与读出数据，并列出读出范围内没有源镜像的空隙
+pub fn compare_dump_segments(
+    dump_address: u32,
+    dump: &[u8],
+    segments: &[ImageSegment],
+) -> Vec<DumpRegionReport> {
+    let dump_start = dump_address as u64;
+    let dump_end = dump_start + dump.len() as u64;
+    let mut regions = Vec::new();
+    let mut covered: Vec<(u64, u64)> = Vec::new();
+
+    for segment in segments {
+        let file_path = Some(segment.range.file_path.as_str());
+        let start = segment.range.start;
+        let end = segment.range.end;
+
+        let overlap_start = start.max(dump_start);
+        let overlap_end = end.min(dump_end);
+        if overlap_start >= overlap_end {
+            regions.push(region(file_path, start, end, DumpRegionStatus::Uncovered));
+            continue;
+        }
+
+        if start < overlap_start {
+            regions.push(region(
+                file_path,
+                start,
+                overlap_start,
+                DumpRegionStatus::Uncovered,
+            ));
+        }
+        if overlap_end < end {
+            regions.push(region(
+                file_path,
+                overlap_end,
+                end,
+                DumpRegionStatus::Uncovered,
+            ));
+        }
+
+        let expected =
+            &segment.data[(overlap_start - start) as usize..(overlap_end - start) as usize];
+        let actual =
+            &dump[(overlap_start - dump_start) as usize..(overlap_end - dump_start) as usize];
+        let mut report = region(
+            file_path,
+            overlap_start,
+            overlap_end,
+            DumpRegionStatus::Match,
+        );
+        for (index, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+            if expected != actual {
+                if report.first_mismatch_address.is_none() {
+                    let address = overlap_start + index as u64;
+                    report.first_mismatch_address = Some(address);
+                    report.first_mismatch_offset = Some(address - dump_start);
+                }
+                report.mismatch_bytes += 1;
+            }
+        }
+        if report.mismatch_bytes > 0 {
+            report.status = DumpRegionStatus::Mismatch;
+        }
+        regions.push(report);
+        covered.push((overlap_start, overlap_end));
+    }
+
+    // 读出范围内没有任何源镜像的区域
+    covered.sort_unstable();
+    let mut cursor = dump_start;
+    for (start, end) in covered {
+        if start > cursor {
+            regions.push(region(None, cursor, start, DumpRegionStatus::Uncovered));
+        }
+        cursor = cursor.max(end);
+    }
+    if cursor < dump_end {
+        regions.push(region(None, cursor, dump_end, DumpRegionStatus::Uncovered));
+    }
+
+    regions.sort_by_key(|region| (region.start, region.end));
+    regions
+}
+
+/// 读取 dump 文件并与源镜像逐字节对比
+pub fn compare_flash_dump_files(
+    request: &CompareFlashDumpRequest,
+) -> Result<FlashDumpComparison, String> {
+    if request.files.is_empty() {
+        return Err("未配置源镜像，无法对比".to_string());
+    }
+
+    let dump = fs::read(&request.dump_path)
+        .map_err(|e| format!("无法读取读出文件 {}: {}", request.dump_path, e))?;
+    let segments = collect_image_segments(&request.files)?;
+    let regions = compare_dump_segments(request.dump_address, &dump, &segments);
+
+    let bytes_with = |status: DumpRegionStatus, source_only: bool| -> u64 {
+        regions
+            .iter()
+            .filter(|region| region.status == status)
+            .filter(|region| !source_only || region.file_path.is_some())
+            .map(|region| region.end - region.start)
+            .sum()
+    };
+    let matched_bytes = bytes_with(DumpRegionStatus::Match, false);
+    let mismatched_bytes = regions.iter().map(|region| region.mismatch_bytes).sum();
+    let uncovered_bytes = bytes_with(DumpRegionStatus::Uncovered, true);
+
+    Ok(FlashDumpComparison {
+        dump_path: request.dump_path.clone(),
+        dump_address: request.dump_address,
+        dump_size: dump.len() as u64,
+        is_match: mismatched_bytes == 0 && uncovered_bytes == 0,
+        regions,
+        matched_bytes,
+        mismatched_bytes,
+        uncovered_bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compare_dump_segments;
+    use crate::types::{DumpRegionStatus, FileAddressRange};
+    use crate::utils::ImageSegment;
+
+    fn segment(file_path: &str, start: u64, data: &[u8]) -> ImageSegment {
+        ImageSegment {
+            range: FileAddressRange {
+                file_path: file_path.to_string(),
+                start,
+                end: start + data.len() as u64,
+            },
+            data: data.to_vec(),
+        }
+    }
+
+    #[test]
+    fn reports_mismatch_and_uncovered_ranges() {
+        let dump = [1, 2, 3, 4, 0xFF, 0xFF, 7, 0, 0];
+        let segments = [
+            segment("ftab.bin", 0x100, &[1, 2, 3, 4]),
+            segment("app.bin", 0x106, &[7, 8, 9, 10]),
+        ];
+
+        let regions = compare_dump_segments(0x100, &dump, &segments);
+        let summary: Vec<_> = regions
+            .iter()
+            .map(|r| (r.file_path.as_deref(), r.start, r.end, r.status))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (Some("ftab.bin"), 0x100, 0x104, DumpRegionStatus::Match),
+                (None, 0x104, 0x106, DumpRegionStatus::Uncovered),
+                (Some("app.bin"), 0x106, 0x109, DumpRegionStatus::Mismatch),
+                (Some("app.bin"), 0x109, 0x10A, DumpRegionStatus::Uncovered),
+            ]
+        );
+
+        let mismatch = &regions[2];
+        assert_eq!(mismatch.first_mismatch_address, Some(0x107));
+        assert_eq!(mismatch.first_mismatch_offset, Some(7));
+        assert_eq!(mismatch.mismatch_bytes, 2);
+    }
+}
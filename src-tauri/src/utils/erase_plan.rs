@@ -0,0 +1,107 @@
+/// 区域擦除的最小单位，起始地址与长度都必须按此对齐：
+/// NOR 按 4 KB 扇区，NAND 按块（常见的 64 页 × 2 KB），SD 按 512 字节块
+pub fn erase_granularity(memory_type: &str) -> u32 {
+    match memory_type.trim().to_lowercase().as_str() {
+        "nand" => 0x2_0000,
+        "sd" => 0x200,
+        _ => 0x1000,
+    }
+}
+
+fn describe_region(start: u64, end: u64) -> String {
+    format!("[0x{:08X}, 0x{:08X})", start, end)
+}
+
+/// 擦除前检查：区域非空、按 granularity 对齐、不超出 32 位地址空间且互不重叠
+pub fn validate_erase_regions(
+    regions: impl IntoIterator<Item = (u32, u32)>,
+    granularity: u32,
+) -> Result<(), String> {
+    let mut ranges = Vec::new();
+    let mut errors = Vec::new();
+
+    for (address, size) in regions {
+        let start = address as u64;
+        let end = start + size as u64;
+        if size == 0 {
+            errors.push(format!("区域 0x{:08X} 的长度为 0", address));
+            continue;
+        }
+        if address % granularity != 0 || size % granularity != 0 {
+            errors.push(format!(
+                "区域 {} 未按 0x{:X} 对齐",
+                describe_region(start, end),
+                granularity
+            ));
+        }
+        if end > u32::MAX as u64 + 1 {
+            errors.push(format!("区域 {} 超出地址空间", describe_region(start, end)));
+        }
+        ranges.push((start, end));
+    }
+
+    if ranges.is_empty() && errors.is_empty() {
+        return Err("未指定擦除区域".to_string());
+    }
+
+    ranges.sort();
+    for pair in ranges.windows(2) {
+        if pair[1].0 < pair[0].1 {
+            errors.push(format!(
+                "区域 {} 与 {} 重叠",
+                describe_region(pair[0].0, pair[0].1),
+                describe_region(pair[1].0, pair[1].1)
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("擦除区域无效: {}", errors.join("; ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{erase_granularity, validate_erase_regions};
+
+    const NOR: u32 = 0x1000;
+
+    #[test]
+    fn accepts_aligned_disjoint_regions() {
+        let regions = [
+            (0x1271_0000, 0x2000),
+            (0x1200_0000, 0x1000),
+            (0x1270_0000, 0x1_0000),
+        ];
+        assert!(validate_erase_regions(regions, NOR).is_ok());
+        assert!(validate_erase_regions([], NOR).is_err());
+    }
+
+    #[test]
+    fn rejects_misaligned_and_overlapping_regions() {
+        let error = validate_erase_regions([(0x1200_0800, 0x1000)], NOR).unwrap_err();
+        assert!(error.contains("对齐"));
+        assert!(validate_erase_regions([(0x1200_0000, 0)], NOR).is_err());
+
+        let error = validate_erase_regions([(0x1200_0000, 0x2000), (0x1200_1000, 0x1000)], NOR)
+            .unwrap_err();
+        assert!(error.contains("重叠"));
+        assert!(
+            validate_erase_regions([(0x1200_0000, 0x1000), (0x1200_1000, 0x1000)], NOR).is_ok()
+        );
+    }
+
+    #[test]
+    fn alignment_follows_memory_type() {
+        assert_eq!(erase_granularity("NOR"), 0x1000);
+        assert_eq!(erase_granularity("sd"), 0x200);
+
+        let nand = erase_granularity("nand");
+        let error = validate_erase_regions([(0x6000_1000, 0x1000)], nand).unwrap_err();
+        assert!(error.contains("0x20000"));
+        assert!(validate_erase_regions([(0x6002_0000, 0x4_0000)], nand).is_ok());
+        assert!(validate_erase_regions([(0x6000_0200, 0x400)], erase_granularity("sd")).is_ok());
+    }
+}
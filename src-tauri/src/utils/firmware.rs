@@ -0,0 +1,191 @@
+use crate::types::{FirmwareFormat, FirmwareImageInfo, FirmwareSegment};
+use goblin::elf::program_header::PT_LOAD;
+use goblin::elf::Elf;
+use ihex::{Reader, Record};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+
+/// 根据文件头与扩展名判断固件格式，无法识别时按 BIN 处理
+pub fn detect_firmware_format(file_path: &str, content: &[u8]) -> FirmwareFormat {
+    if content.starts_with(ELF_MAGIC) {
+        return FirmwareFormat::Elf;
+    }
+
+    let extension = Path::new(file_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("hex") | Some("ihex") => FirmwareFormat::Hex,
+        _ => FirmwareFormat::Bin,
+    }
+}
+
+/// 只读取文件头判断格式，不解析内容
+pub fn detect_firmware_file_format(file_path: &str) -> Result<FirmwareFormat, String> {
+    let mut head = Vec::with_capacity(ELF_MAGIC.len());
+    File::open(file_path)
+        .and_then(|file| file.take(ELF_MAGIC.len() as u64).read_to_end(&mut head))
+        .map_err(|e| format!("无法读取固件文件 {}: {}", file_path, e))?;
+    Ok(detect_firmware_format(file_path, &head))
+}
+
+/// 解析固件文件中的可加载段；BIN 文件没有地址信息，使用调用方给出的基地址
+pub fn inspect_firmware(file_path: &str, base_address: u32) -> Result<FirmwareImageInfo, String> {
+    let content =
+        std::fs::read(file_path).map_err(|e| format!("无法读取固件文件 {}: {}", file_path, e))?;
+    let format = detect_firmware_format(file_path, &content);
+
+    let (segments, entry_point) = match format {
+        FirmwareFormat::Elf => parse_elf_segments(&content)?,
+        FirmwareFormat::Hex => {
+            let text = std::str::from_utf8(&content)
+                .map_err(|_| format!("HEX 文件包含非文本内容: {}", file_path))?;
+            parse_hex_segments(text)?
+        }
+        FirmwareFormat::Bin => (
+            vec![FirmwareSegment {
+                start_address: base_address,
+                length: content.len() as u64,
+                file_offset: Some(0),
+            }],
+            None,
+        ),
+    };
+
+    Ok(FirmwareImageInfo {
+        file_path: file_path.to_string(),
+        format,
+        file_size: content.len() as u64,
+        entry_point,
+        total_size: segments.iter().map(|segment| segment.length).sum(),
+        lowest_address: segments.iter().map(|segment| segment.start_address).min(),
+        highest_address: segments.iter().map(FirmwareSegment::end_address).max(),
+        segments,
+    })
+}
+
+/// 使用 PT_LOAD 段的物理地址（LMA），与烧录时写入的位置一致
+pub fn parse_elf_segments(content: &[u8]) -> Result<(Vec<FirmwareSegment>, Option<u32>), String> {
+    let elf = Elf::parse(content).map_err(|e| format!("解析 ELF 文件失败: {}", e))?;
+
+    let mut segments = Vec::new();
+    for header in &elf.program_headers {
+        if header.p_type != PT_LOAD || header.p_filesz == 0 {
+            continue;
+        }
+
+        let start_address = u32::try_from(header.p_paddr)
+            .map_err(|_| format!("ELF 段地址超出 32 位范围: 0x{:X}", header.p_paddr))?;
+        segments.push(FirmwareSegment {
+            start_address,
+            length: header.p_filesz,
+            file_offset: Some(header.p_offset),
+        });
+    }
+    segments.sort_by_key(|segment| segment.start_address);
+
+    let entry_point = u32::try_from(elf.entry).ok();
+    Ok((segments, entry_point))
+}
+
+/// 合并地址连续的数据记录，得到 HEX 文件实际覆盖的地址段
+pub fn parse_hex_segments(text: &str) -> Result<(Vec<FirmwareSegment>, Option<u32>), String> {
+    let mut base_address: u32 = 0;
+    let mut entry_point = None;
+    let mut ranges: Vec<(u32, u64)> = Vec::new();
+
+    for record in Reader::new(text) {
+        let record = record.map_err(|e| format!("解析 HEX 文件失败: {}", e))?;
+        match record {
+            Record::Data { offset, value } => {
+                if !value.is_empty() {
+                    ranges.push((base_address.wrapping_add(offset as u32), value.len() as u64));
+                }
+            }
+            Record::ExtendedSegmentAddress(segment) => base_address = (segment as u32) << 4,
+            Record::ExtendedLinearAddress(upper) => base_address = (upper as u32) << 16,
+            Record::StartSegmentAddress { cs, ip } => {
+                entry_point = Some(((cs as u32) << 4).wrapping_add(ip as u32))
+            }
+            Record::StartLinearAddress(address) => entry_point = Some(address),
+            Record::EndOfFile => break,
+        }
+    }
+
+    ranges.sort_by_key(|(start, _)| *start);
+
+    let mut segments: Vec<FirmwareSegment> = Vec::new();
+    for (start, length) in ranges {
+        if let Some(last) = segments.last_mut() {
+            if start as u64 <= last.end_address() {
+                let end = (start as u64 + length).max(last.end_address());
+                last.length = end - last.start_address as u64;
+                continue;
+            }
+        }
+        segments.push(FirmwareSegment {
+            start_address: start,
+            length,
+            file_offset: None,
+        });
+    }
+
+    Ok((segments, entry_point))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect_firmware_format, parse_hex_segments};
+    use crate::types::{FirmwareFormat, FirmwareSegment};
+
+    #[test]
+    fn detects_format_by_magic_and_extension() {
+        assert_eq!(
+            detect_firmware_format("app.bin", b"\x7fELF\x01"),
+            FirmwareFormat::Elf
+        );
+        assert_eq!(
+            detect_firmware_format("app.HEX", b":00000001FF"),
+            FirmwareFormat::Hex
+        );
+        assert_eq!(
+            detect_firmware_format("app.bin", &[0xFF; 4]),
+            FirmwareFormat::Bin
+        );
+    }
+
+    #[test]
+    fn merges_contiguous_hex_records() {
+        let text = [
+            ":020000041201E7",
+            ":0400000001020304F2",
+            ":0400040005060708DE",
+            ":04010000AABBCCDDED",
+            ":0400000512010000E4",
+            ":00000001FF",
+        ]
+        .join("\n");
+
+        let (segments, entry_point) = parse_hex_segments(&text).unwrap();
+        assert_eq!(
+            segments,
+            vec![
+                FirmwareSegment {
+                    start_address: 0x1201_0000,
+                    length: 8,
+                    file_offset: None,
+                },
+                FirmwareSegment {
+                    start_address: 0x1201_0100,
+                    length: 4,
+                    file_offset: None,
+                },
+            ]
+        );
+        assert_eq!(entry_point, Some(0x1201_0000));
+    }
+}
@@ -0,0 +1,156 @@
+use crate::types::{MassProductionFrozenFile, MassProductionWriteFileInfo};
+use crate::utils::{hash_file, verify_expected_hashes};
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+use tempfile::TempDir;
+
+struct FrozenEntry {
+    info: MassProductionFrozenFile,
+    /// 原文件的大小与修改时间，变化时才重新计算摘要
+    original_len: u64,
+    original_modified: Option<SystemTime>,
+}
+
+/// 量产会话开始时复制并锁定的固件；副本目录随本结构释放而删除
+pub struct FrozenFirmware {
+    _dir: TempDir,
+    entries: Vec<FrozenEntry>,
+}
+
+fn file_stamp(path: &str) -> Result<(u64, Option<SystemTime>), String> {
+    let metadata = fs::metadata(path).map_err(|e| format!("无法读取文件 {}: {}", path, e))?;
+    Ok((metadata.len(), metadata.modified().ok()))
+}
+
+/// 复制写入列表中的所有文件并记录摘要；复制前后摘要不一致说明文件正在被修改
+pub fn freeze_firmware_files(
+    files: &[MassProductionWriteFileInfo],
+) -> Result<FrozenFirmware, String> {
+    let dir = tempfile::Builder::new()
+        .prefix("sftool-mass-production-")
+        .tempdir()
+        .map_err(|e| format!("无法创建固件锁定目录: {}", e))?;
+
+    let mut entries = Vec::new();
+    for (index, file) in files.iter().enumerate() {
+        let (original_len, original_modified) = file_stamp(&file.file_path)?;
+        let original = hash_file(&file.file_path)?;
+
+        let file_name = Path::new(&file.file_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "firmware.bin".to_string());
+        // 保留原文件名和扩展名，parse_file_info 依赖扩展名识别 ELF/HEX
+        let frozen_path = dir.path().join(format!("{index}_{file_name}"));
+        fs::copy(&file.file_path, &frozen_path)
+            .map_err(|e| format!("复制固件文件 {} 失败: {}", file.file_path, e))?;
+        let frozen_path = frozen_path.to_string_lossy().to_string();
+
+        let copied = hash_file(&frozen_path)?;
+        if copied.sha256 != original.sha256 {
+            return Err(format!(
+                "固件文件 {} 在锁定过程中发生变化，请确认文件未被占用后重试",
+                file.file_path
+            ));
+        }
+        // 请求中给出的期望摘要以副本为准再校验一次
+        verify_expected_hashes([(frozen_path.as_str(), file.expected_hash.as_deref())])?;
+
+        entries.push(FrozenEntry {
+            info: MassProductionFrozenFile {
+                file_path: file.file_path.clone(),
+                frozen_path,
+                address: file.address,
+                size: original.size,
+                crc32: original.crc32,
+                sha256: original.sha256,
+            },
+            original_len,
+            original_modified,
+        });
+    }
+
+    Ok(FrozenFirmware { _dir: dir, entries })
+}
+
+impl FrozenFirmware {
+    pub fn infos(&self) -> Vec<MassProductionFrozenFile> {
+        self.entries
+            .iter()
+            .map(|entry| entry.info.clone())
+            .collect()
+    }
+
+    /// 指向副本的写入列表；期望摘要固定为锁定时的 SHA-256，写入前再次校验副本
+    pub fn write_files(&self) -> Vec<MassProductionWriteFileInfo> {
+        self.entries
+            .iter()
+            .map(|entry| MassProductionWriteFileInfo {
+                address: entry.info.address,
+                file_path: entry.info.frozen_path.clone(),
+                expected_hash: Some(format!("sha256:{}", entry.info.sha256)),
+            })
+            .collect()
+    }
+
+    /// 检查原文件是否被修改；仅在大小或修改时间变化时重新计算摘要
+    pub fn check_originals(&mut self) -> Result<(), String> {
+        for entry in &mut self.entries {
+            let path = entry.info.file_path.clone();
+            let (len, modified) =
+                file_stamp(&path).map_err(|_| format!("固件原文件已被删除或无法访问: {}", path))?;
+            if len == entry.original_len && modified == entry.original_modified {
+                continue;
+            }
+
+            let current = hash_file(&path)?;
+            if current.sha256 != entry.info.sha256 {
+                return Err(format!(
+                    "固件原文件在量产过程中被修改: {}（锁定 SHA-256 {}，当前 {}）",
+                    path, entry.info.sha256, current.sha256
+                ));
+            }
+            entry.original_len = len;
+            entry.original_modified = modified;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::freeze_firmware_files;
+    use crate::types::MassProductionWriteFileInfo;
+    use crate::utils::verify_expected_hashes;
+
+    #[test]
+    fn frozen_copy_survives_original_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("app.bin");
+        std::fs::write(&original, [0x11u8; 32]).unwrap();
+
+        let mut frozen = freeze_firmware_files(&[MassProductionWriteFileInfo {
+            address: 0x1202_0000,
+            file_path: original.to_string_lossy().to_string(),
+            expected_hash: None,
+        }])
+        .unwrap();
+        assert!(frozen.check_originals().is_ok());
+
+        let write_files = frozen.write_files();
+        assert_ne!(write_files[0].file_path, frozen.infos()[0].file_path);
+        assert_eq!(write_files[0].address, 0x1202_0000);
+
+        std::fs::write(&original, [0x22u8; 48]).unwrap();
+        assert!(frozen.check_originals().is_err());
+
+        // 副本仍是锁定时的内容
+        assert!(verify_expected_hashes(
+            write_files
+                .iter()
+                .map(|file| (file.file_path.as_str(), file.expected_hash.as_deref()))
+        )
+        .is_ok());
+    }
+}
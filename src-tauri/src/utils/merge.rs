@@ -0,0 +1,248 @@
+use crate::types::{
+    parse_write_file, FileAddressRange, FileOverlap, MergeImagesRequest, MergeImagesResult,
+    WriteFlashFileInfo,
+};
+use crate::utils::{describe_range, hash_file};
+use ihex::Record;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+const FILL_BYTE: u8 = 0xFF;
+/// 防止地址相距很远的镜像（如 NOR 与 PSRAM）合并出超大文件
+const MAX_MERGED_SIZE: u64 = 512 * 1024 * 1024;
+const HEX_RECORD_LEN: usize = 16;
+
+/// 镜像文件按 Utils::parse_file_info 解析后的一段数据
+pub struct ImageSegment {
+    pub range: FileAddressRange,
+    pub data: Vec<u8>,
+}
+
+/// 通过 Utils::parse_file_info 解析每个文件，HEX/ELF 会按段展开
+pub fn collect_image_segments(files: &[WriteFlashFileInfo]) -> Result<Vec<ImageSegment>, String> {
+    let mut segments = Vec::new();
+    for file_info in files {
+        if !Path::new(&file_info.file_path).exists() {
+            return Err(format!("文件不存在: {}", file_info.file_path));
+        }
+
+        for mut write_file in parse_write_file(&file_info.file_path, file_info.address)? {
+            let mut data = Vec::new();
+            write_file
+                .file
+                .seek(SeekFrom::Start(0))
+                .and_then(|_| write_file.file.read_to_end(&mut data))
+                .map_err(|e| format!("读取文件 {} 失败: {}", file_info.file_path, e))?;
+            if data.is_empty() {
+                continue;
+            }
+            let start = write_file.address as u64;
+            segments.push(ImageSegment {
+                range: FileAddressRange {
+                    file_path: file_info.file_path.clone(),
+                    start,
+                    end: start + data.len() as u64,
+                },
+                data,
+            });
+        }
+    }
+    Ok(segments)
+}
+
+/// 任意两段相交即视为重叠，同一文件内的段也不例外
+fn find_segment_overlaps(segments: &[ImageSegment]) -> Vec<FileOverlap> {
+    let mut sorted: Vec<&FileAddressRange> = segments.iter().map(|s| &s.range).collect();
+    sorted.sort_by_key(|range| (range.start, range.end));
+
+    sorted
+        .windows(2)
+        .filter(|pair| pair[1].start < pair[0].end)
+        .map(|pair| FileOverlap {
+            first: pair[0].clone(),
+            second: pair[1].clone(),
+        })
+        .collect()
+}
+
+/// 将各段放入从 base_address 开始的平铺镜像，空隙填充 0xFF
+pub fn build_merged_image(
+    segments: &[ImageSegment],
+    base_address: Option<u32>,
+) -> Result<(u32, Vec<u8>), String> {
+    if segments.is_empty() {
+        return Err("没有可合并的数据".to_string());
+    }
+
+    let overlaps = find_segment_overlaps(segments);
+    if !overlaps.is_empty() {
+        let pairs = overlaps
+            .iter()
+            .map(|overlap| {
+                format!(
+                    "{} 与 {}",
+                    describe_range(&overlap.first),
+                    describe_range(&overlap.second)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+        return Err(format!("合并地址重叠: {pairs}"));
+    }
+
+    let lowest = segments.iter().map(|s| s.range.start).min().unwrap_or(0);
+    let highest = segments.iter().map(|s| s.range.end).max().unwrap_or(0);
+    let base = base_address.map(u64::from).unwrap_or(lowest);
+    if lowest < base {
+        let below: Vec<String> = segments
+            .iter()
+            .filter(|s| s.range.start < base)
+            .map(|s| describe_range(&s.range))
+            .collect();
+        return Err(format!(
+            "以下数据低于起始地址 0x{:08X}: {}",
+            base,
+            below.join(", ")
+        ));
+    }
+
+    let size = highest - base;
+    if size > MAX_MERGED_SIZE {
+        return Err(format!(
+            "合并后镜像大小 {} 字节超过上限 {} 字节，请检查文件地址",
+            size, MAX_MERGED_SIZE
+        ));
+    }
+
+    let mut image = vec![FILL_BYTE; size as usize];
+    for segment in segments {
+        let offset = (segment.range.start - base) as usize;
+        image[offset..offset + segment.data.len()].copy_from_slice(&segment.data);
+    }
+
+    Ok((base as u32, image))
+}
+
+/// 生成 Intel HEX 文本；数据记录不跨越 64 KB 边界
+pub fn intel_hex_from_image(base_address: u32, image: &[u8]) -> Result<String, String> {
+    let mut records = Vec::new();
+    let mut upper: Option<u16> = None;
+    let mut offset = 0usize;
+
+    while offset < image.len() {
+        let address = base_address as u64 + offset as u64;
+        if address > u32::MAX as u64 {
+            return Err("合并镜像超出 32 位地址范围，无法输出 Intel HEX".to_string());
+        }
+        let address = address as u32;
+        let segment_upper = (address >> 16) as u16;
+        if upper != Some(segment_upper) {
+            records.push(Record::ExtendedLinearAddress(segment_upper));
+            upper = Some(segment_upper);
+        }
+
+        let to_boundary = 0x1_0000 - (address & 0xFFFF) as usize;
+        let len = HEX_RECORD_LEN.min(to_boundary).min(image.len() - offset);
+        records.push(Record::Data {
+            offset: (address & 0xFFFF) as u16,
+            value: image[offset..offset + len].to_vec(),
+        });
+        offset += len;
+    }
+    records.push(Record::EndOfFile);
+
+    ihex::create_object_file_representation(&records)
+        .map_err(|e| format!("生成 Intel HEX 失败: {}", e))
+}
+
+/// 合并写入列表为单个 BIN，可选同时输出 Intel HEX
+pub fn merge_image_files(request: &MergeImagesRequest) -> Result<MergeImagesResult, String> {
+    if request.files.is_empty() {
+        return Err("未配置固件文件，无法合并".to_string());
+    }
+
+    let segments = collect_image_segments(&request.files)?;
+    let (base_address, image) = build_merged_image(&segments, request.base_address)?;
+
+    fs::write(&request.output_path, &image)
+        .map_err(|e| format!("写入合并镜像 {} 失败: {}", request.output_path, e))?;
+
+    let hex_output_path = request
+        .hex_output_path
+        .clone()
+        .filter(|path| !path.trim().is_empty());
+    if let Some(hex_path) = &hex_output_path {
+        let hex = intel_hex_from_image(base_address, &image)?;
+        fs::write(hex_path, hex).map_err(|e| format!("写入 Intel HEX {} 失败: {}", hex_path, e))?;
+    }
+
+    let hash = hash_file(&request.output_path)?;
+    let mut ranges: Vec<FileAddressRange> = segments.into_iter().map(|s| s.range).collect();
+    ranges.sort_by_key(|range| range.start);
+
+    Ok(MergeImagesResult {
+        output_path: request.output_path.clone(),
+        hex_output_path,
+        base_address,
+        size: hash.size,
+        segments: ranges,
+        crc32: hash.crc32,
+        sha256: hash.sha256,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_merged_image, intel_hex_from_image, ImageSegment};
+    use crate::types::FileAddressRange;
+
+    fn segment(file_path: &str, start: u64, data: &[u8]) -> ImageSegment {
+        ImageSegment {
+            range: FileAddressRange {
+                file_path: file_path.to_string(),
+                start,
+                end: start + data.len() as u64,
+            },
+            data: data.to_vec(),
+        }
+    }
+
+    #[test]
+    fn fills_gaps_and_detects_overlaps() {
+        let segments = [
+            segment("ftab.bin", 0x1000_0000, &[1, 2]),
+            segment("app.bin", 0x1000_0004, &[3]),
+        ];
+        let (base, image) = build_merged_image(&segments, None).unwrap();
+        assert_eq!(base, 0x1000_0000);
+        assert_eq!(image, [1, 2, 0xFF, 0xFF, 3]);
+
+        let (_, image) = build_merged_image(&segments, Some(0x0FFF_FFFF)).unwrap();
+        assert_eq!(image[0], 0xFF);
+        assert!(build_merged_image(&segments, Some(0x1000_0001)).is_err());
+
+        let overlapping = [
+            segment("a.bin", 0x1000_0000, &[0; 8]),
+            segment("b.bin", 0x1000_0004, &[0; 8]),
+        ];
+        let error = build_merged_image(&overlapping, None).unwrap_err();
+        assert!(error.contains("a.bin") && error.contains("b.bin"));
+    }
+
+    #[test]
+    fn hex_records_split_at_64k_boundary() {
+        let hex = intel_hex_from_image(0x1000_FFFE, &[0xAA, 0xBB, 0xCC]).unwrap();
+        let lines: Vec<&str> = hex.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                ":020000041000EA",
+                ":02FFFE00AABB9C",
+                ":020000041001E9",
+                ":01000000CC33",
+                ":00000001FF",
+            ]
+        );
+    }
+}
@@ -1,8 +1,40 @@
+pub mod archive;
+pub mod checksum;
+pub mod device_verify;
+pub mod dump_compare;
+pub mod erase_plan;
+pub mod firmware;
+pub mod frozen_firmware;
+pub mod merge;
+pub mod package;
+pub mod param_export;
+pub mod partition;
+pub mod recipe;
+pub mod serial_pool;
 pub mod serial_ports;
 pub mod stub_ops;
 pub mod tool_factory;
+pub mod traceability;
+pub mod unit_data;
 pub mod validator;
+pub mod write_plan;
 
+pub use archive::*;
+pub use checksum::*;
+pub use device_verify::*;
+pub use dump_compare::*;
+pub use erase_plan::*;
+pub use firmware::*;
+pub use frozen_firmware::*;
+pub use merge::*;
+pub use package::*;
+pub use param_export::*;
+pub use partition::*;
+pub use recipe::*;
+pub use serial_pool::*;
 pub use serial_ports::*;
 pub use tool_factory::*;
+pub use traceability::*;
+pub use unit_data::*;
 pub use validator::*;
+pub use write_plan::*;
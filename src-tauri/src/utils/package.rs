@@ -0,0 +1,590 @@
+use crate::types::{
+    ArchiveLimits, SfpkgBuildRequest, SfpkgEntryInfo, SfpkgInfo, SfpkgManifest, SfpkgSignature,
+    SfpkgSignatureStatus, SftoolParamConfig, SftoolParamFile, WriteFlashCommand,
+    SFPKG_FORMAT_VERSION, SFPKG_MANIFEST_NAME, SFPKG_SIGNATURE_NAME,
+};
+use crate::utils::{extract_archive_to_temp_dir, sha256_file};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use tempfile::tempdir;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+const PACKAGE_FILES_DIR: &str = "files";
+const PACKAGE_STUB_DIR: &str = "stub";
+pub const TRUSTED_PACKAGE_KEYS_FILENAME: &str = "trusted-package-keys.json";
+
+fn decode_key_bytes<const N: usize>(text: &str, what: &str) -> Result<[u8; N], String> {
+    let bytes = BASE64
+        .decode(text.trim())
+        .map_err(|e| format!("{}格式无效: {}", what, e))?;
+    <[u8; N]>::try_from(bytes.as_slice())
+        .map_err(|_| format!("{}长度应为 {} 字节，实际为 {}", what, N, bytes.len()))
+}
+
+pub fn parse_package_public_key(text: &str) -> Result<VerifyingKey, String> {
+    let bytes = decode_key_bytes::<32>(text, "公钥")?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| format!("公钥无效: {}", e))
+}
+
+fn encode_public_key(key: &VerifyingKey) -> String {
+    BASE64.encode(key.to_bytes())
+}
+
+fn read_signing_key(path: &str) -> Result<SigningKey, String> {
+    if path.trim().is_empty() {
+        return Err("未指定签名私钥，固件包必须签名".to_string());
+    }
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("读取签名私钥 {} 失败: {}", path, e))?;
+    decode_key_bytes::<32>(&content, "签名私钥").map(|seed| SigningKey::from_bytes(&seed))
+}
+
+/// 生成新的签名私钥文件并返回对应的公钥；已存在的文件不会被覆盖
+pub fn generate_package_signing_key(output_path: &str) -> Result<String, String> {
+    let mut seed = [0u8; 32];
+    getrandom::getrandom(&mut seed).map_err(|e| format!("生成随机数失败: {}", e))?;
+    let key = SigningKey::from_bytes(&seed);
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    // 私钥只允许当前用户读写
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(output_path)
+        .map_err(|e| format!("创建私钥文件 {} 失败: {}", output_path, e))?;
+    writeln!(file, "{}", BASE64.encode(seed))
+        .and_then(|()| file.sync_all())
+        .map_err(|e| format!("写入私钥文件失败: {}", e))?;
+
+    Ok(encode_public_key(&key.verifying_key()))
+}
+
+fn sign_manifest(manifest: &[u8], key: &SigningKey) -> SfpkgSignature {
+    SfpkgSignature {
+        public_key: encode_public_key(&key.verifying_key()),
+        signature: BASE64.encode(key.sign(manifest).to_bytes()),
+    }
+}
+
+fn parse_signature(content: &[u8]) -> Result<SfpkgSignature, String> {
+    serde_json::from_slice(content).map_err(|e| format!("解析固件包签名失败: {}", e))
+}
+
+/// 签名必须覆盖清单原始字节；清单里的文件摘要再覆盖各个文件
+fn signature_status(
+    manifest: &[u8],
+    signature: Option<&SfpkgSignature>,
+    trusted_keys: &[VerifyingKey],
+) -> SfpkgSignatureStatus {
+    let Some(signature) = signature else {
+        return SfpkgSignatureStatus::Unsigned;
+    };
+    let verified = parse_package_public_key(&signature.public_key).and_then(|key| {
+        let bytes = decode_key_bytes::<64>(&signature.signature, "签名")?;
+        key.verify_strict(manifest, &Signature::from_bytes(&bytes))
+            .map_err(|e| e.to_string())?;
+        Ok(key)
+    });
+
+    match verified {
+        Err(_) => SfpkgSignatureStatus::Invalid,
+        Ok(key) if trusted_keys.contains(&key) => SfpkgSignatureStatus::Trusted,
+        Ok(_) => SfpkgSignatureStatus::Untrusted,
+    }
+}
+
+fn verify_manifest_signature(
+    manifest: &[u8],
+    signature: Option<&SfpkgSignature>,
+    trusted_keys: &[VerifyingKey],
+) -> Result<(), String> {
+    match signature_status(manifest, signature, trusted_keys) {
+        SfpkgSignatureStatus::Trusted => Ok(()),
+        SfpkgSignatureStatus::Unsigned => {
+            Err(format!("固件包缺少签名 {}，拒绝导入", SFPKG_SIGNATURE_NAME))
+        }
+        SfpkgSignatureStatus::Invalid => Err("固件包签名无效，清单可能已被篡改".to_string()),
+        SfpkgSignatureStatus::Untrusted if trusted_keys.is_empty() => {
+            Err("未配置受信任的固件包公钥，无法导入".to_string())
+        }
+        SfpkgSignatureStatus::Untrusted => Err(format!(
+            "固件包签名者 {} 不在受信任公钥列表中",
+            signature.map(|s| s.public_key.as_str()).unwrap_or_default()
+        )),
+    }
+}
+
+/// 读取受信任公钥列表，文件不存在时视为空列表
+pub fn load_trusted_package_keys(path: &Path) -> Result<Vec<String>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read(path).map_err(|e| format!("读取受信任公钥列表失败: {}", e))?;
+    serde_json::from_slice(&content).map_err(|e| format!("解析受信任公钥列表失败: {}", e))
+}
+
+/// 校验并去重后保存，返回实际保存的列表
+pub fn save_trusted_package_keys(path: &Path, keys: &[String]) -> Result<Vec<String>, String> {
+    let mut saved: Vec<String> = Vec::new();
+    for key in keys
+        .iter()
+        .map(|key| key.trim())
+        .filter(|key| !key.is_empty())
+    {
+        let parsed = parse_package_public_key(key)?;
+        let encoded = encode_public_key(&parsed);
+        if !saved.contains(&encoded) {
+            saved.push(encoded);
+        }
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建配置目录失败: {}", e))?;
+    }
+    let content =
+        serde_json::to_vec_pretty(&saved).map_err(|e| format!("序列化受信任公钥失败: {}", e))?;
+    fs::write(path, content).map_err(|e| format!("保存受信任公钥列表失败: {}", e))?;
+    Ok(saved)
+}
+
+pub fn parse_trusted_package_keys(keys: &[String]) -> Result<Vec<VerifyingKey>, String> {
+    keys.iter()
+        .map(|key| parse_package_public_key(key))
+        .collect()
+}
+
+/// 生成包内路径；不同目录下的同名文件加序号前缀区分
+fn package_entry_name(
+    dir: &str,
+    file_path: &str,
+    used: &mut HashSet<String>,
+) -> Result<String, String> {
+    let file_name = Path::new(file_path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("无法获取文件名: {}", file_path))?;
+
+    let mut entry_name = format!("{dir}/{file_name}");
+    let mut index = 1;
+    while !used.insert(entry_name.clone()) {
+        entry_name = format!("{dir}/{index}_{file_name}");
+        index += 1;
+    }
+
+    Ok(entry_name)
+}
+
+/// 清单中的路径必须是包内相对路径，不能跳出包目录
+fn ensure_package_relative(path: &str) -> Result<(), String> {
+    let is_safe = Path::new(path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    if path.is_empty() || !is_safe {
+        return Err(format!("固件包清单中的路径无效: {}", path));
+    }
+    Ok(())
+}
+
+/// 按请求打包固件，返回写入包内的清单
+pub fn build_package(request: &SfpkgBuildRequest) -> Result<SfpkgManifest, String> {
+    if request.chip.trim().is_empty() {
+        return Err("未指定芯片型号".to_string());
+    }
+    if request.files.is_empty() {
+        return Err("未配置固件文件，无法打包".to_string());
+    }
+    let signing_key = read_signing_key(&request.signing_key_path)?;
+
+    let mut used_names = HashSet::new();
+    let mut sha256 = BTreeMap::new();
+    let mut sources = Vec::new();
+    let mut param_files = Vec::new();
+
+    for file in &request.files {
+        if !Path::new(&file.file_path).is_file() {
+            return Err(format!("文件不存在: {}", file.file_path));
+        }
+
+        let entry_name = package_entry_name(PACKAGE_FILES_DIR, &file.file_path, &mut used_names)?;
+        sha256.insert(entry_name.clone(), sha256_file(&file.file_path)?);
+        param_files.push(SftoolParamFile {
+            path: entry_name.clone(),
+            address: file
+                .address
+                .clone()
+                .filter(|address| !address.trim().is_empty()),
+        });
+        sources.push((entry_name, file.file_path.clone()));
+    }
+
+    let stub_config = match request
+        .stub_config_path
+        .as_ref()
+        .filter(|path| !path.trim().is_empty())
+    {
+        Some(stub_path) => {
+            if !Path::new(stub_path).is_file() {
+                return Err(format!("Stub 配置文件不存在: {}", stub_path));
+            }
+            let entry_name = package_entry_name(PACKAGE_STUB_DIR, stub_path, &mut used_names)?;
+            sha256.insert(entry_name.clone(), sha256_file(stub_path)?);
+            sources.push((entry_name.clone(), stub_path.clone()));
+            Some(entry_name)
+        }
+        None => None,
+    };
+
+    let manifest = SfpkgManifest {
+        format_version: SFPKG_FORMAT_VERSION,
+        config: SftoolParamConfig {
+            chip: request.chip.clone(),
+            memory: request.memory.clone(),
+            port: None,
+            baud: None,
+            before: None,
+            after: None,
+            connect_attempts: None,
+            compat: None,
+            quiet: None,
+            write_flash: Some(WriteFlashCommand {
+                verify: Some(request.verify),
+                erase_all: Some(request.erase_all),
+                no_compress: Some(request.no_compress),
+                files: param_files,
+            }),
+            read_flash: None,
+            erase_flash: None,
+            erase_region: None,
+        },
+        stub_config,
+        sha256,
+    };
+
+    // 先写临时文件再重命名，避免中途失败留下半个包
+    let output_path = PathBuf::from(&request.output_path);
+    let temp_path = output_path.with_extension("sfpkg.tmp");
+    let result = write_package(&temp_path, &manifest, &signing_key, &sources).and_then(|()| {
+        fs::rename(&temp_path, &output_path).map_err(|e| format!("保存固件包失败: {}", e))
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result?;
+
+    Ok(manifest)
+}
+
+fn write_package(
+    path: &Path,
+    manifest: &SfpkgManifest,
+    signing_key: &SigningKey,
+    sources: &[(String, String)],
+) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("创建固件包失败: {}", e))?;
+    let mut writer = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let manifest_json =
+        serde_json::to_vec_pretty(manifest).map_err(|e| format!("序列化固件包清单失败: {}", e))?;
+    writer
+        .start_file(SFPKG_MANIFEST_NAME, options)
+        .map_err(|e| format!("写入固件包清单失败: {}", e))?;
+    writer
+        .write_all(&manifest_json)
+        .map_err(|e| format!("写入固件包清单失败: {}", e))?;
+
+    let signature = serde_json::to_vec_pretty(&sign_manifest(&manifest_json, signing_key))
+        .map_err(|e| format!("序列化固件包签名失败: {}", e))?;
+    writer
+        .start_file(SFPKG_SIGNATURE_NAME, options)
+        .map_err(|e| format!("写入固件包签名失败: {}", e))?;
+    writer
+        .write_all(&signature)
+        .map_err(|e| format!("写入固件包签名失败: {}", e))?;
+
+    for (entry_name, source_path) in sources {
+        writer
+            .start_file(entry_name.as_str(), options)
+            .map_err(|e| format!("写入 {} 失败: {}", entry_name, e))?;
+        let mut source =
+            File::open(source_path).map_err(|e| format!("无法打开文件 {}: {}", source_path, e))?;
+        std::io::copy(&mut source, &mut writer)
+            .map_err(|e| format!("写入 {} 失败: {}", entry_name, e))?;
+    }
+
+    writer
+        .finish()
+        .map_err(|e| format!("保存固件包失败: {}", e))?;
+    Ok(())
+}
+
+fn parse_manifest(content: &[u8]) -> Result<SfpkgManifest, String> {
+    let manifest: SfpkgManifest =
+        serde_json::from_slice(content).map_err(|e| format!("解析固件包清单失败: {}", e))?;
+
+    if manifest.format_version == 0 || manifest.format_version > SFPKG_FORMAT_VERSION {
+        return Err(format!(
+            "不支持的固件包版本: {}（当前支持 {}）",
+            manifest.format_version, SFPKG_FORMAT_VERSION
+        ));
+    }
+
+    Ok(manifest)
+}
+
+fn read_zip_entry(archive: &mut ZipArchive<File>, name: &str) -> Result<Option<Vec<u8>>, String> {
+    let mut entry = match archive.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(format!("读取固件包条目 {} 失败: {}", name, e)),
+    };
+    let mut content = Vec::new();
+    entry
+        .read_to_end(&mut content)
+        .map_err(|e| format!("读取固件包条目 {} 失败: {}", name, e))?;
+    Ok(Some(content))
+}
+
+/// 不解压，直接读取包内清单、签名状态与条目列表；签名不受信任时不报错，由调用方展示
+pub fn read_package_info(
+    package_path: &str,
+    trusted_keys: &[VerifyingKey],
+) -> Result<SfpkgInfo, String> {
+    let file =
+        File::open(package_path).map_err(|e| format!("无法打开固件包 {}: {}", package_path, e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| format!("固件包格式无效: {}", e))?;
+
+    let manifest_bytes = read_zip_entry(&mut archive, SFPKG_MANIFEST_NAME)?
+        .ok_or_else(|| format!("固件包缺少 {}", SFPKG_MANIFEST_NAME))?;
+    let manifest = parse_manifest(&manifest_bytes)?;
+    let signature = read_zip_entry(&mut archive, SFPKG_SIGNATURE_NAME)?
+        .map(|content| parse_signature(&content))
+        .transpose()?;
+    let signature_status = signature_status(&manifest_bytes, signature.as_ref(), trusted_keys);
+
+    let mut entries = Vec::new();
+    for index in 0..archive.len() {
+        let entry = archive
+            .by_index(index)
+            .map_err(|e| format!("读取固件包条目失败: {}", e))?;
+        if entry.is_dir() || [SFPKG_MANIFEST_NAME, SFPKG_SIGNATURE_NAME].contains(&entry.name()) {
+            continue;
+        }
+        entries.push(SfpkgEntryInfo {
+            path: entry.name().to_string(),
+            size: entry.size(),
+        });
+    }
+
+    Ok(SfpkgInfo {
+        package_path: package_path.to_string(),
+        manifest,
+        signature,
+        signature_status,
+        entries,
+    })
+}
+
+/// 解压固件包到临时目录；调用方负责将返回的目录注册到 AppState 统一清理
+pub fn extract_package_to_temp_dir(package_path: &str) -> Result<PathBuf, String> {
+    if !Path::new(package_path).is_file() {
+        return Err(format!("文件不存在: {}", package_path));
+    }
+
+    // unarc-rs 按扩展名识别格式，先以 .zip 名称暂存一份再复用通用解压流程
+    let staging = tempdir().map_err(|e| format!("无法创建临时目录: {}", e))?;
+    let staged_path = staging.path().join("package.zip");
+    fs::copy(package_path, &staged_path).map_err(|e| format!("读取固件包失败: {}", e))?;
+
+    extract_archive_to_temp_dir(&staged_path.to_string_lossy(), ArchiveLimits::default())
+        .map_err(String::from)
+}
+
+/// 读取解压目录中的清单，校验清单签名来自受信任公钥，再校验所有文件的 SHA-256
+pub fn load_verified_manifest(
+    package_dir: &Path,
+    trusted_keys: &[VerifyingKey],
+) -> Result<SfpkgManifest, String> {
+    let content = fs::read(package_dir.join(SFPKG_MANIFEST_NAME))
+        .map_err(|_| format!("固件包缺少 {}", SFPKG_MANIFEST_NAME))?;
+    let signature_path = package_dir.join(SFPKG_SIGNATURE_NAME);
+    let signature = if signature_path.is_file() {
+        let signature_content =
+            fs::read(&signature_path).map_err(|e| format!("读取固件包签名失败: {}", e))?;
+        Some(parse_signature(&signature_content)?)
+    } else {
+        None
+    };
+    verify_manifest_signature(&content, signature.as_ref(), trusted_keys)?;
+    let manifest = parse_manifest(&content)?;
+
+    let mut referenced: Vec<&str> = manifest
+        .config
+        .write_flash
+        .as_ref()
+        .map(|write_flash| {
+            write_flash
+                .files
+                .iter()
+                .map(|file| file.path.as_str())
+                .collect()
+        })
+        .unwrap_or_default();
+    referenced.extend(manifest.stub_config.as_deref());
+
+    for path in referenced {
+        ensure_package_relative(path)?;
+        if !manifest.sha256.contains_key(path) {
+            return Err(format!("固件包清单缺少文件摘要: {}", path));
+        }
+    }
+
+    for (path, expected) in &manifest.sha256 {
+        ensure_package_relative(path)?;
+        let actual = sha256_file(&package_dir.join(path).to_string_lossy())?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(format!(
+                "固件包文件校验失败: {}（期望 {}，实际 {}）",
+                path, expected, actual
+            ));
+        }
+    }
+
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_package, ensure_package_relative, generate_package_signing_key,
+        load_verified_manifest, package_entry_name, parse_package_public_key, read_package_info,
+        sign_manifest, SigningKey,
+    };
+    use crate::types::{
+        SfpkgBuildFile, SfpkgBuildRequest, SfpkgSignatureStatus, SFPKG_MANIFEST_NAME,
+        SFPKG_SIGNATURE_NAME,
+    };
+    use crate::utils::sha256_file;
+    use std::collections::HashSet;
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn deduplicates_entry_names() {
+        let mut used = HashSet::new();
+        assert_eq!(
+            package_entry_name("files", "/a/app.bin", &mut used).unwrap(),
+            "files/app.bin"
+        );
+        assert_eq!(
+            package_entry_name("files", "/b/app.bin", &mut used).unwrap(),
+            "files/1_app.bin"
+        );
+    }
+
+    #[test]
+    fn rejects_paths_escaping_package() {
+        assert!(ensure_package_relative("files/app.bin").is_ok());
+        assert!(ensure_package_relative("../app.bin").is_err());
+        assert!(ensure_package_relative("/etc/passwd").is_err());
+        assert!(ensure_package_relative("").is_err());
+    }
+
+    #[test]
+    fn built_package_can_be_inspected() {
+        let dir = tempfile::tempdir().unwrap();
+        let firmware = dir.path().join("app.bin");
+        fs::write(&firmware, [0xAAu8; 64]).unwrap();
+        let output = dir.path().join("release.sfpkg");
+        let key_path = dir.path().join("release.key");
+        let public_key = generate_package_signing_key(&key_path.to_string_lossy()).unwrap();
+        assert!(generate_package_signing_key(&key_path.to_string_lossy()).is_err());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&key_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let manifest = build_package(&SfpkgBuildRequest {
+            output_path: output.to_string_lossy().to_string(),
+            chip: "SF32LB52".to_string(),
+            memory: Some("nor".to_string()),
+            files: vec![SfpkgBuildFile {
+                file_path: firmware.to_string_lossy().to_string(),
+                address: Some("0x12020000".to_string()),
+            }],
+            verify: true,
+            erase_all: false,
+            no_compress: false,
+            stub_config_path: None,
+            signing_key_path: key_path.to_string_lossy().to_string(),
+        })
+        .unwrap();
+        assert_eq!(manifest.sha256.len(), 1);
+
+        let trusted = [parse_package_public_key(&public_key).unwrap()];
+        let info = read_package_info(&output.to_string_lossy(), &trusted).unwrap();
+        assert_eq!(info.manifest.config.chip, "SF32LB52");
+        assert_eq!(info.manifest.sha256, manifest.sha256);
+        assert_eq!(info.signature_status, SfpkgSignatureStatus::Trusted);
+        assert_eq!(info.signature.unwrap().public_key, public_key);
+        assert_eq!(info.entries.len(), 1);
+        assert_eq!(info.entries[0].path, "files/app.bin");
+        assert_eq!(info.entries[0].size, 64);
+
+        let info = read_package_info(&output.to_string_lossy(), &[]).unwrap();
+        assert_eq!(info.signature_status, SfpkgSignatureStatus::Untrusted);
+    }
+
+    fn write_manifest(dir: &Path, firmware: &[u8]) -> Vec<u8> {
+        fs::create_dir_all(dir.join("files")).unwrap();
+        let firmware_path = dir.join("files").join("app.bin");
+        fs::write(&firmware_path, firmware).unwrap();
+        let manifest = serde_json::json!({
+            "format_version": 1,
+            "chip": "SF32LB52",
+            "write_flash": { "files": [{ "path": "files/app.bin", "address": "0x12020000" }] },
+            "sha256": { "files/app.bin": sha256_file(&firmware_path.to_string_lossy()).unwrap() },
+        });
+        let content = serde_json::to_vec(&manifest).unwrap();
+        fs::write(dir.join(SFPKG_MANIFEST_NAME), &content).unwrap();
+        content
+    }
+
+    #[test]
+    fn import_requires_trusted_manifest_signature() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let other = SigningKey::from_bytes(&[8u8; 32]);
+        let trusted = [key.verifying_key()];
+
+        let manifest = write_manifest(dir.path(), &[0xAA; 16]);
+        let signature_path = dir.path().join(SFPKG_SIGNATURE_NAME);
+        assert!(load_verified_manifest(dir.path(), &trusted).is_err());
+
+        let signature = serde_json::to_vec(&sign_manifest(&manifest, &key)).unwrap();
+        fs::write(&signature_path, &signature).unwrap();
+        assert!(load_verified_manifest(dir.path(), &trusted).is_ok());
+        assert!(load_verified_manifest(dir.path(), &[other.verifying_key()]).is_err());
+        assert!(load_verified_manifest(dir.path(), &[]).is_err());
+
+        // 替换文件并重新计算清单摘要，签名不再匹配
+        write_manifest(dir.path(), &[0x55; 16]);
+        let error = load_verified_manifest(dir.path(), &trusted).unwrap_err();
+        assert!(error.contains("签名无效"), "{error}");
+
+        // 用不受信任的私钥重新签名同样被拒绝
+        let manifest = write_manifest(dir.path(), &[0x55; 16]);
+        let signature = serde_json::to_vec(&sign_manifest(&manifest, &other)).unwrap();
+        fs::write(&signature_path, &signature).unwrap();
+        assert!(load_verified_manifest(dir.path(), &trusted).is_err());
+    }
+}
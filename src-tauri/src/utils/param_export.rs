@@ -0,0 +1,288 @@
+use crate::types::{
+    FirmwareFormat, SftoolParamConfig, SftoolParamExportSource, SftoolParamFile, WriteFlashCommand,
+};
+use crate::utils::detect_firmware_file_format;
+use std::path::{Component, Path, PathBuf};
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+fn export_address(file_path: &str, address: u32) -> Result<Option<String>, String> {
+    // ELF/HEX 的地址为 0 表示由文件自身决定；BIN 文件即使写到 0 也必须给出地址
+    if address == 0 && detect_firmware_file_format(file_path)? != FirmwareFormat::Bin {
+        return Ok(None);
+    }
+    Ok(Some(format!("0x{:08X}", address)))
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// 计算 target 相对于 base_dir 的路径，统一使用 `/` 分隔以便在其他平台上使用；
+/// 两者不在同一根目录（如 Windows 不同盘符）时保留绝对路径
+pub fn relative_path_string(base_dir: &Path, target: &Path) -> String {
+    let base = normalize(base_dir);
+    let target = normalize(target);
+
+    let base_components: Vec<Component> = base.components().collect();
+    let target_components: Vec<Component> = target.components().collect();
+    if base_components.first() != target_components.first() {
+        return target.to_string_lossy().to_string();
+    }
+
+    let common = base_components
+        .iter()
+        .zip(target_components.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut parts: Vec<String> = Vec::new();
+    for _ in common..base_components.len() {
+        parts.push("..".to_string());
+    }
+    parts.extend(
+        target_components[common..]
+            .iter()
+            .map(|component| component.as_os_str().to_string_lossy().to_string()),
+    );
+
+    parts.join("/")
+}
+
+struct ExportFields<'a> {
+    chip: &'a str,
+    memory: &'a str,
+    port: Option<String>,
+    baud: Option<u32>,
+    before: &'a str,
+    after: &'a str,
+    files: Vec<(&'a str, u32)>,
+    verify: bool,
+    erase_all: bool,
+    no_compress: bool,
+}
+
+impl<'a> From<&'a SftoolParamExportSource> for ExportFields<'a> {
+    fn from(source: &'a SftoolParamExportSource) -> Self {
+        match source {
+            SftoolParamExportSource::WriteFlash { request, device } => Self {
+                chip: &device.chip_type,
+                memory: &device.memory_type,
+                port: non_empty(&device.port_name),
+                baud: Some(device.baud_rate),
+                before: &device.before_operation,
+                after: &device.after_operation,
+                files: request
+                    .files
+                    .iter()
+                    .map(|file| (file.file_path.as_str(), file.address))
+                    .collect(),
+                verify: request.verify,
+                erase_all: request.erase_all,
+                no_compress: request.no_compress,
+            },
+            // 量产按端口动态分配，不导出串口
+            SftoolParamExportSource::MassProduction { request } => Self {
+                chip: &request.chip_model,
+                memory: &request.memory_type,
+                port: None,
+                baud: request.baud_rate,
+                before: &request.before_operation,
+                after: &request.after_operation,
+                files: request
+                    .files
+                    .iter()
+                    .map(|file| (file.file_path.as_str(), file.address))
+                    .collect(),
+                verify: request.verify,
+                erase_all: request.erase_all,
+                no_compress: request.no_compress,
+            },
+        }
+    }
+}
+
+/// 将当前写入配置转换为 sftool_param.json，文件路径相对于 output_dir
+pub fn build_sftool_param_config(
+    source: &SftoolParamExportSource,
+    output_dir: &Path,
+) -> Result<SftoolParamConfig, String> {
+    let ExportFields {
+        chip,
+        memory,
+        port,
+        baud,
+        before,
+        after,
+        files,
+        verify,
+        erase_all,
+        no_compress,
+    } = ExportFields::from(source);
+
+    if chip.trim().is_empty() {
+        return Err("未指定芯片型号".to_string());
+    }
+    if files.is_empty() {
+        return Err("未配置固件文件，无法导出".to_string());
+    }
+
+    let mut param_files = Vec::new();
+    for (file_path, address) in files {
+        if !Path::new(file_path).is_file() {
+            return Err(format!("文件不存在: {}", file_path));
+        }
+        param_files.push(SftoolParamFile {
+            path: relative_path_string(output_dir, Path::new(file_path)),
+            address: export_address(file_path, address)?,
+        });
+    }
+
+    Ok(SftoolParamConfig {
+        chip: chip.trim().to_string(),
+        memory: non_empty(memory).map(|memory| memory.to_lowercase()),
+        port,
+        baud,
+        before: non_empty(before),
+        after: non_empty(after),
+        connect_attempts: None,
+        compat: None,
+        quiet: None,
+        write_flash: Some(WriteFlashCommand {
+            verify: Some(verify),
+            erase_all: Some(erase_all),
+            no_compress: Some(no_compress),
+            files: param_files,
+        }),
+        read_flash: None,
+        erase_flash: None,
+        erase_region: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_sftool_param_config, relative_path_string};
+    use crate::commands::resolve_sftool_param_config;
+    use crate::types::{
+        DeviceConfig, SftoolParamConfig, SftoolParamExportSource, WriteFlashFileInfo,
+        WriteFlashRequest,
+    };
+    use std::path::Path;
+
+    #[test]
+    fn computes_relative_paths() {
+        assert_eq!(
+            relative_path_string(Path::new("/work/out"), Path::new("/work/build/app.bin")),
+            "../build/app.bin"
+        );
+        assert_eq!(
+            relative_path_string(Path::new("/work"), Path::new("/work/build/app.bin")),
+            "build/app.bin"
+        );
+    }
+
+    #[test]
+    fn exported_config_round_trips_through_parser() {
+        let dir = tempfile::tempdir().unwrap();
+        let build_dir = dir.path().join("build");
+        let output_dir = dir.path().join("release");
+        std::fs::create_dir_all(&build_dir).unwrap();
+        std::fs::create_dir_all(&output_dir).unwrap();
+
+        let firmware = [
+            ("app.bin", 0x1202_0000, &[0u8; 16][..]),
+            // 写到 0 的 BIN 文件也要保留地址
+            ("boot.bin", 0, &[0u8; 16][..]),
+            ("main.elf", 0, &b"\x7fELF\x01\x01\x01"[..]),
+            ("ftab.hex", 0, &b":00000001FF\n"[..]),
+        ];
+        let mut files = Vec::new();
+        for (name, address, content) in firmware {
+            let path = build_dir.join(name);
+            std::fs::write(&path, content).unwrap();
+            files.push(WriteFlashFileInfo {
+                address,
+                file_path: path.to_string_lossy().to_string(),
+                expected_hash: None,
+            });
+        }
+
+        let source = SftoolParamExportSource::WriteFlash {
+            request: WriteFlashRequest {
+                files,
+                verify: true,
+                no_compress: false,
+                erase_all: false,
+            },
+            device: DeviceConfig {
+                chip_type: "SF32LB52".to_string(),
+                memory_type: "NOR".to_string(),
+                port_name: "COM3".to_string(),
+                baud_rate: 1_000_000,
+                stub_config_path: String::new(),
+                external_stub_path: String::new(),
+                before_operation: "default_reset".to_string(),
+                after_operation: "soft_reset".to_string(),
+            },
+        };
+
+        let config = build_sftool_param_config(&source, &output_dir).unwrap();
+        let json = serde_json::to_string_pretty(&config).unwrap();
+        assert!(!json.contains("null"));
+
+        let parsed: SftoolParamConfig = serde_json::from_str(&json).unwrap();
+        let result = resolve_sftool_param_config(
+            parsed,
+            &output_dir,
+            Some("SF32LB52".to_string()),
+            Some("nor".to_string()),
+        )
+        .unwrap();
+
+        assert!(result.validation.is_valid);
+        let addresses: Vec<(&str, &str)> = result
+            .extracted_files
+            .iter()
+            .map(|file| (file.name.as_str(), file.address.as_str()))
+            .collect();
+        assert_eq!(
+            addresses,
+            [
+                ("app.bin", "0x12020000"),
+                ("boot.bin", "0x00000000"),
+                ("main.elf", ""),
+                ("ftab.hex", ""),
+            ]
+        );
+        assert_eq!(
+            Path::new(&result.extracted_files[0].path)
+                .canonicalize()
+                .unwrap(),
+            build_dir.join("app.bin").canonicalize().unwrap()
+        );
+    }
+
+    #[test]
+    fn defaults_bin_file_without_address_with_warning() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("app.bin"), [0u8; 16]).unwrap();
+        let parsed: SftoolParamConfig = serde_json::from_str(
+            r#"{"chip": "SF32LB52", "write_flash": {"files": [{"path": "app.bin"}]}}"#,
+        )
+        .unwrap();
+
+        let result = resolve_sftool_param_config(parsed, dir.path(), None, None).unwrap();
+        assert_eq!(result.extracted_files[0].address, "0x10000000");
+        assert!(result.validation.is_valid);
+        assert_eq!(result.validation.warnings.len(), 1);
+        assert!(result.validation.warnings[0].contains("app.bin"));
+    }
+}
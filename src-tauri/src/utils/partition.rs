@@ -0,0 +1,228 @@
+use crate::types::{FileAddressRange, PartitionInfo};
+use crate::utils::{describe_range, parse_number};
+use serde::Deserialize;
+use std::fs;
+
+/// ptab.json 中的数值可以是整数或 "0x..." 字符串
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PtabNumber {
+    Int(u64),
+    Text(String),
+}
+
+impl PtabNumber {
+    fn to_u32(&self, field: &str) -> Result<u32, String> {
+        match self {
+            PtabNumber::Int(value) => {
+                u32::try_from(*value).map_err(|_| format!("分区表字段 {field} 超出范围: {value}"))
+            }
+            PtabNumber::Text(text) => {
+                parse_number(text).map_err(|e| format!("分区表字段 {field}: {e}"))
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PtabMemory {
+    mem: String,
+    base: PtabNumber,
+    #[serde(default)]
+    regions: Vec<PtabRegion>,
+}
+
+#[derive(Deserialize)]
+struct PtabRegion {
+    offset: PtabNumber,
+    max_size: Option<PtabNumber>,
+    size: Option<PtabNumber>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// 解析 SDK 的 ptab.json：顶层为存储器数组，每个存储器含 base 与 regions
+pub fn parse_partition_table(content: &str) -> Result<Vec<PartitionInfo>, String> {
+    let memories: Vec<PtabMemory> =
+        serde_json::from_str(content).map_err(|e| format!("解析分区表失败: {}", e))?;
+
+    let mut partitions = Vec::new();
+    for memory in memories {
+        let base = memory.base.to_u32("base")?;
+        for region in memory.regions {
+            let offset = region.offset.to_u32("offset")?;
+            let size = region
+                .max_size
+                .as_ref()
+                .or(region.size.as_ref())
+                .ok_or_else(|| {
+                    format!(
+                        "分区表 {} 中偏移 0x{:08X} 的区域缺少 max_size",
+                        memory.mem, offset
+                    )
+                })?
+                .to_u32("max_size")?;
+            let address = base.checked_add(offset).ok_or_else(|| {
+                format!("分区表 {} 中偏移 0x{:08X} 超出地址空间", memory.mem, offset)
+            })?;
+            let name = region
+                .tags
+                .first()
+                .cloned()
+                .unwrap_or_else(|| format!("{}@0x{:08X}", memory.mem, address));
+
+            partitions.push(PartitionInfo {
+                name,
+                tags: region.tags,
+                memory: memory.mem.clone(),
+                address,
+                size,
+            });
+        }
+    }
+
+    Ok(partitions)
+}
+
+pub fn load_partition_table_file(path: &str) -> Result<Vec<PartitionInfo>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("读取分区表 {} 失败: {}", path, e))?;
+    parse_partition_table(&content)
+}
+
+/// 名称与任一 tag 匹配（不区分大小写），tag 的 _REGION 后缀可省略，如 fs_root 匹配 FS_ROOT_REGION
+fn matches_partition_name(partition: &PartitionInfo, name: &str) -> bool {
+    let name = name.trim().to_uppercase();
+    partition.name.to_uppercase() == name
+        || partition.tags.iter().any(|tag| {
+            let tag = tag.to_uppercase();
+            tag == name || tag.strip_suffix("_REGION") == Some(name.as_str())
+        })
+}
+
+pub fn find_partition<'a>(
+    partitions: &'a [PartitionInfo],
+    name: &str,
+) -> Result<&'a PartitionInfo, String> {
+    let matched: Vec<&PartitionInfo> = partitions
+        .iter()
+        .filter(|partition| matches_partition_name(partition, name))
+        .collect();
+
+    match matched.as_slice() {
+        [partition] => Ok(partition),
+        [] => {
+            let names = partitions
+                .iter()
+                .map(|partition| partition.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            Err(format!("分区表中没有名为 {name} 的分区，可用分区: {names}"))
+        }
+        _ => {
+            let candidates = matched
+                .iter()
+                .map(|partition| format!("{} (0x{:08X})", partition.name, partition.address))
+                .collect::<Vec<_>>()
+                .join(", ");
+            Err(format!("分区名 {name} 匹配到多个分区: {candidates}"))
+        }
+    }
+}
+
+/// 包含该地址的最小分区；分区表中的区域可能相互嵌套
+fn partition_containing(partitions: &[PartitionInfo], address: u64) -> Option<&PartitionInfo> {
+    partitions
+        .iter()
+        .filter(|partition| partition.address as u64 <= address && address < partition.end())
+        .min_by_key(|partition| partition.size)
+}
+
+/// 检查每段镜像都完整落在其起始地址所在的分区内
+pub fn check_images_fit(
+    partitions: &[PartitionInfo],
+    ranges: &[FileAddressRange],
+) -> Result<(), String> {
+    let mut errors = Vec::new();
+    for range in ranges {
+        match partition_containing(partitions, range.start) {
+            Some(partition) if range.end > partition.end() => errors.push(format!(
+                "{} 超出分区 {} [0x{:08X}, 0x{:08X}) {} 字节",
+                describe_range(range),
+                partition.name,
+                partition.address,
+                partition.end(),
+                range.end - partition.end()
+            )),
+            Some(_) => {}
+            None => errors.push(format!("{} 不在分区表的任何分区内", describe_range(range))),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("镜像与分区表不符: {}", errors.join("; ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_images_fit, find_partition, parse_partition_table};
+    use crate::types::FileAddressRange;
+
+    const PTAB: &str = r#"[
+        {
+            "mem": "flash2",
+            "base": "0x12000000",
+            "regions": [
+                { "offset": "0x00000000", "max_size": "0x00010000", "tags": ["FLASH_TABLE"] },
+                { "offset": "0x00020000", "max_size": "0x00200000", "tags": ["HCPU_FLASH_CODE"] },
+                { "offset": 10485760, "max_size": "0x00200000", "tags": ["FS_ROOT_REGION"] }
+            ]
+        },
+        {
+            "mem": "psram1",
+            "base": "0x60000000",
+            "regions": [{ "offset": "0x0", "max_size": "0x100000" }]
+        }
+    ]"#;
+
+    fn range(file_path: &str, start: u64, end: u64) -> FileAddressRange {
+        FileAddressRange {
+            file_path: file_path.to_string(),
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn resolves_partitions_by_tag() {
+        let partitions = parse_partition_table(PTAB).unwrap();
+        assert_eq!(partitions.len(), 4);
+        assert_eq!(partitions[3].name, "psram1@0x60000000");
+
+        let fs_root = find_partition(&partitions, "fs_root").unwrap();
+        assert_eq!(fs_root.address, 0x12A0_0000);
+        assert_eq!(fs_root.size, 0x20_0000);
+        assert!(find_partition(&partitions, "hcpu_flash_code").is_ok());
+        assert!(find_partition(&partitions, "nvds").is_err());
+    }
+
+    #[test]
+    fn rejects_images_that_overflow_their_partition() {
+        let partitions = parse_partition_table(PTAB).unwrap();
+        let fits = [
+            range("ftab.bin", 0x1200_0000, 0x1200_2000),
+            range("app.bin", 0x1202_0000, 0x1222_0000),
+        ];
+        assert!(check_images_fit(&partitions, &fits).is_ok());
+
+        let error = check_images_fit(&partitions, &[range("app.bin", 0x1202_0000, 0x1222_0001)])
+            .unwrap_err();
+        assert!(error.contains("HCPU_FLASH_CODE"));
+        assert!(
+            check_images_fit(&partitions, &[range("x.bin", 0x1201_0000, 0x1201_0010)]).is_err()
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use crate::types::{
+    ArchiveRecipeSource, SftoolParamConfig, SftoolParamFile, WriteFlashCommand, SFPKG_MANIFEST_NAME,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+const SFTOOL_PARAM_SUFFIX: &str = "sftool_param.json";
+const SDK_BUILD_DIR_PREFIX: &str = "build_";
+/// SDK 构建目录中的镜像：(相对目录, 文件名前缀, 是否必需)
+const SDK_BUILD_IMAGES: [(&str, &str, bool); 3] = [
+    ("bootloader", "bootloader", false),
+    ("ftab", "ftab", true),
+    ("", "main", true),
+];
+/// 只使用自带地址的格式，BIN 的地址与板型相关无法推断
+const SDK_IMAGE_EXTENSIONS: [&str; 2] = ["elf", "hex"];
+
+/// 在解压目录中识别到的烧录配置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedRecipe {
+    pub source: ArchiveRecipeSource,
+    /// sftool_param.json 或 manifest.json 的路径；SDK 构建时为构建目录
+    pub path: PathBuf,
+}
+
+fn is_sftool_param_name(name: &str) -> bool {
+    name.to_lowercase().ends_with(SFTOOL_PARAM_SUFFIX)
+}
+
+/// 固件包清单带有 format_version 字段，与其他同名 JSON 区分
+fn is_package_manifest(path: &Path) -> bool {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .is_some_and(|value| value.get("format_version").is_some())
+}
+
+/// 按优先级查找：固件包清单 > sftool_param.json > SDK 构建目录，同类取层级最浅者
+pub fn detect_archive_recipe(root: &Path) -> Option<DetectedRecipe> {
+    let mut manifest: Option<(usize, PathBuf)> = None;
+    let mut param: Option<(usize, PathBuf)> = None;
+    let mut sdk_build: Option<(usize, PathBuf)> = None;
+
+    let entries = WalkDir::new(root)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|entry| entry.ok());
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy();
+        let depth = entry.depth();
+        let slot = if entry.file_type().is_dir() {
+            if !name.starts_with(SDK_BUILD_DIR_PREFIX) || !is_sdk_build_dir(entry.path()) {
+                continue;
+            }
+            &mut sdk_build
+        } else if name == SFPKG_MANIFEST_NAME && is_package_manifest(entry.path()) {
+            &mut manifest
+        } else if is_sftool_param_name(&name) {
+            &mut param
+        } else {
+            continue;
+        };
+
+        let is_shallower = match slot {
+            Some((found_depth, _)) => depth < *found_depth,
+            None => true,
+        };
+        if is_shallower {
+            *slot = Some((depth, entry.path().to_path_buf()));
+        }
+    }
+
+    [
+        (ArchiveRecipeSource::PackageManifest, manifest),
+        (ArchiveRecipeSource::SftoolParam, param),
+        (ArchiveRecipeSource::SdkBuild, sdk_build),
+    ]
+    .into_iter()
+    .find_map(|(source, found)| found.map(|(_, path)| DetectedRecipe { source, path }))
+}
+
+fn find_sdk_image(build_dir: &Path, sub_dir: &str, stem: &str) -> Option<String> {
+    SDK_IMAGE_EXTENSIONS.iter().find_map(|ext| {
+        let relative = if sub_dir.is_empty() {
+            format!("{stem}.{ext}")
+        } else {
+            format!("{sub_dir}/{stem}.{ext}")
+        };
+        build_dir.join(&relative).is_file().then_some(relative)
+    })
+}
+
+fn is_sdk_build_dir(dir: &Path) -> bool {
+    SDK_BUILD_IMAGES
+        .iter()
+        .filter(|(_, _, required)| *required)
+        .all(|(sub_dir, stem, _)| find_sdk_image(dir, sub_dir, stem).is_some())
+}
+
+/// 从目录名推断芯片型号，如 build_sf32lb52-lcd_n16r8_hcpu -> SF32LB52
+pub fn chip_from_build_dir_name(name: &str) -> Option<String> {
+    let lower = name.to_lowercase();
+    let start = lower.find("sf32lb")?;
+    let digits: String = lower[start + 6..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    if digits.is_empty() {
+        return None;
+    }
+    Some(format!("SF32LB{}", digits))
+}
+
+/// 为 SDK 构建目录生成等价的 sftool_param 配置，镜像地址由 ELF/HEX 自身决定
+pub fn sdk_build_config(
+    build_dir: &Path,
+    fallback_chip: Option<&str>,
+) -> Result<SftoolParamConfig, String> {
+    let dir_name = build_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let chip = chip_from_build_dir_name(&dir_name)
+        .or_else(|| fallback_chip.map(str::to_string))
+        .ok_or_else(|| format!("无法从构建目录 {} 推断芯片型号", dir_name))?;
+
+    let files: Vec<SftoolParamFile> = SDK_BUILD_IMAGES
+        .iter()
+        .filter_map(|(sub_dir, stem, _)| find_sdk_image(build_dir, sub_dir, stem))
+        .map(|path| SftoolParamFile {
+            path,
+            address: None,
+        })
+        .collect();
+    if files.is_empty() {
+        return Err(format!("构建目录 {} 中没有可烧录的镜像", dir_name));
+    }
+
+    Ok(SftoolParamConfig {
+        chip,
+        memory: None,
+        port: None,
+        baud: None,
+        before: None,
+        after: None,
+        connect_attempts: None,
+        compat: None,
+        quiet: None,
+        write_flash: Some(WriteFlashCommand {
+            verify: Some(true),
+            erase_all: None,
+            no_compress: None,
+            files,
+        }),
+        read_flash: None,
+        erase_flash: None,
+        erase_region: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{chip_from_build_dir_name, detect_archive_recipe, sdk_build_config};
+    use crate::types::ArchiveRecipeSource;
+    use std::fs;
+
+    #[test]
+    fn infers_chip_from_build_dir() {
+        assert_eq!(
+            chip_from_build_dir_name("build_sf32lb52-lcd_n16r8_hcpu").as_deref(),
+            Some("SF32LB52")
+        );
+        assert_eq!(chip_from_build_dir_name("build_eh-lb523_hcpu"), None);
+    }
+
+    #[test]
+    fn prefers_shallowest_param_file_over_sdk_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let build = dir.path().join("release/build_sf32lb56-lcd_a128r12n1_hcpu");
+        fs::create_dir_all(build.join("ftab")).unwrap();
+        fs::create_dir_all(build.join("bootloader")).unwrap();
+        fs::write(build.join("main.elf"), b"").unwrap();
+        fs::write(build.join("ftab/ftab.hex"), b"").unwrap();
+        fs::write(build.join("bootloader/bootloader.elf"), b"").unwrap();
+
+        let recipe = detect_archive_recipe(dir.path()).unwrap();
+        assert_eq!(recipe.source, ArchiveRecipeSource::SdkBuild);
+        let config = sdk_build_config(&recipe.path, None).unwrap();
+        assert_eq!(config.chip, "SF32LB56");
+        let paths: Vec<_> = config
+            .write_flash
+            .unwrap()
+            .files
+            .into_iter()
+            .map(|f| f.path)
+            .collect();
+        assert_eq!(
+            paths,
+            ["bootloader/bootloader.elf", "ftab/ftab.hex", "main.elf"]
+        );
+
+        fs::write(build.join("sftool_param.json"), b"{}").unwrap();
+        fs::write(dir.path().join("release/sftool_param.json"), b"{}").unwrap();
+        let recipe = detect_archive_recipe(dir.path()).unwrap();
+        assert_eq!(recipe.source, ArchiveRecipeSource::SftoolParam);
+        assert_eq!(recipe.path, dir.path().join("release/sftool_param.json"));
+    }
+}
@@ -0,0 +1,586 @@
+use crate::types::{SerialPoolImportRequest, SerialPoolStatus, SerialRange};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+pub const SERIAL_POOL_DIRNAME: &str = "serial_pools";
+const SERIAL_POOL_FILE_VERSION: u32 = 1;
+
+/// 池文件的写锁，同时记录每个文件已写入的最新变更序号，较旧的内容不会覆盖较新的内容
+static POOL_FILES: Mutex<BTreeMap<PathBuf, u64>> = Mutex::new(BTreeMap::new());
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Serialize, Deserialize, Clone)]
+struct RangeCursor {
+    #[serde(flatten)]
+    range: SerialRange,
+    /// 已从号段开头分配出去的数量
+    issued: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct SerialReservation {
+    value: String,
+    owner: String,
+    reserved_at: u64,
+}
+
+/// 池文件内容；号段和列表只追加不删除，已分配的值永远不会被再次导入
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct SerialPoolData {
+    version: u32,
+    ranges: Vec<RangeCursor>,
+    values: Vec<String>,
+    values_issued: usize,
+    released: Vec<String>,
+    reserved: Vec<SerialReservation>,
+    committed: u64,
+    burned: Vec<String>,
+}
+
+impl SerialRange {
+    fn format_value(&self, number: u64) -> String {
+        format!(
+            "{}{:0width$}",
+            self.prefix,
+            number,
+            width = self.width as usize
+        )
+    }
+
+    fn size(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    fn contains(&self, value: &str) -> bool {
+        let Some(digits) = value.strip_prefix(self.prefix.as_str()) else {
+            return false;
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return false;
+        }
+        digits.parse::<u64>().is_ok_and(|number| {
+            (self.start..=self.end).contains(&number) && self.format_value(number) == value
+        })
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.start > self.end {
+            return Err(format!("号段起始值 {} 大于结束值 {}", self.start, self.end));
+        }
+        if self.end - self.start == u64::MAX {
+            return Err("号段过大".to_string());
+        }
+        // 限定位数后每个值长度固定，不同号段之间才能可靠地判断重复
+        if self.width > 0 {
+            if let Some(limit) = 10u64.checked_pow(self.width) {
+                if self.end >= limit {
+                    return Err(format!("号段结束值 {} 超过 {} 位", self.end, self.width));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn conflicts_with(&self, other: &SerialRange) -> Option<String> {
+        if self.prefix == other.prefix {
+            if self.width != other.width {
+                return Some(format!(
+                    "前缀 {:?} 的号段位数不一致（{} 与 {}）",
+                    self.prefix, self.width, other.width
+                ));
+            }
+            if self.start <= other.end && other.start <= self.end {
+                return Some(format!(
+                    "号段 {}..={} 与已有号段 {}..={} 重叠",
+                    self.format_value(self.start),
+                    self.format_value(self.end),
+                    other.format_value(other.start),
+                    other.format_value(other.end)
+                ));
+            }
+            None
+        } else if self.prefix.starts_with(&other.prefix) || other.prefix.starts_with(&self.prefix) {
+            Some(format!(
+                "号段前缀 {:?} 与 {:?} 相互包含，可能生成重复序列号",
+                self.prefix, other.prefix
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+impl SerialPoolData {
+    fn remaining(&self) -> u64 {
+        let from_ranges: u64 = self
+            .ranges
+            .iter()
+            .map(|cursor| cursor.range.size() - cursor.issued)
+            .sum();
+        from_ranges + (self.values.len() - self.values_issued) as u64 + self.released.len() as u64
+    }
+
+    fn contains(&self, value: &str) -> bool {
+        self.values.iter().any(|existing| existing == value)
+            || self
+                .ranges
+                .iter()
+                .any(|cursor| cursor.range.contains(value))
+    }
+}
+
+fn validate_pool_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "序列号池名称 {:?} 无效，只能包含字母、数字、- 和 _",
+            name
+        ))
+    }
+}
+
+pub fn serial_pool_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join(SERIAL_POOL_DIRNAME)
+}
+
+fn pool_file_path(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    validate_pool_name(name)?;
+    Ok(dir.join(format!("{name}.json")))
+}
+
+fn read_pool_file(path: &Path) -> Result<SerialPoolData, String> {
+    let content =
+        fs::read(path).map_err(|e| format!("读取序列号池 {} 失败: {}", path.display(), e))?;
+    let data: SerialPoolData = serde_json::from_slice(&content)
+        .map_err(|e| format!("解析序列号池 {} 失败: {}", path.display(), e))?;
+    if data.version != SERIAL_POOL_FILE_VERSION {
+        return Err(format!(
+            "序列号池 {} 的版本 {} 不受支持",
+            path.display(),
+            data.version
+        ));
+    }
+    Ok(data)
+}
+
+/// 写临时文件并落盘后再重命名，进程在任何时刻崩溃都只会留下旧文件或新文件
+fn write_pool_file(path: &Path, data: &SerialPoolData) -> Result<(), String> {
+    let content =
+        serde_json::to_vec_pretty(data).map_err(|e| format!("序列化序列号池失败: {}", e))?;
+    let temp_path = path.with_extension("json.tmp");
+    let result = File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(&content)?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temp_path, path));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(format!("保存序列号池 {} 失败: {}", path.display(), e));
+    }
+    Ok(())
+}
+
+/// 池在内存中变更后待写入的内容；调用方在释放量产状态锁之后再调用 persist
+#[must_use]
+pub struct SerialPoolWrite {
+    path: PathBuf,
+    data: SerialPoolData,
+    generation: u64,
+}
+
+impl SerialPoolWrite {
+    /// 文件中已有更新的内容时直接跳过，其中已包含本次变更
+    pub fn persist(self) -> Result<(), String> {
+        let mut written = POOL_FILES.lock().unwrap();
+        if written
+            .get(&self.path)
+            .is_some_and(|generation| *generation >= self.generation)
+        {
+            return Ok(());
+        }
+        write_pool_file(&self.path, &self.data)?;
+        written.insert(self.path, self.generation);
+        Ok(())
+    }
+}
+
+/// 量产会话持有的序列号池；变更先在内存中生效，由返回的 SerialPoolWrite 写盘。
+/// 预留必须写盘成功后才能使用，其余变更未写盘时崩溃，序列号在下次打开时作废
+pub struct SerialPool {
+    name: String,
+    path: PathBuf,
+    data: SerialPoolData,
+}
+
+impl SerialPool {
+    /// 打开已有的池；上次异常退出时仍处于预留状态的序列号一律作废并返回
+    pub fn open(dir: &Path, name: &str) -> Result<(Self, Vec<String>), String> {
+        let path = pool_file_path(dir, name)?;
+        if !path.exists() {
+            return Err(format!("序列号池 {} 不存在，请先导入", name));
+        }
+        let mut pool = Self {
+            name: name.to_string(),
+            data: read_pool_file(&path)?,
+            path,
+        };
+
+        let (stale, write) = pool.update(|data| {
+            let stale: Vec<String> = data.reserved.drain(..).map(|r| r.value).collect();
+            data.burned.extend(stale.iter().cloned());
+            Ok(stale)
+        })?;
+        write.persist()?;
+        Ok((pool, stale))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn status(&self) -> SerialPoolStatus {
+        SerialPoolStatus {
+            name: self.name.clone(),
+            remaining: self.data.remaining(),
+            reserved: self.data.reserved.len() as u64,
+            committed: self.data.committed,
+            burned: self.data.burned.len() as u64,
+            released: self.data.released.len() as u64,
+        }
+    }
+
+    fn update<T>(
+        &mut self,
+        change: impl FnOnce(&mut SerialPoolData) -> Result<T, String>,
+    ) -> Result<(T, SerialPoolWrite), String> {
+        let mut next = self.data.clone();
+        let output = change(&mut next)?;
+        self.data = next.clone();
+        let write = SerialPoolWrite {
+            path: self.path.clone(),
+            data: next,
+            generation: NEXT_GENERATION.fetch_add(1, Ordering::Relaxed),
+        };
+        Ok((output, write))
+    }
+
+    /// 依次从归还的序列号、导入列表、号段中取出一个值并记录为预留
+    pub fn reserve(&mut self, owner: &str, now: u64) -> Result<(String, SerialPoolWrite), String> {
+        let name = self.name.clone();
+        self.update(|data| {
+            let value = if !data.released.is_empty() {
+                data.released.remove(0)
+            } else if data.values_issued < data.values.len() {
+                data.values_issued += 1;
+                data.values[data.values_issued - 1].clone()
+            } else if let Some(cursor) = data
+                .ranges
+                .iter_mut()
+                .find(|cursor| cursor.issued < cursor.range.size())
+            {
+                cursor.issued += 1;
+                cursor
+                    .range
+                    .format_value(cursor.range.start + cursor.issued - 1)
+            } else {
+                return Err(format!("序列号池 {} 已耗尽", name));
+            };
+
+            data.reserved.push(SerialReservation {
+                value: value.clone(),
+                owner: owner.to_string(),
+                reserved_at: now,
+            });
+            Ok(value)
+        })
+    }
+
+    fn take_reservation(data: &mut SerialPoolData, value: &str) -> Result<(), String> {
+        let index = data
+            .reserved
+            .iter()
+            .position(|reservation| reservation.value == value)
+            .ok_or_else(|| format!("序列号 {} 不在预留列表中", value))?;
+        data.reserved.remove(index);
+        Ok(())
+    }
+
+    /// 设备写入成功，序列号正式占用
+    pub fn commit(&mut self, value: &str) -> Result<SerialPoolWrite, String> {
+        self.update(|data| {
+            Self::take_reservation(data, value)?;
+            data.committed += 1;
+            Ok(())
+        })
+        .map(|((), write)| write)
+    }
+
+    pub fn release(&mut self, value: &str) -> Result<SerialPoolWrite, String> {
+        self.update(|data| {
+            Self::take_reservation(data, value)?;
+            data.released.push(value.to_string());
+            Ok(())
+        })
+        .map(|((), write)| write)
+    }
+
+    pub fn burn(&mut self, value: &str) -> Result<SerialPoolWrite, String> {
+        self.update(|data| {
+            Self::take_reservation(data, value)?;
+            data.burned.push(value.to_string());
+            Ok(())
+        })
+        .map(|((), write)| write)
+    }
+}
+
+fn read_list_file(path: &str) -> Result<Vec<String>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("读取序列号列表 {} 失败: {}", path, e))?;
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// 新建序列号池或向已有的池追加；与池中已有的任何值重复都会拒绝整个导入
+pub fn import_serial_pool(
+    dir: &Path,
+    request: &SerialPoolImportRequest,
+) -> Result<SerialPoolStatus, String> {
+    let path = pool_file_path(dir, &request.name)?;
+
+    let mut values: Vec<String> = request
+        .values
+        .iter()
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .collect();
+    if let Some(list_path) = request
+        .list_path
+        .as_deref()
+        .filter(|p| !p.trim().is_empty())
+    {
+        values.extend(read_list_file(list_path)?);
+    }
+    if values.is_empty() && request.ranges.is_empty() {
+        return Err("没有要导入的号段或序列号".to_string());
+    }
+
+    // 持有写锁完成读取和写入，避免与会话中尚未写盘的变更交错
+    let mut written = POOL_FILES.lock().unwrap();
+    let mut data = if path.exists() {
+        read_pool_file(&path)?
+    } else {
+        SerialPoolData {
+            version: SERIAL_POOL_FILE_VERSION,
+            ..Default::default()
+        }
+    };
+
+    for range in &request.ranges {
+        range.validate()?;
+        if let Some(conflict) = data
+            .ranges
+            .iter()
+            .find_map(|cursor| range.conflicts_with(&cursor.range))
+        {
+            return Err(conflict);
+        }
+        if let Some(value) = data.values.iter().find(|value| range.contains(value)) {
+            return Err(format!("号段与池中已有的序列号 {} 重复", value));
+        }
+        data.ranges.push(RangeCursor {
+            range: range.clone(),
+            issued: 0,
+        });
+    }
+
+    let mut seen = HashSet::new();
+    for value in &values {
+        if !seen.insert(value.as_str()) || data.contains(value) {
+            return Err(format!("序列号 {} 重复", value));
+        }
+    }
+    data.values.extend(values);
+
+    fs::create_dir_all(dir).map_err(|e| format!("创建序列号池目录失败: {}", e))?;
+    write_pool_file(&path, &data)?;
+    written.insert(
+        path.clone(),
+        NEXT_GENERATION.fetch_add(1, Ordering::Relaxed),
+    );
+    drop(written);
+
+    Ok(SerialPool {
+        name: request.name.clone(),
+        path,
+        data,
+    }
+    .status())
+}
+
+pub fn load_serial_pool_status(dir: &Path, name: &str) -> Result<SerialPoolStatus, String> {
+    let path = pool_file_path(dir, name)?;
+    if !path.exists() {
+        return Err(format!("序列号池 {} 不存在，请先导入", name));
+    }
+    Ok(SerialPool {
+        name: name.to_string(),
+        data: read_pool_file(&path)?,
+        path,
+    }
+    .status())
+}
+
+pub fn list_serial_pools(dir: &Path) -> Result<Vec<SerialPoolStatus>, String> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let entries = fs::read_dir(dir).map_err(|e| format!("读取序列号池目录失败: {}", e))?;
+
+    let mut pools = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+            pools.push(load_serial_pool_status(dir, name)?);
+        }
+    }
+    pools.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(pools)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{import_serial_pool, load_serial_pool_status, SerialPool};
+    use crate::types::{SerialPoolImportRequest, SerialRange};
+
+    fn request(ranges: Vec<SerialRange>, values: &[&str]) -> SerialPoolImportRequest {
+        SerialPoolImportRequest {
+            name: "line1".to_string(),
+            ranges,
+            values: values.iter().map(|value| value.to_string()).collect(),
+            list_path: None,
+        }
+    }
+
+    fn reserve(pool: &mut SerialPool, owner: &str) -> String {
+        let (value, write) = pool.reserve(owner, 0).unwrap();
+        write.persist().unwrap();
+        value
+    }
+
+    fn range(prefix: &str, width: u32, start: u64, end: u64) -> SerialRange {
+        SerialRange {
+            prefix: prefix.to_string(),
+            width,
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn hands_out_released_then_list_then_range_values() {
+        let dir = tempfile::tempdir().unwrap();
+        import_serial_pool(
+            dir.path(),
+            &request(vec![range("SN", 4, 1, 2)], &["LIST-A"]),
+        )
+        .unwrap();
+
+        let (mut pool, stale) = SerialPool::open(dir.path(), "line1").unwrap();
+        assert!(stale.is_empty());
+        assert_eq!(pool.status().remaining, 3);
+
+        let first = reserve(&mut pool, "COM3");
+        assert_eq!(first, "LIST-A");
+        pool.release(&first).unwrap().persist().unwrap();
+        assert_eq!(reserve(&mut pool, "COM4"), "LIST-A");
+        assert_eq!(reserve(&mut pool, "COM5"), "SN0001");
+        pool.commit("LIST-A").unwrap().persist().unwrap();
+        pool.burn("SN0001").unwrap().persist().unwrap();
+        assert_eq!(reserve(&mut pool, "COM3"), "SN0002");
+        assert!(pool.reserve("COM4", 0).is_err());
+
+        let status = load_serial_pool_status(dir.path(), "line1").unwrap();
+        assert_eq!(status.remaining, 0);
+        assert_eq!(status.reserved, 1);
+        assert_eq!(status.committed, 1);
+        assert_eq!(status.burned, 1);
+    }
+
+    #[test]
+    fn burns_reservations_left_by_a_crash() {
+        let dir = tempfile::tempdir().unwrap();
+        import_serial_pool(dir.path(), &request(vec![range("", 0, 100, 199)], &[])).unwrap();
+
+        let (mut pool, _) = SerialPool::open(dir.path(), "line1").unwrap();
+        assert_eq!(reserve(&mut pool, "COM3"), "100");
+        drop(pool);
+
+        let (mut pool, stale) = SerialPool::open(dir.path(), "line1").unwrap();
+        assert_eq!(stale, vec!["100".to_string()]);
+        assert_eq!(reserve(&mut pool, "COM3"), "101");
+        assert_eq!(pool.status().burned, 1);
+    }
+
+    #[test]
+    fn older_write_does_not_overwrite_newer_state() {
+        let dir = tempfile::tempdir().unwrap();
+        import_serial_pool(dir.path(), &request(vec![range("", 0, 100, 199)], &[])).unwrap();
+
+        let (mut pool, _) = SerialPool::open(dir.path(), "line1").unwrap();
+        let first = reserve(&mut pool, "COM3");
+        let second = reserve(&mut pool, "COM4");
+        // 两个 worker 释放状态锁后的写盘顺序与变更顺序相反
+        let commit = pool.commit(&first).unwrap();
+        pool.burn(&second).unwrap().persist().unwrap();
+        commit.persist().unwrap();
+
+        let status = load_serial_pool_status(dir.path(), "line1").unwrap();
+        assert_eq!(status.reserved, 0);
+        assert_eq!(status.committed, 1);
+        assert_eq!(status.burned, 1);
+    }
+
+    #[test]
+    fn rejects_duplicate_imports() {
+        let dir = tempfile::tempdir().unwrap();
+        import_serial_pool(dir.path(), &request(vec![range("SN", 4, 1, 100)], &[])).unwrap();
+
+        assert!(
+            import_serial_pool(dir.path(), &request(vec![range("SN", 4, 50, 150)], &[])).is_err()
+        );
+        assert!(import_serial_pool(dir.path(), &request(vec![range("S", 5, 1, 9)], &[])).is_err());
+        assert!(import_serial_pool(dir.path(), &request(vec![], &["SN0042"])).is_err());
+        assert!(import_serial_pool(dir.path(), &request(vec![], &["X1", "X1"])).is_err());
+        assert!(
+            import_serial_pool(dir.path(), &request(vec![range("SN", 2, 1, 100)], &[])).is_err()
+        );
+
+        let status = import_serial_pool(
+            dir.path(),
+            &request(vec![range("SN", 4, 101, 200)], &["X1"]),
+        )
+        .unwrap();
+        assert_eq!(status.remaining, 201);
+    }
+}
@@ -58,6 +58,29 @@ pub fn list_serial_ports() -> Result<Vec<PortInfo>, String> {
 }
 
 pub fn spawn_serial_hotplug_watcher<R: Runtime>(app_handle: AppHandle<R>) {
+    spawn_serial_port_watcher(move |previous_ports, ports| {
+        clear_disconnected_device_state(&app_handle, ports);
+        let connected_identities = extract_connected_identities(previous_ports, ports);
+
+        app_handle
+            .emit(
+                SERIAL_PORTS_CHANGED_EVENT,
+                SerialPortsChangedEvent {
+                    ports: ports.to_vec(),
+                },
+            )
+            .map_err(|e| format!("发送串口列表变化事件失败: {e}"))?;
+
+        mass_production_handle_hotplug_event(&app_handle, connected_identities);
+        Ok(())
+    });
+}
+
+/// 监听 USB 热插拔事件，串口列表稳定且发生变化后以 (变化前, 变化后) 的列表调用 on_change
+pub fn spawn_serial_port_watcher<F>(mut on_change: F)
+where
+    F: FnMut(&[PortInfo], &[PortInfo]) -> Result<(), String> + Send + 'static,
+{
     thread::spawn(move || {
         let Ok(mut watch) = nusb::watch_devices() else {
             eprintln!("Failed to start USB hotplug watcher");
@@ -75,7 +98,8 @@ pub fn spawn_serial_hotplug_watcher<R: Runtime>(app_handle: AppHandle<R>) {
         while let Some(event) = future::block_on(watch.next()) {
             match event {
                 HotplugEvent::Connected(_) | HotplugEvent::Disconnected(_) => {
-                    if let Err(error) = refresh_ports_after_hotplug(&app_handle, &mut last_ports) {
+                    if let Err(error) = refresh_ports_after_hotplug(&mut last_ports, &mut on_change)
+                    {
                         eprintln!("Failed to refresh serial ports after hotplug: {error}");
                     }
                 }
@@ -84,34 +108,24 @@ pub fn spawn_serial_hotplug_watcher<R: Runtime>(app_handle: AppHandle<R>) {
     });
 }
 
-fn refresh_ports_after_hotplug<R: Runtime>(
-    app_handle: &AppHandle<R>,
+fn refresh_ports_after_hotplug<F>(
     last_ports: &mut Vec<PortInfo>,
-) -> Result<(), String> {
+    on_change: &mut F,
+) -> Result<(), String>
+where
+    F: FnMut(&[PortInfo], &[PortInfo]) -> Result<(), String>,
+{
     let ports = wait_for_settled_ports(last_ports, list_serial_ports)?;
     if *last_ports == ports {
         return Ok(());
     }
 
-    clear_disconnected_device_state(app_handle, &ports);
-    let connected_identities = extract_connected_identities(last_ports, &ports);
-
-    app_handle
-        .emit(
-            SERIAL_PORTS_CHANGED_EVENT,
-            SerialPortsChangedEvent {
-                ports: ports.clone(),
-            },
-        )
-        .map_err(|e| format!("发送串口列表变化事件失败: {e}"))?;
-
-    mass_production_handle_hotplug_event(app_handle, connected_identities);
-
+    let result = on_change(last_ports, &ports);
     *last_ports = ports;
-    Ok(())
+    result
 }
 
-fn extract_connected_identities(
+pub fn extract_connected_identities(
     previous_ports: &[PortInfo],
     current_ports: &[PortInfo],
 ) -> Vec<PortIdentity> {
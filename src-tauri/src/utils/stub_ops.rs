@@ -1,4 +1,4 @@
-use crate::types::StubConfigSpec;
+use crate::types::{FlashMediaSpec, StubConfigSpec};
 use sftool_lib::ChipType;
 use std::io::Write;
 use tempfile;
@@ -13,6 +13,36 @@ pub fn load_stub_config_spec(path: &str) -> Result<StubConfigSpec, String> {
     Ok(spec)
 }
 
+/// 读取 Stub 配置中所选存储器的 Flash 容量，未配置时返回 None
+///
+/// 同一介质配置了多颗 Flash 时取最大容量，写入地址以此为上限
+pub fn configured_flash_size(
+    stub_config_path: &str,
+    memory_type: &str,
+) -> Result<Option<u64>, String> {
+    if stub_config_path.trim().is_empty() {
+        return Ok(None);
+    }
+    let is_nand = match memory_type.trim().to_lowercase().as_str() {
+        "nor" => false,
+        "nand" => true,
+        _ => return Ok(None),
+    };
+
+    let spec = load_stub_config_spec(stub_config_path)?;
+    let mut size = None;
+    for flash in &spec.flash {
+        if matches!(flash.media, FlashMediaSpec::Nand) != is_nand {
+            continue;
+        }
+        let capacity = u64::from(flash.capacity()?);
+        if capacity > 0 {
+            size = Some(size.map_or(capacity, |size: u64| size.max(capacity)));
+        }
+    }
+    Ok(size)
+}
+
 pub fn chip_key(chip_type: &ChipType) -> &'static str {
     // 根据芯片类型返回对应的字符串标识
     match chip_type {
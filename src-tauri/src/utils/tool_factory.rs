@@ -1,4 +1,4 @@
-use crate::types::DeviceConfig;
+use crate::types::{DeviceConfig, FlashError, FlashErrorCategory};
 use crate::utils::stub_ops::prepare_stub_path;
 use serialport::{ErrorKind as SerialPortErrorKind, SerialPort};
 use sftool_lib::{
@@ -87,12 +87,19 @@ fn parse_chip_type(chip_type: &str) -> Result<ChipType, String> {
     }
 }
 
-fn wait_for_serial_port(port_name: &str, baud_rate: u32) -> Result<(), String> {
+fn wait_for_serial_port(port_name: &str, baud_rate: u32) -> Result<(), FlashError> {
     serialport::new(port_name, baud_rate)
         .timeout(Duration::from_millis(200))
         .open()
         .map(|_| ())
-        .map_err(|error| format_serial_open_error(port_name, &error))
+        .map_err(|error| {
+            // 端口已从系统中消失视为设备断开，其余都是打开失败
+            let category = match error.kind() {
+                SerialPortErrorKind::Io(IoErrorKind::NotFound) => FlashErrorCategory::Disconnect,
+                _ => FlashErrorCategory::PortOpen,
+            };
+            FlashError::new(category, format_serial_open_error(port_name, &error))
+        })
 }
 
 fn format_serial_open_error(port_name: &str, error: &serialport::Error) -> String {
@@ -141,54 +148,20 @@ fn format_serial_open_error(port_name: &str, error: &serialport::Error) -> Strin
 fn create_sifli_tool_checked(
     chip_type: ChipType,
     base: SifliToolBase,
-) -> Result<Box<dyn SifliTool>, String> {
+) -> Result<Box<dyn SifliTool>, FlashError> {
     catch_unwind(AssertUnwindSafe(|| create_sifli_tool(chip_type, base)))
         .map_err(format_tool_creation_panic)
 }
 
-fn format_tool_creation_panic(payload: Box<dyn Any + Send>) -> String {
-    let detail = panic_payload_to_string(&payload);
-    let detail_lower = detail.to_lowercase();
-
-    if detail_lower.contains("failed to connect to the chip")
-        || detail_lower.contains("timeout while")
-        || detail_lower.contains("receive timeout")
-        || detail_lower.contains("timed out")
-    {
-        return format!(
-            "连接超时：串口已打开，但芯片未响应。请确认开发板已上电、BOOT/复位操作正确，且芯片型号和接口选择匹配。原始错误: {}",
-            detail
-        );
-    }
-
-    if detail_lower.contains("failed to download stub") {
-        return format!(
-            "Stub 下载失败：串口已打开，但初始化程序下载失败。请检查存储器类型、Stub 配置/外部 Stub 文件和复位方式。原始错误: {}",
-            detail
-        );
-    }
-
-    if detail_lower.contains("access is denied")
-        || detail_lower.contains("permission denied")
-        || detail_lower.contains("拒绝访问")
-    {
-        return format!(
-            "串口被占用或权限不足，请关闭其它串口工具后重试。原始错误: {}",
-            detail
-        );
-    }
-
-    if detail_lower.contains("cannot find")
-        || detail_lower.contains("not found")
-        || detail_lower.contains("no such file")
-    {
-        return format!(
-            "串口不存在或已断开，请刷新串口列表后重试。原始错误: {}",
-            detail
-        );
-    }
-
-    format!("连接失败：工具初始化异常。原始错误: {}", detail)
+/// sftool-lib 通过 panic 报告连接失败；此处无法得知失败阶段，由调用方按进度阶段归类
+fn format_tool_creation_panic(payload: Box<dyn Any + Send>) -> FlashError {
+    FlashError::new(
+        FlashErrorCategory::Other,
+        format!(
+            "连接失败：工具初始化异常。原始错误: {}",
+            panic_payload_to_string(&payload)
+        ),
+    )
 }
 
 fn panic_payload_to_string(payload: &(dyn Any + Send)) -> String {
@@ -201,12 +174,14 @@ fn panic_payload_to_string(payload: &(dyn Any + Send)) -> String {
     }
 }
 
+/// 只处理本地参数和 Stub 文件，出错时归为配置错误
 fn build_tool_base_with_progress(
     config: &DeviceConfig,
     progress_callback: ProgressSinkArc,
     cancel_token: CancelToken,
-) -> Result<(SifliToolBase, Option<tempfile::NamedTempFile>), String> {
-    let chip_type = parse_chip_type(&config.chip_type)?;
+) -> Result<(SifliToolBase, Option<tempfile::NamedTempFile>), FlashError> {
+    let chip_type = parse_chip_type(&config.chip_type)
+        .map_err(|e| FlashError::new(FlashErrorCategory::Config, e))?;
 
     let before_enum = match config.before_operation.as_str() {
         "default_reset" => BeforeOperation::DefaultReset,
@@ -229,7 +204,12 @@ fn build_tool_base_with_progress(
             None
         },
     )
-    .map_err(|e| format!("准备存根文件失败: {}", e))?;
+    .map_err(|e| {
+        FlashError::new(
+            FlashErrorCategory::Config,
+            format!("准备存根文件失败: {}", e),
+        )
+    })?;
 
     let base = SifliToolBase::new_with_external_stub_and_cancel(
         config.port_name.clone(),
@@ -279,9 +259,10 @@ pub fn create_tool_instance_with_progress(
     config: &DeviceConfig,
     progress_callback: ProgressSinkArc,
     cancel_token: CancelToken,
-) -> Result<Box<dyn SifliTool>, String> {
+) -> Result<Box<dyn SifliTool>, FlashError> {
     // 解析芯片类型
-    let chip_type = parse_chip_type(&config.chip_type)?;
+    let chip_type = parse_chip_type(&config.chip_type)
+        .map_err(|e| FlashError::new(FlashErrorCategory::Config, e))?;
     let (base, temp_stub_file) =
         build_tool_base_with_progress(config, progress_callback.clone(), cancel_token)?;
 
@@ -292,13 +273,86 @@ pub fn create_tool_instance_with_progress(
     Ok(Box::new(ToolWithStubOwner::new(tool, temp_stub_file)))
 }
 
+/// 端口仍在系统串口列表中；无法枚举串口时按仍存在处理
+fn port_is_present(port_name: &str) -> bool {
+    port_name.is_empty()
+        || serialport::available_ports()
+            .map(|ports| ports.iter().any(|port| port.port_name == port_name))
+            .unwrap_or(true)
+}
+
+fn refined_category(
+    category: FlashErrorCategory,
+    cancelled: bool,
+    port_present: bool,
+) -> FlashErrorCategory {
+    match category {
+        _ if cancelled => FlashErrorCategory::Cancelled,
+        // 本地参数错误和打开失败发生在操作设备之前，与断开无关
+        FlashErrorCategory::Config | FlashErrorCategory::PortOpen => category,
+        _ if !port_present => FlashErrorCategory::Disconnect,
+        _ => category,
+    }
+}
+
+/// 写入过程的错误；sftool-lib 在写入流程中也会擦除和校验，
+/// stage 取自出错时进度回调报告的操作，没有进度事件时按写入阶段处理
+pub fn classify_write_error(stage: Option<FlashErrorCategory>, message: String) -> FlashError {
+    FlashError::new(stage.unwrap_or(FlashErrorCategory::Write), message)
+}
+
+/// 按失败时的状态细分，不解析错误文字：取消令牌已触发为取消，串口已从系统中消失为断开
+pub fn refine_flash_error(error: FlashError, cancelled: bool, port_name: &str) -> FlashError {
+    let port_present = cancelled || port_is_present(port_name);
+    FlashError {
+        category: refined_category(error.category, cancelled, port_present),
+        ..error
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::parse_chip_type;
+    use super::{classify_write_error, parse_chip_type, refine_flash_error, refined_category};
+    use crate::types::{FlashError, FlashErrorCategory};
     use sftool_lib::ChipType;
 
     #[test]
     fn parses_sf32lb57() {
         assert_eq!(parse_chip_type("SF32LB57"), Ok(ChipType::SF32LB57));
     }
+
+    #[test]
+    fn classifies_flash_errors() {
+        let category = |stage: Option<FlashErrorCategory>, message: &str| {
+            classify_write_error(stage, message.to_string()).category
+        };
+        assert_eq!(
+            category(None, "写入 Flash 失败: timeout"),
+            FlashErrorCategory::Write
+        );
+        // 阶段来自进度事件，与错误文字无关
+        assert_eq!(
+            category(
+                Some(FlashErrorCategory::VerifyMismatch),
+                "写入 Flash 失败: Operation cancelled"
+            ),
+            FlashErrorCategory::VerifyMismatch
+        );
+    }
+
+    #[test]
+    fn refines_by_cancel_token_and_port_presence() {
+        use FlashErrorCategory::*;
+        assert_eq!(refined_category(Erase, true, true), Cancelled);
+        assert_eq!(refined_category(Other, true, false), Cancelled);
+        assert_eq!(refined_category(Write, false, false), Disconnect);
+        assert_eq!(refined_category(PortOpen, false, false), PortOpen);
+        assert_eq!(refined_category(Config, false, false), Config);
+        assert_eq!(refined_category(Write, false, true), Write);
+        assert_eq!(refined_category(Other, false, true), Other);
+
+        // 错误文字中的 cancelled 不再影响归类
+        let error = FlashError::new(Write, "写入 Flash 失败: transfer cancelled by chip");
+        assert_eq!(refine_flash_error(error, false, "").category, Write);
+    }
 }
@@ -0,0 +1,333 @@
+use crate::types::{
+    MassProductionMode, TraceExportFormat, TraceQuery, TraceQueryResult, TraceRecord, TraceResult,
+};
+use chrono::{Local, TimeZone};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+pub const TRACE_DIRNAME: &str = "traceability";
+const TRACE_RECORDS_FILENAME: &str = "records.jsonl";
+
+/// 追加记录的写锁，并发 worker 的记录逐行写入，补换行时也不会与其它写入交错
+static TRACE_FILE: Mutex<()> = Mutex::new(());
+
+pub fn trace_records_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(TRACE_DIRNAME).join(TRACE_RECORDS_FILENAME)
+}
+
+/// 文件非空且最后一个字节不是换行，说明上次写入中途中断
+fn missing_trailing_newline(file: &mut File) -> std::io::Result<bool> {
+    if file.metadata()?.len() == 0 {
+        return Ok(false);
+    }
+    let mut last = [0u8; 1];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last)?;
+    Ok(last[0] != b'\n')
+}
+
+/// 以 JSON Lines 追加一条记录并落盘；只追加，不改写已有内容
+///
+/// 若上次写入中断留下了没有换行的残片，先补一个换行，避免新记录与残片拼成一行
+pub fn append_trace_record(path: &Path, record: &TraceRecord) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建追溯记录目录失败: {}", e))?;
+    }
+    let mut line =
+        serde_json::to_string(record).map_err(|e| format!("序列化追溯记录失败: {}", e))?;
+    line.push('\n');
+
+    let _guard = TRACE_FILE.lock().unwrap();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("打开追溯记录文件失败: {}", e))?;
+    if missing_trailing_newline(&mut file).map_err(|e| format!("读取追溯记录失败: {}", e))?
+    {
+        line.insert(0, '\n');
+    }
+    file.write_all(line.as_bytes())
+        .and_then(|()| file.sync_data())
+        .map_err(|e| format!("写入追溯记录失败: {}", e))
+}
+
+/// 解析记录文件；末尾没有换行的一行视为写入中途崩溃留下的残片并忽略，
+/// 其它格式错误的行跳过并记录行号
+pub fn parse_trace_records(content: &str) -> TraceQueryResult {
+    let complete = match content.rfind('\n') {
+        Some(index) => &content[..=index],
+        None => "",
+    };
+
+    let mut result = TraceQueryResult::default();
+    for (index, line) in complete.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(record) => result.records.push(record),
+            Err(e) => {
+                tracing::warn!("追溯记录第 {} 行格式错误，已跳过: {}", index + 1, e);
+                result.skipped_lines.push(index + 1);
+            }
+        }
+    }
+    result
+}
+
+fn matches_query(record: &TraceRecord, query: &TraceQuery) -> bool {
+    let device_matches = query
+        .device
+        .as_deref()
+        .map(str::trim)
+        .filter(|device| !device.is_empty())
+        .map(|device| {
+            record.port_name.eq_ignore_ascii_case(device)
+                || record.usb_serial.as_deref() == Some(device)
+                || record.serial.as_deref() == Some(device)
+        })
+        .unwrap_or(true);
+
+    device_matches
+        && query
+            .session_key
+            .as_deref()
+            .map(|session_key| record.session_key == session_key)
+            .unwrap_or(true)
+        && query
+            .result
+            .map(|result| record.result == result)
+            .unwrap_or(true)
+        && query
+            .since
+            .map(|since| record.finished_at >= since)
+            .unwrap_or(true)
+        && query
+            .until
+            .map(|until| record.finished_at < until)
+            .unwrap_or(true)
+}
+
+/// 按条件筛选记录，结果按完成顺序排列；格式错误的行不会导致整个查询失败
+pub fn query_trace_records(path: &Path, query: &TraceQuery) -> Result<TraceQueryResult, String> {
+    if !path.exists() {
+        return Ok(TraceQueryResult::default());
+    }
+    let content = fs::read_to_string(path).map_err(|e| format!("读取追溯记录失败: {}", e))?;
+
+    let mut result = parse_trace_records(&content);
+    result.records.retain(|record| matches_query(record, query));
+    if let Some(limit) = query.limit {
+        let skip = result.records.len().saturating_sub(limit);
+        result.records.drain(..skip);
+    }
+    Ok(result)
+}
+
+fn format_trace_timestamp(timestamp_millis: u64) -> String {
+    let timestamp = i64::try_from(timestamp_millis).unwrap_or(i64::MAX);
+    match Local.timestamp_millis_opt(timestamp).single() {
+        Some(datetime) => datetime.format("%Y-%m-%d %H:%M:%S%.3f %:z").to_string(),
+        None => timestamp_millis.to_string(),
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn result_label(result: TraceResult) -> &'static str {
+    match result {
+        TraceResult::Success => "success",
+        TraceResult::Failed => "failed",
+        TraceResult::Cancelled => "cancelled",
+    }
+}
+
+const CSV_HEADER: &str = "session_key,session_id,port_name,attempt,vid,pid,usb_serial,location_path,chip_model,memory_type,mode,serial,firmware,started_at,finished_at,result,error_category,error";
+
+/// 每条记录一行；固件列为 "文件@0x地址:sha256"，多个文件以分号分隔
+pub fn trace_records_to_csv(records: &[TraceRecord]) -> String {
+    let mut output = String::from(CSV_HEADER);
+    output.push('\n');
+
+    for record in records {
+        let firmware = record
+            .firmware
+            .iter()
+            .map(|file| format!("{}@0x{:08X}:{}", file.file_path, file.address, file.sha256))
+            .collect::<Vec<_>>()
+            .join(";");
+        let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+        let fields = [
+            record.session_key.clone(),
+            record.session_id.to_string(),
+            record.port_name.clone(),
+            record.attempt.to_string(),
+            optional(&record.vid),
+            optional(&record.pid),
+            optional(&record.usb_serial),
+            optional(&record.location_path),
+            record.chip_model.clone(),
+            record.memory_type.clone(),
+            match record.mode {
+                MassProductionMode::Write => "write",
+                MassProductionMode::Verify => "verify",
+            }
+            .to_string(),
+            optional(&record.serial),
+            firmware,
+            format_trace_timestamp(record.started_at),
+            format_trace_timestamp(record.finished_at),
+            result_label(record.result).to_string(),
+            record
+                .error_category
+                .map(|category| category.as_str().to_string())
+                .unwrap_or_default(),
+            optional(&record.error),
+        ];
+        output.push_str(
+            &fields
+                .iter()
+                .map(|field| csv_field(field))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        output.push('\n');
+    }
+    output
+}
+
+/// 导出筛选后的记录，返回导出的条数
+pub fn export_trace_records(
+    path: &Path,
+    query: &TraceQuery,
+    format: TraceExportFormat,
+    output_path: &str,
+) -> Result<usize, String> {
+    let records = query_trace_records(path, query)?.records;
+    let content = match format {
+        TraceExportFormat::Csv => trace_records_to_csv(&records),
+        TraceExportFormat::Json => serde_json::to_string_pretty(&records)
+            .map_err(|e| format!("序列化追溯记录失败: {}", e))?,
+    };
+    fs::write(output_path, content)
+        .map_err(|e| format!("导出追溯记录到 {} 失败: {}", output_path, e))?;
+    Ok(records.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        append_trace_record, parse_trace_records, query_trace_records, trace_records_to_csv,
+    };
+    use crate::types::{
+        FlashErrorCategory, MassProductionMode, TraceFirmwareFile, TraceQuery, TraceRecord,
+        TraceResult,
+    };
+
+    fn record(session_id: u64, port_name: &str, result: TraceResult) -> TraceRecord {
+        TraceRecord {
+            session_id,
+            session_key: format!("1700000000000-{session_id:08x}"),
+            port_name: port_name.to_string(),
+            attempt: 1,
+            vid: Some("1A86".to_string()),
+            pid: Some("55D3".to_string()),
+            usb_serial: None,
+            location_path: None,
+            chip_model: "SF32LB52".to_string(),
+            memory_type: "nor".to_string(),
+            mode: MassProductionMode::Write,
+            firmware: vec![TraceFirmwareFile {
+                file_path: "app.bin".to_string(),
+                address: 0x1202_0000,
+                sha256: "ab".repeat(32),
+            }],
+            serial: Some("SN0001".to_string()),
+            started_at: 1_000,
+            finished_at: 2_000,
+            result,
+            error_category: (result != TraceResult::Success).then_some(FlashErrorCategory::Sync),
+            error: (result != TraceResult::Success)
+                .then(|| "写入 Flash 失败: \"timeout\", retry".to_string()),
+        }
+    }
+
+    #[test]
+    fn appends_and_filters_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("traceability").join("records.jsonl");
+        append_trace_record(&path, &record(1, "COM3", TraceResult::Success)).unwrap();
+        append_trace_record(&path, &record(1, "COM4", TraceResult::Failed)).unwrap();
+        append_trace_record(&path, &record(2, "COM3", TraceResult::Success)).unwrap();
+
+        let all = query_trace_records(&path, &TraceQuery::default()).unwrap();
+        assert_eq!(all.records.len(), 3);
+
+        let query = TraceQuery {
+            device: Some("com3".to_string()),
+            result: Some(TraceResult::Success),
+            limit: Some(1),
+            ..Default::default()
+        };
+        let latest = query_trace_records(&path, &query).unwrap().records;
+        assert_eq!(latest.len(), 1);
+        assert_eq!(latest[0].session_id, 2);
+
+        let failed = TraceQuery {
+            session_key: Some("1700000000000-00000001".to_string()),
+            result: Some(TraceResult::Failed),
+            ..Default::default()
+        };
+        assert_eq!(
+            query_trace_records(&path, &failed).unwrap().records[0].port_name,
+            "COM4"
+        );
+    }
+
+    #[test]
+    fn ignores_truncated_last_line() {
+        let line = serde_json::to_string(&record(1, "COM3", TraceResult::Success)).unwrap();
+        let content = format!("{line}\n{{\"session_id\":2,\"port");
+        let parsed = parse_trace_records(&content);
+        assert_eq!(parsed.records.len(), 1);
+        assert!(parsed.skipped_lines.is_empty());
+
+        let parsed = parse_trace_records(&format!("{{\n{line}\n"));
+        assert_eq!(parsed.records.len(), 1);
+        assert_eq!(parsed.skipped_lines, vec![1]);
+    }
+
+    #[test]
+    fn append_repairs_missing_trailing_newline() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("records.jsonl");
+        std::fs::write(&path, "{\"session_id\":1,\"port").unwrap();
+
+        append_trace_record(&path, &record(2, "COM3", TraceResult::Success)).unwrap();
+
+        let result = query_trace_records(&path, &TraceQuery::default()).unwrap();
+        assert_eq!(result.records.len(), 1);
+        assert_eq!(result.records[0].session_id, 2);
+        assert_eq!(result.skipped_lines, vec![1]);
+    }
+
+    #[test]
+    fn quotes_csv_fields() {
+        let csv = trace_records_to_csv(&[record(1, "COM4", TraceResult::Failed)]);
+        let row = csv.lines().nth(1).unwrap();
+        assert!(
+            row.starts_with("1700000000000-00000001,1,COM4,1,1A86,55D3,,,SF32LB52,nor,write,SN0001,app.bin@0x12020000:")
+        );
+        assert!(row.ends_with(",failed,sync,\"写入 Flash 失败: \"\"timeout\"\", retry\""));
+    }
+}
@@ -0,0 +1,553 @@
+use crate::types::{
+    FileAddressRange, MassProductionPortInfo, UnitDataEncoding, UnitDataField, UnitDataPortField,
+    UnitDataSource, UnitDataTemplate,
+};
+use crate::utils::describe_range;
+use sftool_lib::WriteFlashFile;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{Seek, SeekFrom, Write};
+
+/// 出厂数据区一般只有几个扇区，限制大小避免误填成整片地址
+const MAX_UNIT_DATA_SIZE: u32 = 1024 * 1024;
+
+/// 表头 + 数据行；仅支持逗号分隔与双引号转义
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvTable {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    fields.push(current);
+
+    fields.into_iter().map(|f| f.trim().to_string()).collect()
+}
+
+pub fn parse_csv(content: &str) -> Result<CsvTable, String> {
+    let mut lines = content
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .filter(|line| !line.trim().is_empty());
+    let headers = split_csv_line(lines.next().ok_or("CSV 文件为空")?);
+    let rows: Vec<Vec<String>> = lines.map(split_csv_line).collect();
+
+    if let Some((index, row)) = rows
+        .iter()
+        .enumerate()
+        .find(|(_, row)| row.len() != headers.len())
+    {
+        return Err(format!(
+            "CSV 第 {} 条数据有 {} 列，表头为 {} 列",
+            index + 1,
+            row.len(),
+            headers.len()
+        ));
+    }
+
+    Ok(CsvTable { headers, rows })
+}
+
+enum UnitValue {
+    Number(u64),
+    Text(String),
+}
+
+fn parse_u64(text: &str) -> Result<u64, String> {
+    let trimmed = text.trim();
+    match trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => trimmed.parse::<u64>(),
+    }
+    .map_err(|_| format!("无效的数值: {text}"))
+}
+
+fn encode_value(field: &UnitDataField, value: UnitValue) -> Result<Vec<u8>, String> {
+    let size = field.size as usize;
+    match field.encoding {
+        UnitDataEncoding::Ascii => {
+            let text = match value {
+                UnitValue::Number(number) => number.to_string(),
+                UnitValue::Text(text) => text,
+            };
+            if text.len() > size {
+                return Err(format!(
+                    "字段 {} 的值 {} 超过 {} 字节",
+                    field.name, text, size
+                ));
+            }
+            let mut bytes = text.into_bytes();
+            bytes.resize(size, 0);
+            Ok(bytes)
+        }
+        UnitDataEncoding::Hex => {
+            let bytes = match value {
+                UnitValue::Number(number) => {
+                    let full = number.to_be_bytes();
+                    let skip = full.len().saturating_sub(size);
+                    if full[..skip].iter().any(|b| *b != 0) {
+                        return Err(format!(
+                            "字段 {} 的值 {} 超过 {} 字节",
+                            field.name, number, size
+                        ));
+                    }
+                    let mut bytes = vec![0; size.saturating_sub(full.len())];
+                    bytes.extend_from_slice(&full[skip..]);
+                    bytes
+                }
+                UnitValue::Text(text) => {
+                    let digits: String = text
+                        .chars()
+                        .filter(|c| !matches!(c, ':' | '-' | ' '))
+                        .collect();
+                    hex::decode(&digits).map_err(|_| {
+                        format!("字段 {} 的值 {} 不是有效的十六进制", field.name, text)
+                    })?
+                }
+            };
+            if bytes.len() != size {
+                return Err(format!(
+                    "字段 {} 需要 {} 字节，实际为 {} 字节",
+                    field.name,
+                    size,
+                    bytes.len()
+                ));
+            }
+            Ok(bytes)
+        }
+        UnitDataEncoding::UintLe | UnitDataEncoding::UintBe => {
+            let number = match value {
+                UnitValue::Number(number) => number,
+                UnitValue::Text(text) => {
+                    parse_u64(&text).map_err(|e| format!("字段 {}: {}", field.name, e))?
+                }
+            };
+            if size < 8 && number >> (size * 8) != 0 {
+                return Err(format!(
+                    "字段 {} 的值 {} 超过 {} 字节",
+                    field.name, number, size
+                ));
+            }
+            let le = number.to_le_bytes();
+            let mut bytes = le[..size].to_vec();
+            if field.encoding == UnitDataEncoding::UintBe {
+                bytes.reverse();
+            }
+            Ok(bytes)
+        }
+    }
+}
+
+fn port_value(port: &MassProductionPortInfo, field: UnitDataPortField) -> Option<String> {
+    match field {
+        UnitDataPortField::PortName => Some(port.name.clone()),
+        UnitDataPortField::SerialNumber => port.serial_number.clone(),
+        UnitDataPortField::LocationPath => port.location_path.clone(),
+        UnitDataPortField::VidPid => match (&port.vid, &port.pid) {
+            (Some(vid), Some(pid)) => Some(format!("{vid}:{pid}")),
+            _ => None,
+        },
+    }
+}
+
+/// 会话启动时校验模板并载入 CSV，之后由各 worker 按设备序号渲染
+pub struct UnitDataRenderer {
+    template: UnitDataTemplate,
+    csv: Option<CsvTable>,
+}
+
+impl UnitDataRenderer {
+    pub fn new(template: UnitDataTemplate) -> Result<Self, String> {
+        if template.size == 0 || template.size > MAX_UNIT_DATA_SIZE {
+            return Err(format!(
+                "出厂数据区大小应在 1 到 {} 字节之间，当前为 {}",
+                MAX_UNIT_DATA_SIZE, template.size
+            ));
+        }
+        if template.address.checked_add(template.size - 1).is_none() {
+            return Err("出厂数据区超出地址空间".to_string());
+        }
+        if template.fields.is_empty() {
+            return Err("出厂数据模板没有字段".to_string());
+        }
+
+        let mut spans: Vec<(u32, u32, &str)> = Vec::new();
+        for field in &template.fields {
+            let end = field.offset as u64 + field.size as u64;
+            if field.size == 0 || end > template.size as u64 {
+                return Err(format!(
+                    "字段 {} [0x{:X}, 0x{:X}) 超出出厂数据区大小 0x{:X}",
+                    field.name, field.offset, end, template.size
+                ));
+            }
+            if matches!(
+                field.encoding,
+                UnitDataEncoding::UintLe | UnitDataEncoding::UintBe
+            ) && field.size > 8
+            {
+                return Err(format!("整数字段 {} 不能超过 8 字节", field.name));
+            }
+            spans.push((field.offset, end as u32, &field.name));
+        }
+        spans.sort();
+        if let Some(pair) = spans.windows(2).find(|pair| pair[1].0 < pair[0].1) {
+            return Err(format!("字段 {} 与 {} 重叠", pair[0].2, pair[1].2));
+        }
+
+        let csv = match template
+            .csv_path
+            .as_deref()
+            .filter(|p| !p.trim().is_empty())
+        {
+            Some(path) => {
+                let content = fs::read_to_string(path)
+                    .map_err(|e| format!("读取 CSV 文件 {} 失败: {}", path, e))?;
+                Some(parse_csv(&content)?)
+            }
+            None => None,
+        };
+        for field in &template.fields {
+            if let UnitDataSource::CsvColumn { column } = &field.source {
+                let table = csv
+                    .as_ref()
+                    .ok_or_else(|| format!("字段 {} 引用 CSV 列，但未指定 CSV 文件", field.name))?;
+                if !table.headers.iter().any(|header| header == column) {
+                    return Err(format!("CSV 文件中没有列 {}", column));
+                }
+            }
+        }
+
+        Ok(Self { template, csv })
+    }
+
+    pub fn address(&self) -> u32 {
+        self.template.address
+    }
+
+    pub fn range(&self) -> FileAddressRange {
+        FileAddressRange {
+            file_path: "<per-unit data>".to_string(),
+            start: self.template.address as u64,
+            end: self.template.address as u64 + self.template.size as u64,
+        }
+    }
+
+    /// CSV 数据行数；未使用 CSV 时为 None，表示数量不受限
+    pub fn capacity(&self) -> Option<usize> {
+        self.csv.as_ref().map(|table| table.rows.len())
+    }
+
+    fn field_value(
+        &self,
+        field: &UnitDataField,
+        index: u64,
+        port: &MassProductionPortInfo,
+        serial: Option<&str>,
+    ) -> Result<UnitValue, String> {
+        match &field.source {
+            UnitDataSource::Counter { start, step } => step
+                .checked_mul(index)
+                .and_then(|offset| start.checked_add(offset))
+                .map(UnitValue::Number)
+                .ok_or_else(|| format!("字段 {} 的计数器溢出", field.name)),
+            UnitDataSource::CsvColumn { column } => {
+                let table = self.csv.as_ref().ok_or("未载入 CSV 文件")?;
+                let column_index = table
+                    .headers
+                    .iter()
+                    .position(|header| header == column)
+                    .ok_or_else(|| format!("CSV 文件中没有列 {}", column))?;
+                let row = table.rows.get(index as usize).ok_or_else(|| {
+                    format!(
+                        "CSV 数据已用完：共 {} 条，当前为第 {} 台设备",
+                        table.rows.len(),
+                        index + 1
+                    )
+                })?;
+                Ok(UnitValue::Text(row[column_index].clone()))
+            }
+            UnitDataSource::Port { field: port_field } => port_value(port, *port_field)
+                .map(UnitValue::Text)
+                .ok_or_else(|| format!("端口 {} 没有字段 {} 所需的信息", port.name, field.name)),
+            UnitDataSource::Constant { value } => Ok(UnitValue::Text(value.clone())),
+            UnitDataSource::Serial => serial
+                .map(|value| UnitValue::Text(value.to_string()))
+                .ok_or_else(|| format!("字段 {} 引用序列号，但会话未配置序列号池", field.name)),
+        }
+    }
+
+    /// 模板中是否有字段引用序列号池
+    pub fn uses_serial(&self) -> bool {
+        self.template
+            .fields
+            .iter()
+            .any(|field| field.source == UnitDataSource::Serial)
+    }
+
+    /// 渲染第 index 台设备（从 0 开始）的出厂数据
+    pub fn render(
+        &self,
+        index: u64,
+        port: &MassProductionPortInfo,
+        serial: Option<&str>,
+    ) -> Result<Vec<u8>, String> {
+        let mut image = vec![self.template.fill_byte; self.template.size as usize];
+        for field in &self.template.fields {
+            let value = self.field_value(field, index, port, serial)?;
+            let bytes = encode_value(field, value)?;
+            let offset = field.offset as usize;
+            image[offset..offset + bytes.len()].copy_from_slice(&bytes);
+        }
+        Ok(image)
+    }
+
+    /// 出厂数据区不能与固件镜像重叠
+    pub fn check_no_overlap(&self, ranges: &[FileAddressRange]) -> Result<(), String> {
+        let own = self.range();
+        let overlapping: Vec<String> = ranges
+            .iter()
+            .filter(|range| range.start < own.end && own.start < range.end)
+            .map(describe_range)
+            .collect();
+        if overlapping.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "出厂数据区 [0x{:08X}, 0x{:08X}) 与固件重叠: {}",
+                own.start,
+                own.end,
+                overlapping.join(", ")
+            ))
+        }
+    }
+}
+
+/// 会话内的设备序号分配；按策略归还的序号优先复用
+#[derive(Debug, Default)]
+pub struct UnitIndexAllocator {
+    next: u64,
+    capacity: Option<u64>,
+    released: BTreeSet<u64>,
+}
+
+impl UnitIndexAllocator {
+    /// capacity 为 CSV 数据行数，None 表示不受限
+    pub fn new(capacity: Option<usize>) -> Self {
+        Self {
+            capacity: capacity.map(|rows| rows as u64),
+            ..Self::default()
+        }
+    }
+
+    pub fn allocate(&mut self) -> Result<u64, String> {
+        if let Some(index) = self.released.pop_first() {
+            return Ok(index);
+        }
+        if let Some(capacity) = self.capacity.filter(|capacity| self.next >= *capacity) {
+            return Err(format!("CSV 数据已用完：共 {} 条", capacity));
+        }
+        let index = self.next;
+        self.next += 1;
+        Ok(index)
+    }
+
+    pub fn release(&mut self, index: u64) {
+        self.released.insert(index);
+    }
+}
+
+/// 将渲染结果写入匿名临时文件，作为额外的写入项
+pub fn unit_data_write_file(address: u32, image: &[u8]) -> Result<WriteFlashFile, String> {
+    let mut file = tempfile::tempfile().map_err(|e| format!("创建出厂数据临时文件失败: {}", e))?;
+    file.write_all(image)
+        .and_then(|_| file.seek(SeekFrom::Start(0)).map(|_| ()))
+        .map_err(|e| format!("写入出厂数据临时文件失败: {}", e))?;
+    Ok(WriteFlashFile { address, file })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_csv, UnitDataRenderer, UnitIndexAllocator};
+    use crate::types::{
+        MassProductionPortInfo, MassProductionPortStatus, SerialFailurePolicy, UnitDataEncoding,
+        UnitDataField, UnitDataPortField, UnitDataSource, UnitDataTemplate,
+    };
+
+    fn port() -> MassProductionPortInfo {
+        MassProductionPortInfo {
+            id: "COM7".to_string(),
+            name: "COM7".to_string(),
+            port_type: "usb".to_string(),
+            vid: Some("1A86".to_string()),
+            pid: Some("55D3".to_string()),
+            serial_number: None,
+            location_path: None,
+            chip: None,
+            status: MassProductionPortStatus::Flashing,
+            progress: 0,
+            message: None,
+            is_allowed: true,
+            last_seen_at: 0,
+            task_started_at: None,
+            task_finished_at: None,
+            error_category: None,
+            attempt: 0,
+        }
+    }
+
+    fn field(
+        name: &str,
+        offset: u32,
+        size: u32,
+        source: UnitDataSource,
+        encoding: UnitDataEncoding,
+    ) -> UnitDataField {
+        UnitDataField {
+            name: name.to_string(),
+            offset,
+            size,
+            source,
+            encoding,
+        }
+    }
+
+    #[test]
+    fn parses_quoted_csv() {
+        let table = parse_csv("\u{feff}sn,seed\r\n\"A,1\",\"x\"\"y\"\r\n\r\nB2,z\n").unwrap();
+        assert_eq!(table.headers, ["sn", "seed"]);
+        assert_eq!(table.rows, [["A,1", "x\"y"], ["B2", "z"]]);
+        assert!(parse_csv("a,b\n1\n").is_err());
+    }
+
+    #[test]
+    fn renders_counter_mac_and_port_fields() {
+        let renderer = UnitDataRenderer::new(UnitDataTemplate {
+            address: 0x1270_0000,
+            size: 24,
+            fill_byte: 0xFF,
+            csv_path: None,
+            fields: vec![
+                field(
+                    "serial",
+                    0,
+                    4,
+                    UnitDataSource::Counter {
+                        start: 100,
+                        step: 1,
+                    },
+                    UnitDataEncoding::UintLe,
+                ),
+                field(
+                    "mac",
+                    4,
+                    6,
+                    UnitDataSource::Counter {
+                        start: 0xC0FF_EE00_0000,
+                        step: 2,
+                    },
+                    UnitDataEncoding::UintBe,
+                ),
+                field(
+                    "port",
+                    12,
+                    9,
+                    UnitDataSource::Port {
+                        field: UnitDataPortField::VidPid,
+                    },
+                    UnitDataEncoding::Ascii,
+                ),
+            ],
+            on_failure: SerialFailurePolicy::Burn,
+        })
+        .unwrap();
+
+        let image = renderer.render(3, &port(), None).unwrap();
+        assert_eq!(&image[0..4], &[103, 0, 0, 0]);
+        assert_eq!(&image[4..10], &[0xC0, 0xFF, 0xEE, 0x00, 0x00, 0x06]);
+        assert_eq!(&image[10..12], &[0xFF, 0xFF]);
+        assert_eq!(&image[12..21], b"1A86:55D3");
+        assert!(image[21..].iter().all(|b| *b == 0xFF));
+    }
+
+    #[test]
+    fn rejects_overlapping_fields_and_oversized_values() {
+        let overlapping = UnitDataTemplate {
+            address: 0x1270_0000,
+            size: 8,
+            fill_byte: 0xFF,
+            csv_path: None,
+            fields: vec![
+                field(
+                    "a",
+                    0,
+                    4,
+                    UnitDataSource::Constant { value: "1".into() },
+                    UnitDataEncoding::UintLe,
+                ),
+                field(
+                    "b",
+                    2,
+                    4,
+                    UnitDataSource::Constant { value: "2".into() },
+                    UnitDataEncoding::UintLe,
+                ),
+            ],
+            on_failure: SerialFailurePolicy::Burn,
+        };
+        assert!(UnitDataRenderer::new(overlapping).is_err());
+
+        let renderer = UnitDataRenderer::new(UnitDataTemplate {
+            address: 0x1270_0000,
+            size: 2,
+            fill_byte: 0xFF,
+            csv_path: None,
+            fields: vec![field(
+                "mac",
+                0,
+                2,
+                UnitDataSource::Constant {
+                    value: "AA:BB:CC".into(),
+                },
+                UnitDataEncoding::Hex,
+            )],
+            on_failure: SerialFailurePolicy::Burn,
+        })
+        .unwrap();
+        assert!(renderer.render(0, &port(), None).is_err());
+    }
+
+    #[test]
+    fn unit_index_allocation_reuses_released_until_exhausted() {
+        let mut allocator = UnitIndexAllocator::new(Some(3));
+        assert_eq!(allocator.allocate(), Ok(0));
+        assert_eq!(allocator.allocate(), Ok(1));
+
+        allocator.release(1);
+        assert_eq!(allocator.allocate(), Ok(1));
+        assert_eq!(allocator.allocate(), Ok(2));
+        assert!(allocator.allocate().is_err());
+
+        let mut unlimited = UnitIndexAllocator::new(None);
+        assert_eq!(unlimited.allocate(), Ok(0));
+        assert_eq!(unlimited.allocate(), Ok(1));
+    }
+}
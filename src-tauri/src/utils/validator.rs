@@ -43,13 +43,17 @@ pub fn validate_config_with_device(
         }
     }
 
-    // 检查write_flash命令
-    if config.write_flash.is_none() {
+    // 至少需要一个可执行的命令
+    if !config.has_any_command() {
         result.is_valid = false;
-        result
-            .errors
-            .push("配置文件中没有找到有效的write_flash命令".to_string());
-    } else if let Some(ref write_flash) = config.write_flash {
+        result.errors.push(
+            "配置文件中没有找到有效的 write_flash / read_flash / erase_flash / erase_region 命令"
+                .to_string(),
+        );
+    }
+
+    // 检查write_flash命令
+    if let Some(ref write_flash) = config.write_flash {
         if write_flash.files.is_empty() {
             result.is_valid = false;
             result
@@ -58,5 +62,103 @@ pub fn validate_config_with_device(
         }
     }
 
+    // 检查read_flash命令
+    if let Some(ref read_flash) = config.read_flash {
+        if read_flash.files.is_empty() {
+            result
+                .errors
+                .push("配置文件中read_flash命令的文件列表为空".to_string());
+        }
+        for file in &read_flash.files {
+            check_number(&mut result.errors, "read_flash", "address", &file.address);
+            check_number(&mut result.errors, "read_flash", "size", &file.size);
+        }
+    }
+
+    // 检查erase_flash命令
+    if let Some(ref erase_flash) = config.erase_flash {
+        check_number(
+            &mut result.errors,
+            "erase_flash",
+            "address",
+            &erase_flash.address,
+        );
+    }
+
+    // 检查erase_region命令
+    if let Some(ref erase_region) = config.erase_region {
+        if erase_region.regions.is_empty() {
+            result
+                .errors
+                .push("配置文件中erase_region命令的区域列表为空".to_string());
+        }
+        for region in &erase_region.regions {
+            check_number(
+                &mut result.errors,
+                "erase_region",
+                "address",
+                &region.address,
+            );
+            check_number(&mut result.errors, "erase_region", "size", &region.size);
+        }
+    }
+
+    if !result.errors.is_empty() {
+        result.is_valid = false;
+    }
+
     result
 }
+
+fn check_number(errors: &mut Vec<String>, command: &str, field: &str, raw: &str) {
+    if parse_number(raw).is_err() {
+        errors.push(format!("配置文件中{command}命令的 {field} 无效: {raw}"));
+    }
+}
+
+/// 解析十进制或 0x 前缀的十六进制数值
+pub fn parse_number(raw: &str) -> Result<u32, String> {
+    let trimmed = raw.trim();
+    let parsed = match trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+    {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => trimmed.parse::<u32>(),
+    };
+
+    parsed.map_err(|_| format!("无效的数值: {raw}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate_config_with_device;
+    use crate::types::SftoolParamConfig;
+
+    #[test]
+    fn accepts_read_and_erase_only_config() {
+        let config: SftoolParamConfig = serde_json::from_str(
+            r#"{
+                "chip": "SF32LB52",
+                "memory": "nor",
+                "read_flash": { "files": [{ "path": "cal.bin", "address": "0x12700000", "size": "0x1000" }] },
+                "erase_region": { "regions": [{ "address": "0x12710000", "size": "0x2000" }] }
+            }"#,
+        )
+        .unwrap();
+
+        let result = validate_config_with_device(&config, None, None);
+        assert!(result.is_valid, "{:?}", result.errors);
+    }
+
+    #[test]
+    fn rejects_invalid_numbers_and_empty_config() {
+        let config: SftoolParamConfig =
+            serde_json::from_str(r#"{ "chip": "SF32LB52", "erase_flash": { "address": "nvds" } }"#)
+                .unwrap();
+        assert!(!validate_config_with_device(&config, None, None).is_valid);
+
+        let config: SftoolParamConfig = serde_json::from_str(r#"{ "chip": "SF32LB52" }"#).unwrap();
+        assert!(!validate_config_with_device(&config, None, None).is_valid);
+    }
+}
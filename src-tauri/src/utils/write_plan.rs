@@ -0,0 +1,289 @@
+use crate::types::{parse_write_file, FileAddressRange, FileOverlap};
+use std::ops::Range;
+
+const MIB: u64 = 1024 * 1024;
+
+/// XIP 映射区，每个 MPI 控制器占 32 MB，从各自的起始地址映射一片 Flash
+const MPI_XIP_WINDOW: FlashWindow = FlashWindow {
+    range: 0x1000_0000..0x2000_0000,
+    aperture: 32 * MIB,
+};
+const MPI_EXT_WINDOW: FlashWindow = FlashWindow {
+    range: 0x6000_0000..0x7000_0000,
+    aperture: 256 * MIB,
+};
+
+/// 一段地址映射区，按 aperture 切分为各控制器的映射窗口
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlashWindow {
+    pub range: Range<u64>,
+    pub aperture: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlashLayout {
+    /// 写入地址必须落在某个映射窗口起始的 Flash 容量之内
+    Mapped(&'static [FlashWindow]),
+    /// 没有收录地址映射的存储器（如 SD），显式跳过地址范围检查
+    Unchecked,
+}
+
+/// 写入计划的地址范围检查依据
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FlashBounds {
+    /// 配置的 Flash 容量，None 时以映射窗口的大小为上限
+    pub flash_size: Option<u64>,
+    /// 命令行显式关闭地址范围检查，用于未收录的芯片/存储器组合
+    pub skip_check: bool,
+}
+
+/// 各芯片/存储器的地址映射；未收录的组合返回错误，需要显式关闭检查才能写入
+pub fn flash_layout(chip: &str, memory: &str) -> Result<FlashLayout, String> {
+    const NOR: &[FlashWindow] = &[MPI_XIP_WINDOW];
+    const NOR_WITH_EXT: &[FlashWindow] = &[MPI_XIP_WINDOW, MPI_EXT_WINDOW];
+    const NAND: &[FlashWindow] = &[MPI_EXT_WINDOW];
+
+    let chip = chip.trim().to_uppercase();
+    let memory = memory.trim().to_lowercase();
+
+    match (chip.as_str(), memory.as_str()) {
+        ("SF32LB52", "nor") | ("SF32LB57", "nor") => Ok(FlashLayout::Mapped(NOR)),
+        ("SF32LB55", "nor") | ("SF32LB56", "nor") | ("SF32LB58", "nor") => {
+            Ok(FlashLayout::Mapped(NOR_WITH_EXT))
+        }
+        ("SF32LB52", "nand") => Ok(FlashLayout::Mapped(NAND)),
+        ("SF32LB52", "sd") | ("SF32LB55", "sd") => Ok(FlashLayout::Unchecked),
+        _ => Err(format!(
+            "未收录 {chip} {memory} 的 Flash 地址范围，确认地址无误后可关闭地址范围检查"
+        )),
+    }
+}
+
+/// 将写入计划中的每个文件解析为地址范围
+///
+/// 与烧录使用同一个解析器 parse_write_file，HEX/ELF 按实际写入的段展开
+pub fn resolve_write_ranges<'a>(
+    files: impl IntoIterator<Item = (&'a str, u32)>,
+) -> Result<Vec<FileAddressRange>, String> {
+    let mut ranges = Vec::new();
+    for (file_path, address) in files {
+        for write_file in parse_write_file(file_path, address)? {
+            let length = write_file
+                .file
+                .metadata()
+                .map_err(|e| format!("读取文件 {} 失败: {}", file_path, e))?
+                .len();
+            if length == 0 {
+                continue;
+            }
+            let start = write_file.address as u64;
+            ranges.push(FileAddressRange {
+                file_path: file_path.to_string(),
+                start,
+                end: start + length,
+            });
+        }
+    }
+
+    Ok(ranges)
+}
+
+/// 找出属于不同文件且地址相交的范围对
+pub fn find_overlaps(ranges: &[FileAddressRange]) -> Vec<FileOverlap> {
+    let mut sorted: Vec<&FileAddressRange> = ranges.iter().collect();
+    sorted.sort_by_key(|range| (range.start, range.end));
+
+    let mut overlaps = Vec::new();
+    for (index, first) in sorted.iter().enumerate() {
+        for second in sorted.iter().skip(index + 1) {
+            if second.start >= first.end {
+                break;
+            }
+            if first.file_path != second.file_path {
+                overlaps.push(FileOverlap {
+                    first: (*first).clone(),
+                    second: (*second).clone(),
+                });
+            }
+        }
+    }
+
+    overlaps
+}
+
+/// 检查每个范围是否落在所在映射窗口起始的 flash_size 之内，返回超出的范围及原因
+pub fn find_out_of_window<'a>(
+    ranges: &'a [FileAddressRange],
+    windows: &[FlashWindow],
+    flash_size: Option<u64>,
+) -> Vec<(&'a FileAddressRange, String)> {
+    ranges
+        .iter()
+        .filter_map(|range| {
+            let Some(window) = windows
+                .iter()
+                .find(|window| window.range.contains(&range.start))
+            else {
+                return Some((range, "不在 Flash 映射区内".to_string()));
+            };
+            let base = window.range.start
+                + (range.start - window.range.start) / window.aperture * window.aperture;
+            let size = flash_size.map_or(window.aperture, |size| size.min(window.aperture));
+            (range.end > base + size).then(|| {
+                (
+                    range,
+                    format!("超出 0x{base:08X} 起 {} 的 Flash", describe_size(size)),
+                )
+            })
+        })
+        .collect()
+}
+
+fn describe_size(size: u64) -> String {
+    if size & (MIB - 1) == 0 {
+        format!("{} MB", size / MIB)
+    } else {
+        format!("{} KB", size / 1024)
+    }
+}
+
+pub(crate) fn describe_range(range: &FileAddressRange) -> String {
+    format!(
+        "{} [0x{:08X}, 0x{:08X})",
+        range.file_path, range.start, range.end
+    )
+}
+
+/// 写入前检查：文件间地址不能重叠，且必须落在所选芯片/存储器映射的 Flash 容量之内
+pub fn validate_write_plan<'a>(
+    files: impl IntoIterator<Item = (&'a str, u32)>,
+    chip: &str,
+    memory: &str,
+    bounds: FlashBounds,
+) -> Result<Vec<FileAddressRange>, String> {
+    let ranges = resolve_write_ranges(files)?;
+
+    let overlaps = find_overlaps(&ranges);
+    if !overlaps.is_empty() {
+        let pairs = overlaps
+            .iter()
+            .map(|overlap| {
+                format!(
+                    "{} 与 {}",
+                    describe_range(&overlap.first),
+                    describe_range(&overlap.second)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+        return Err(format!("烧录地址重叠: {pairs}"));
+    }
+
+    if bounds.skip_check {
+        tracing::warn!("已关闭 {chip} {memory} 的地址范围检查");
+        return Ok(ranges);
+    }
+    let FlashLayout::Mapped(windows) = flash_layout(chip, memory)? else {
+        return Ok(ranges);
+    };
+
+    let outside = find_out_of_window(&ranges, windows, bounds.flash_size);
+    if !outside.is_empty() {
+        let files = outside
+            .iter()
+            .map(|(range, reason)| format!("{} {reason}", describe_range(range)))
+            .collect::<Vec<_>>()
+            .join("; ");
+        return Err(format!(
+            "烧录地址超出 {chip} {memory} 的 Flash 范围: {files}"
+        ));
+    }
+
+    Ok(ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_out_of_window, find_overlaps, flash_layout, FlashLayout, MIB};
+    use crate::types::FileAddressRange;
+
+    fn range(file_path: &str, start: u64, end: u64) -> FileAddressRange {
+        FileAddressRange {
+            file_path: file_path.to_string(),
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn reports_overlapping_file_pairs() {
+        let ranges = vec![
+            range("app.bin", 0x1202_0000, 0x1210_0000),
+            range("bootloader.bin", 0x1201_0000, 0x1202_1000),
+            range("ftab.bin", 0x1200_0000, 0x1200_2000),
+            range("app.elf", 0x1220_0000, 0x1221_0000),
+            range("app.elf", 0x1221_0000, 0x1222_0000),
+        ];
+
+        let overlaps = find_overlaps(&ranges);
+        assert_eq!(overlaps.len(), 1);
+        assert_eq!(overlaps[0].first.file_path, "bootloader.bin");
+        assert_eq!(overlaps[0].second.file_path, "app.bin");
+    }
+
+    #[test]
+    fn adjacent_ranges_do_not_overlap() {
+        let ranges = vec![
+            range("a.bin", 0x1000_0000, 0x1000_1000),
+            range("b.bin", 0x1000_1000, 0x1000_2000),
+        ];
+        assert!(find_overlaps(&ranges).is_empty());
+    }
+
+    #[test]
+    fn rejects_ranges_outside_flash_window() {
+        let FlashLayout::Mapped(windows) = flash_layout("sf32lb52", "NOR").unwrap() else {
+            panic!("expected mapped layout");
+        };
+        let ranges = vec![
+            range("ok.bin", 0x1200_0000, 0x1200_1000),
+            range("ram.bin", 0x2000_0000, 0x2000_1000),
+            range("tail.bin", 0x1FFF_F000, 0x2000_1000),
+            range("cross.bin", 0x11FF_F000, 0x1200_1000),
+        ];
+
+        let outside = find_out_of_window(&ranges, windows, None);
+        let names: Vec<_> = outside
+            .iter()
+            .map(|(range, _)| range.file_path.as_str())
+            .collect();
+        assert_eq!(names, ["ram.bin", "tail.bin", "cross.bin"]);
+    }
+
+    #[test]
+    fn bounds_ranges_by_configured_flash_size() {
+        let FlashLayout::Mapped(windows) = flash_layout("SF32LB52", "nor").unwrap() else {
+            panic!("expected mapped layout");
+        };
+        let ranges = vec![
+            range("app.bin", 0x1202_0000, 0x1240_0000),
+            range("fs.bin", 0x1240_0000, 0x1240_1000),
+        ];
+
+        let outside = find_out_of_window(&ranges, windows, Some(4 * MIB));
+        assert_eq!(outside.len(), 1);
+        assert_eq!(outside[0].0.file_path, "fs.bin");
+        assert!(outside[0].1.contains("0x12000000 起 4 MB"));
+        assert!(find_out_of_window(&ranges, windows, Some(8 * MIB)).is_empty());
+    }
+
+    #[test]
+    fn unknown_layouts_are_errors_and_sd_is_unchecked() {
+        assert_eq!(
+            flash_layout("SF32LB52", "sd").unwrap(),
+            FlashLayout::Unchecked
+        );
+        assert!(flash_layout("SF32LB56", "nand").is_err());
+        assert!(flash_layout("SF32LB99", "nor").is_err());
+    }
+}
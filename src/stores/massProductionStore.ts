@@ -8,6 +8,7 @@ import type {
   MassProductionPortInfo,
   MassProductionPortStatus,
   MassProductionProgressEvent,
+  MassProductionFrozenFile,
   MassProductionSnapshot,
   MassProductionStartRequest,
   MassProductionLogPaths,
@@ -133,6 +134,8 @@ export const useMassProductionStore = defineStore('massProduction', () => {
   const startedAt = ref<number | null>(null);
   const endedAt = ref<number | null>(null);
   const manualStopped = ref(false);
+  const frozenFiles = ref<MassProductionFrozenFile[]>([]);
+  const abortReason = ref<string | null>(null);
   const chipModel = ref<string | null>(null);
   const memoryType = ref<string | null>(null);
 
@@ -326,6 +329,8 @@ export const useMassProductionStore = defineStore('massProduction', () => {
     startedAt.value = snapshot.started_at ?? null;
     endedAt.value = snapshot.ended_at ?? null;
     manualStopped.value = snapshot.manual_stopped;
+    frozenFiles.value = snapshot.frozen_files || [];
+    abortReason.value = snapshot.abort_reason ?? null;
     chipModel.value = snapshot.chip_model ?? null;
     memoryType.value = snapshot.memory_type ?? null;
 
@@ -618,6 +623,8 @@ export const useMassProductionStore = defineStore('massProduction', () => {
     startedAt,
     endedAt,
     manualStopped,
+    frozenFiles,
+    abortReason,
     chipModel,
     memoryType,
     autoDownload,
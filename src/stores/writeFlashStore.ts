@@ -61,7 +61,7 @@ export const useWriteFlashStore = defineStore('writeFlash', () => {
   const isSupportedFile = (fileName: string): boolean => {
     const extension = fileName.toLowerCase().substring(fileName.lastIndexOf('.'));
     const SUPPORTED_EXTENSIONS = ['.bin', '.hex', '.elf', '.axf'];
-    const ARCHIVE_EXTENSIONS = ['.zip', '.rar', '.7z', '.tar', '.gz', '.xz', '.bz2', '.tgz', '.tbz', '.tbz2'];
+    const ARCHIVE_EXTENSIONS = ['.zip', '.tar', '.gz', '.bz2', '.tgz', '.tbz', '.tbz2'];
 
     // 检查是否是sftool配置文件
     if (fileName.toLowerCase().includes('sftool_param.json')) {
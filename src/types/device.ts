@@ -58,3 +58,34 @@ export const findMatchingPort = (selectedPort: PortInfo | null, ports: PortInfo[
   const matchedByUsbIdentity = ports.filter(port => usbIdentityKey(port.usb_info) === selectedUsbIdentity);
   return matchedByUsbIdentity.length === 1 ? matchedByUsbIdentity[0] : null;
 };
+
+/** 烧录失败的类别，用于统计失败原因 */
+export type FlashErrorCategory =
+  | 'port_open'
+  | 'sync'
+  | 'stub_download'
+  | 'erase'
+  | 'write'
+  | 'verify_mismatch'
+  | 'cancelled'
+  | 'panic'
+  | 'disconnect'
+  | 'config'
+  | 'other';
+
+/** 单设备写入/读取/擦除命令的错误，用户取消时 kind 为 cancelled，已有操作进行中时为 busy */
+export interface OperationError {
+  kind: 'cancelled' | 'busy' | 'failed';
+  message: string;
+  /** busy 时为正在执行的操作名称 */
+  operation?: string;
+  /** failed 时的失败类别 */
+  category?: FlashErrorCategory;
+}
+
+/** peek_flash 读取到内存的一段 Flash，data 为 base64 编码 */
+export interface PeekFlashResult {
+  address: number;
+  size: number;
+  data: string;
+}
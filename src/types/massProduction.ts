@@ -1,3 +1,4 @@
+import type { FlashErrorCategory } from './device';
 import type { ProgressEvent } from './progress';
 
 export type MassProductionFilterField = 'vid_pid' | 'serial_number' | 'location_path' | 'port_name';
@@ -19,9 +20,86 @@ export type MassProductionPortStatus =
   | 'filtered'
   | 'disconnected';
 
+/** write 为正常烧录；verify 只回读校验设备上已有的固件 */
+export type MassProductionMode = 'write' | 'verify';
+
 export interface MassProductionWriteFileInfo {
   address: number;
   file_path: string;
+  /** 期望的 SHA-256 或 CRC32，不一致时拒绝启动 */
+  expected_hash?: string;
+}
+
+export type UnitDataSource =
+  | { type: 'counter'; start?: number; step?: number }
+  | { type: 'csv_column'; column: string }
+  | { type: 'port'; field: 'port_name' | 'serial_number' | 'location_path' | 'vid_pid' }
+  | { type: 'constant'; value: string }
+  | { type: 'serial' };
+
+export type UnitDataEncoding = 'ascii' | 'hex' | 'uint_le' | 'uint_be';
+
+export interface UnitDataField {
+  name: string;
+  /** 相对模板起始地址的偏移 */
+  offset: number;
+  size: number;
+  source: UnitDataSource;
+  encoding: UnitDataEncoding;
+}
+
+/** 每台设备渲染一份的出厂数据镜像 */
+export interface UnitDataTemplate {
+  address: number;
+  size: number;
+  fill_byte?: number;
+  csv_path?: string;
+  fields: UnitDataField[];
+  /** 设备失败时已分配的数据行如何处理，含义与序列号池相同；重试的端口始终沿用原数据行 */
+  on_failure?: SerialFailurePolicy;
+}
+
+/** 号段 [start, end]，序列号为 prefix + 补零到 width 位的十进制数 */
+export interface SerialRange {
+  prefix?: string;
+  width?: number;
+  start: number;
+  end: number;
+}
+
+/** release 归还到池中，仅对开始写入前的失败生效；burn 作废，永不再分配 */
+export type SerialFailurePolicy = 'release' | 'burn';
+
+export interface SerialPoolImportRequest {
+  name: string;
+  ranges?: SerialRange[];
+  values?: string[];
+  /** 每行一个序列号的文本文件 */
+  list_path?: string;
+}
+
+export interface SerialPoolStatus {
+  name: string;
+  remaining: number;
+  reserved: number;
+  committed: number;
+  burned: number;
+  released: number;
+}
+
+export interface MassProductionSerialConfig {
+  pool: string;
+  on_failure?: SerialFailurePolicy;
+}
+
+/** 失败设备的自动重试策略，重试在原端口上进行 */
+export interface MassProductionRetryPolicy {
+  /** 每台设备最多尝试的次数，包括第一次，默认 3 */
+  max_attempts?: number;
+  backoff_ms?: number;
+  max_backoff_ms?: number;
+  retry_on?: FlashErrorCategory[];
+  retry_baud_rate?: number;
 }
 
 export interface MassProductionStartRequest {
@@ -33,6 +111,11 @@ export interface MassProductionStartRequest {
   before_operation: string;
   after_operation: string;
   files: MassProductionWriteFileInfo[];
+  mode?: MassProductionMode;
+  unit_data?: UnitDataTemplate;
+  serial?: MassProductionSerialConfig;
+  retry?: MassProductionRetryPolicy;
+  skip_address_check?: boolean;
   verify: boolean;
   no_compress: boolean;
   erase_all: boolean;
@@ -59,17 +142,39 @@ export interface MassProductionPortInfo {
   last_seen_at: number;
   task_started_at?: number | null;
   task_finished_at?: number | null;
+  /** 最近一次失败的类别，成功或取消时为空 */
+  error_category?: FlashErrorCategory | null;
+  /** 当前或最近一次尝试的序号，从 1 开始 */
+  attempt?: number;
+}
+
+/** 量产启动时锁定的固件文件 */
+export interface MassProductionFrozenFile {
+  file_path: string;
+  frozen_path: string;
+  address: number;
+  size: number;
+  crc32: string;
+  sha256: string;
+}
+
+export interface FlashErrorCount {
+  category: FlashErrorCategory;
+  count: number;
 }
 
 export interface MassProductionSnapshot {
   is_running: boolean;
   is_enabled: boolean;
   session_id: number;
+  /** 会话的持久标识，可用于查询追溯记录 */
+  session_key?: string;
   started_at?: number | null;
   ended_at?: number | null;
   manual_stopped: boolean;
   chip_model?: string | null;
   memory_type?: string | null;
+  mode: MassProductionMode;
   auto_download: boolean;
   max_concurrency: number;
   queued_count: number;
@@ -79,9 +184,17 @@ export interface MassProductionSnapshot {
   failed_count: number;
   total_count: number;
   ports: MassProductionPortInfo[];
+  frozen_files: MassProductionFrozenFile[];
+  abort_reason?: string | null;
+  serial_pool?: SerialPoolStatus | null;
+  /** 各失败类别的次数，按次数从多到少排列；每次失败的尝试都计入，包括随后重试成功的 */
+  failure_counts?: FlashErrorCount[];
+  /** 已安排的自动重试次数，这些失败不计入 failed_count */
+  retry_count?: number;
 }
 
 export interface MassProductionProgressEvent {
+  session_id: number;
   port_name: string;
   event: ProgressEvent;
 }
@@ -0,0 +1,9 @@
+/** SDK 分区表（ptab.json）中的一个区域，address 为存储器基地址加偏移 */
+export interface PartitionInfo {
+  /** 第一个 tag，没有 tag 时为 "<mem>@<address>" */
+  name: string;
+  tags: string[];
+  memory: string;
+  address: number;
+  size: number;
+}
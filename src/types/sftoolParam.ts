@@ -73,11 +73,41 @@ export interface ConfigValidationResult {
  */
 export interface ExtractedFile {
   path: string;
+  /** ELF/HEX 文件未指定地址时为空，由文件自身决定 */
   address: string;
   name: string;
   size: number;
 }
 
+/**
+ * 解压失败原因，entry 为被拒绝的压缩包条目
+ */
+export interface ArchiveExtractError {
+  kind: 'not_found' | 'path_traversal' | 'symlink' | 'unsupported_format' | 'too_many_entries' | 'too_large' | 'io';
+  entry?: string | null;
+  message: string;
+}
+
+/**
+ * extract_archive 的返回结果，识别到烧录配置时附带解析结果
+ */
+export interface ArchiveExtractResult {
+  files: ExtractedFile[];
+  recipeSource?: 'sftool_param' | 'package_manifest' | 'sdk_build';
+  recipePath?: string;
+  recipe?: SftoolParamParseResult;
+  recipeError?: string;
+}
+
+/**
+ * read_flash 中的读取任务，路径已按配置文件所在目录解析
+ */
+export interface ExtractedReadFile {
+  path: string;
+  address: string;
+  size: string;
+}
+
 /**
  * 解析结果
  */
@@ -85,4 +115,6 @@ export interface SftoolParamParseResult {
   config: SftoolParamConfig;
   validation: ConfigValidationResult;
   extractedFiles: ExtractedFile[];
+  readFiles: ExtractedReadFile[];
+  stubConfigPath?: string;
 }
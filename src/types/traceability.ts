@@ -0,0 +1,53 @@
+import type { FlashErrorCategory } from './device';
+import type { MassProductionMode } from './massProduction';
+
+export type TraceResult = 'success' | 'failed' | 'cancelled';
+
+export interface TraceFirmwareFile {
+  file_path: string;
+  address: number;
+  sha256: string;
+}
+
+/** 一次烧录尝试的追溯记录 */
+export interface TraceRecord {
+  session_id: number;
+  /** 会话的持久标识，session_id 每次启动程序都会重新计数 */
+  session_key?: string;
+  port_name: string;
+  attempt?: number;
+  vid?: string | null;
+  pid?: string | null;
+  usb_serial?: string | null;
+  location_path?: string | null;
+  chip_model: string;
+  memory_type: string;
+  mode: MassProductionMode;
+  firmware: TraceFirmwareFile[];
+  serial?: string | null;
+  started_at: number;
+  finished_at: number;
+  result: TraceResult;
+  error_category?: FlashErrorCategory | null;
+  error?: string | null;
+}
+
+/** 查询结果；格式错误的行会被跳过 */
+export interface TraceQueryResult {
+  records: TraceRecord[];
+  /** 被跳过的行号，从 1 开始 */
+  skipped_lines: number[];
+}
+
+export interface TraceQuery {
+  session_key?: string;
+  /** 匹配端口名、USB 序列号或设备序列号 */
+  device?: string;
+  result?: TraceResult;
+  since?: number;
+  until?: number;
+  /** 只返回最新的 limit 条 */
+  limit?: number;
+}
+
+export type TraceExportFormat = 'csv' | 'json';
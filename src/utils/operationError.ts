@@ -0,0 +1,22 @@
+import type { OperationError } from '../types/device';
+
+const asOperationError = (error: unknown): Partial<OperationError> | null =>
+  error && typeof error === 'object' ? (error as Partial<OperationError>) : null;
+
+/**
+ * write_flash / read_flash / erase 等命令被 cancel_current_operation 中止
+ */
+export function isOperationCancelled(error: unknown): boolean {
+  return asOperationError(error)?.kind === 'cancelled';
+}
+
+/**
+ * 操作命令返回结构化错误，其余情况按原样显示
+ */
+export function formatOperationError(error: unknown): string {
+  const operationError = asOperationError(error);
+  if (operationError && typeof operationError.message === 'string') {
+    return operationError.message;
+  }
+  return String(error);
+}
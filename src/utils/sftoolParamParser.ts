@@ -3,7 +3,12 @@
  */
 
 import { invoke } from '@tauri-apps/api/core';
-import type { SftoolParamConfig, ConfigValidationResult, SftoolParamParseResult } from '../types/sftoolParam';
+import type {
+  ArchiveExtractError,
+  SftoolParamConfig,
+  ConfigValidationResult,
+  SftoolParamParseResult,
+} from '../types/sftoolParam';
 import type { ChipModel } from '../config/chips';
 
 /**
@@ -27,6 +32,13 @@ export async function parseSftoolParamFile(
   }
 }
 
+/**
+ * 配置文件中是否包含读取或擦除任务
+ */
+export function hasReadOrEraseCommand(config: SftoolParamConfig): boolean {
+  return !!(config.read_flash || config.erase_flash || config.erase_region);
+}
+
 /**
  * 验证配置文件与当前设备设置的兼容性
  */
@@ -62,10 +74,13 @@ export function validateConfigWithDevice(
     result.errors.push(`存储器类型不匹配: 当前设备 ${currentMemory}，配置文件 ${config.memory || 'nor'}`);
   }
 
-  // 检查是否有write_flash命令
-  if (!config.write_flash || !config.write_flash.files || config.write_flash.files.length === 0) {
+  // 至少需要一个可执行的命令，write_flash 存在时文件列表不能为空
+  if (!config.write_flash && !hasReadOrEraseCommand(config)) {
+    result.isValid = false;
+    result.errors.push('配置文件中没有找到有效的 write_flash / read_flash / erase_flash / erase_region 命令');
+  } else if (config.write_flash && (!config.write_flash.files || config.write_flash.files.length === 0)) {
     result.isValid = false;
-    result.errors.push('配置文件中没有找到有效的write_flash命令或文件列表为空');
+    result.errors.push('配置文件中write_flash命令的文件列表为空');
   }
 
   return result;
@@ -78,6 +93,17 @@ export function isSftoolParamFile(fileName: string): boolean {
   return fileName.toLowerCase().endsWith('sftool_param.json') || fileName.toLowerCase() === 'sftool_param.json';
 }
 
+/**
+ * extract_archive 返回结构化错误，其余情况按原样显示
+ */
+export function formatArchiveError(error: unknown): string {
+  const archiveError = error as Partial<ArchiveExtractError> | null;
+  if (archiveError && typeof archiveError === 'object' && typeof archiveError.message === 'string') {
+    return archiveError.message;
+  }
+  return String(error);
+}
+
 /**
  * 格式化验证错误信息为用户友好的文本
  */